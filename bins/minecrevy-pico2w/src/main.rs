@@ -5,7 +5,7 @@ use core::str::FromStr;
 
 use cyw43::JoinOptions;
 use cyw43_pio::{DEFAULT_CLOCK_DIVIDER, PioSpi};
-use defmt::{Format, debug, error, info, unwrap, warn};
+use defmt::{Debug2Format, Format, debug, error, info, unwrap, warn};
 use defmt_rtt as _;
 use embassy_executor::Spawner;
 use embassy_net::{Config, DhcpConfig, StackResources, tcp::TcpSocket};
@@ -17,13 +17,18 @@ use embassy_rp::{
     trng::Trng,
 };
 use embassy_time::{Duration, Timer};
-use embedded_io_async::ReadExactError;
 use heapless::String;
 use minecrevy_encdec::{
-    AsyncDecode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, WritePacketError,
+    AsyncDecode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, Compression, WritePacketError,
 };
-use minecrevy_protocol::r770::{
-    Handshake, NextState, StatusPing, StatusRequest, StatusResponseSimple,
+use minecrevy_net::{answer_legacy_ping, read_handshake, read_legacy_ping};
+use minecrevy_protocol::{
+    json::JsonError,
+    r770::{
+        AsyncDecodeStatusPingError, AsyncEncodeStatusPingError, NextState, StatusPing,
+        StatusRequest, StatusResponse, StatusResponsePlayers, Version,
+    },
+    text::Text,
 };
 use panic_halt as _;
 use static_cell::StaticCell;
@@ -147,14 +152,27 @@ async fn main(spawner: Spawner) {
 
         info!("Received connection from {:?}", socket.remote_endpoint());
 
-        let Ok(handshake) = socket
-            .read_packet(async move |id, mut reader| {
-                assert_eq!(id, 0x00);
-                Handshake::decode(&mut reader, ()).await
-            })
-            .await
-        else {
-            continue;
+        match read_legacy_ping(&mut socket).await {
+            Ok(true) => {
+                debug!("Received legacy server list ping");
+                if let Err(e) = answer_legacy_ping(socket, &STATUS_RESPONSE).await {
+                    warn!("Error writing legacy ping response: {:?}", e);
+                }
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                warn!("handshake error: {:?}", e);
+                continue;
+            }
+        }
+
+        let handshake = match read_handshake(&mut socket).await {
+            Ok(handshake) => handshake,
+            Err(e) => {
+                warn!("handshake error: {:?}", Debug2Format(&e));
+                continue;
+            }
         };
 
         if handshake.protocol_version == 770 {
@@ -182,35 +200,33 @@ async fn main(spawner: Spawner) {
     }
 }
 
-// const STATUS_RESPONSE: StatusResponse<'static> = StatusResponse {
-//     version: minecrevy_protocol::r770::Version::V1_21_5,
-//     players: StatusResponsePlayers { max: 0, online: 0 },
-//     description: "Hello, world!",
-//     enforces_secure_chat: false,
-// };
-
-const STATUS_RESPONSE_SIMPLE: StatusResponseSimple<'static> = StatusResponseSimple(
-    r#"{"version":{"name":"1.21.5","protocol":770},"players":{"max":0,"online":0},"description":"Hello, world from a RPi Pico 2W!","enforcesSecureChat":false}"#,
-);
+const STATUS_RESPONSE: StatusResponse<'static> = StatusResponse {
+    version: Version::V1_21_5,
+    players: StatusResponsePlayers {
+        max: 0,
+        online: 0,
+        sample: &[],
+    },
+    description: Text::literal("Hello, world from a RPi Pico 2W!"),
+    favicon: None,
+    enforces_secure_chat: false,
+    prevents_chat_reports: false,
+};
 
 async fn handle_status_packets(mut socket: TcpSocket<'_>) {
     loop {
         let (mut reader, mut writer) = socket.split();
 
         if let Err(e) = reader
-            .read_packet(async move |id, mut reader| {
+            .read_packet(Compression::Disabled, async move |id, mut reader| {
                 match id {
                     0x00 => {
                         let Ok(request) = StatusRequest::decode(&mut reader, ()).await;
                         debug!("Received status request: {:?}", request);
                         writer
-                            .write_packet(0x00, STATUS_RESPONSE_SIMPLE, ())
+                            .write_packet(Compression::Disabled, 0x00, STATUS_RESPONSE, ())
                             .await
                             .map_err(StatusPacketError::Response)?;
-                        // writer
-                        //     .write_packet(0x00, STATUS_RESPONSE, Default::default())
-                        //     .await
-                        //     .expect("failed to write response");
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
                         Ok(())
                     }
@@ -220,7 +236,7 @@ async fn handle_status_packets(mut socket: TcpSocket<'_>) {
                             .map_err(StatusPacketError::Ping)?;
                         debug!("Received status ping: {:?}", ping);
                         writer
-                            .write_packet(0x01, ping, ())
+                            .write_packet(Compression::Disabled, 0x01, ping, ())
                             .await
                             .map_err(StatusPacketError::Pong)?;
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
@@ -240,11 +256,11 @@ async fn handle_status_packets(mut socket: TcpSocket<'_>) {
 #[derive(Format, Error, Debug)]
 pub enum StatusPacketError<E> {
     #[error("failed to read status ping: {0}")]
-    Ping(ReadExactError<E>),
+    Ping(AsyncDecodeStatusPingError<E>),
     #[error("failed to write status pong: {0}")]
-    Pong(WritePacketError<E, E>),
+    Pong(WritePacketError<E, AsyncEncodeStatusPingError<E>>),
     #[error("failed to write status response: {0}")]
-    Response(WritePacketError<E, E>),
+    Response(WritePacketError<E, JsonError<E>>),
     #[error("failed to flush write buffer: {0}")]
     Flush(E),
     #[error("unknown packet ID: 0x{0:02X}")]
@@ -2,14 +2,18 @@ use embassy_executor::Spawner;
 use embassy_net::{Config, Ipv4Cidr, StackResources, StaticConfigV4, tcp::TcpSocket};
 use embassy_net_tuntap::TunTapDevice;
 use embassy_time::Duration;
-use embedded_byteorder::ReadExactError;
 use heapless::Vec;
 use minecrevy_encdec::{
-    AsyncDecode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, WritePacketError,
+    AsyncDecode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, Compression, WritePacketError,
 };
-use minecrevy_protocol::r770::{
-    Handshake, NextState, StatusPing, StatusRequest, StatusResponse, StatusResponsePlayers,
-    StatusResponseSimple,
+use minecrevy_net::{answer_legacy_ping, read_handshake, read_legacy_ping};
+use minecrevy_protocol::{
+    json::JsonError,
+    r770::{
+        AsyncDecodeStatusPingError, AsyncEncodeStatusPingError, NextState, StatusPing,
+        StatusRequest, StatusResponse, StatusResponsePlayers, Version,
+    },
+    text::Text,
 };
 use rand::RngCore;
 use static_cell::StaticCell;
@@ -58,18 +62,27 @@ async fn main(spawner: Spawner) {
 
         println!("Received connection from {:?}", socket.remote_endpoint());
 
-        let Ok(handshake): Result<
-            Handshake,
-            minecrevy_encdec::ReadPacketError<embassy_net::tcp::Error, _>,
-        > = socket
-            .read_packet(async move |id, mut reader| {
-                assert_eq!(id, 0x00);
-                Handshake::decode(&mut reader, ()).await
-            })
-            .await
-        else {
-            println!("handshake error");
-            continue;
+        match read_legacy_ping(&mut socket).await {
+            Ok(true) => {
+                println!("Received legacy server list ping");
+                if let Err(e) = answer_legacy_ping(socket, &STATUS_RESPONSE).await {
+                    println!("Error writing legacy ping response: {:?}", e);
+                }
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                println!("handshake error: {:?}", e);
+                continue;
+            }
+        }
+
+        let handshake = match read_handshake(&mut socket).await {
+            Ok(handshake) => handshake,
+            Err(e) => {
+                println!("handshake error: {:?}", e);
+                continue;
+            }
         };
 
         if handshake.protocol_version == 770 {
@@ -95,35 +108,33 @@ async fn main(spawner: Spawner) {
 }
 
 const STATUS_RESPONSE: StatusResponse<'static> = StatusResponse {
-    version: minecrevy_protocol::r770::Version::V1_21_5,
-    players: StatusResponsePlayers { max: 0, online: 0 },
-    description: "Hello, world!",
+    version: Version::V1_21_5,
+    players: StatusResponsePlayers {
+        max: 0,
+        online: 0,
+        sample: &[],
+    },
+    description: Text::literal("Hello, world!"),
+    favicon: None,
     enforces_secure_chat: false,
+    prevents_chat_reports: false,
 };
 
-const STATUS_RESPONSE_SIMPLE: StatusResponseSimple<'static> = StatusResponseSimple(
-    r#"{"version":{"name":"1.21.5","protocol":770},"players":{"max":0,"online":0},"description":"Hello, world!","enforcesSecureChat":false}"#,
-);
-
 async fn handle_status_packets(mut socket: TcpSocket<'_>) {
     loop {
         let (mut reader, mut writer) = socket.split();
 
         if let Err(e) = reader
-            .read_packet(async move |id, mut reader| {
+            .read_packet(Compression::Disabled, async move |id, mut reader| {
                 match id {
                     0x00 => {
                         let Ok(request) = StatusRequest::decode(&mut reader, ()).await;
                         println!("Received status request: {request:?}");
                         writer
-                            .write_packet(0x00, STATUS_RESPONSE_SIMPLE, ())
+                            .write_packet(Compression::Disabled, 0x00, STATUS_RESPONSE, ())
                             .await
                             .map_err(StatusPacketError::Response)?;
                         println!("Flushing response...");
-                        // writer
-                        //     .write_packet(0x00, STATUS_RESPONSE, Default::default())
-                        //     .await
-                        //     .expect("failed to write response");
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
                         Ok(())
                     }
@@ -133,7 +144,7 @@ async fn handle_status_packets(mut socket: TcpSocket<'_>) {
                             .map_err(StatusPacketError::Ping)?;
                         println!("Received status ping: {ping:?}");
                         writer
-                            .write_packet(0x01, ping, ())
+                            .write_packet(Compression::Disabled, 0x01, ping, ())
                             .await
                             .map_err(StatusPacketError::Pong)?;
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
@@ -153,11 +164,11 @@ async fn handle_status_packets(mut socket: TcpSocket<'_>) {
 #[derive(Error, Debug)]
 pub enum StatusPacketError<E> {
     #[error("failed to read status ping: {0}")]
-    Ping(ReadExactError<E>),
+    Ping(AsyncDecodeStatusPingError<E>),
     #[error("failed to write status pong: {0}")]
-    Pong(WritePacketError<E, E>),
+    Pong(WritePacketError<E, AsyncEncodeStatusPingError<E>>),
     #[error("failed to write status response: {0}")]
-    Response(WritePacketError<E, E>),
+    Response(WritePacketError<E, JsonError<E>>),
     #[error("failed to flush write buffer: {0}")]
     Flush(E),
     #[error("unknown packet ID: 0x{0:02X}")]
@@ -0,0 +1,270 @@
+//! Just enough fixed-size unsigned big integer arithmetic for RSA.
+//!
+//! Numbers are little-endian slices of 32-bit limbs, so the same code serves
+//! both the 512-bit primes and the 1024-bit modulus without an allocator.
+
+use core::cmp::Ordering;
+
+/// The number of limbs in the largest number, a 1024-bit modulus.
+pub const LIMBS: usize = 32;
+
+pub type Limbs = [u32; LIMBS];
+
+/// Reads a big-endian byte string into `out`, which must be large enough.
+pub fn from_be_bytes(out: &mut [u32], bytes: &[u8]) {
+    out.fill(0);
+    for (i, chunk) in bytes.rchunks(4).enumerate() {
+        let mut word = [0; 4];
+        word[4 - chunk.len()..].copy_from_slice(chunk);
+        out[i] = u32::from_be_bytes(word);
+    }
+}
+
+/// Writes `value` as a big-endian byte string that fills `out`.
+pub fn to_be_bytes(value: &[u32], out: &mut [u8]) {
+    for (chunk, limb) in out.rchunks_mut(4).zip(value) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&limb.to_be_bytes()[4 - len..]);
+    }
+}
+
+pub fn cmp(a: &[u32], b: &[u32]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+pub fn bit(a: &[u32], i: usize) -> bool {
+    (a[i / 32] >> (i % 32)) & 1 == 1
+}
+
+/// The number of significant bits.
+pub fn bits(a: &[u32]) -> usize {
+    match a.iter().rposition(|&limb| limb != 0) {
+        Some(i) => i * 32 + 32 - a[i].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// `a += b`, returning the carry.
+pub fn add(a: &mut [u32], b: &[u32]) -> bool {
+    let mut carry = false;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let (s, c1) = limb.overflowing_add(b.get(i).copied().unwrap_or(0));
+        let (s, c2) = s.overflowing_add(u32::from(carry));
+        *limb = s;
+        carry = c1 || c2;
+    }
+    carry
+}
+
+/// `a -= b`, returning the borrow.
+pub fn sub(a: &mut [u32], b: &[u32]) -> bool {
+    let mut borrow = false;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let (d, b1) = limb.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (d, b2) = d.overflowing_sub(u32::from(borrow));
+        *limb = d;
+        borrow = b1 || b2;
+    }
+    borrow
+}
+
+/// `a += small`, returning the carry.
+pub fn add_small(a: &mut [u32], small: u32) -> bool {
+    let mut carry = small;
+    for limb in a {
+        let (s, overflow) = limb.overflowing_add(carry);
+        *limb = s;
+        carry = u32::from(overflow);
+        if carry == 0 {
+            break;
+        }
+    }
+    carry != 0
+}
+
+/// `a *= small`, returning the limb carried out.
+pub fn mul_small(a: &mut [u32], small: u32) -> u32 {
+    let mut carry = 0;
+    for limb in a {
+        let product = u64::from(*limb) * u64::from(small) + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    carry as u32
+}
+
+/// `a /= small`, returning the remainder.
+pub fn div_small(a: &mut [u32], small: u32) -> u32 {
+    let mut rem = 0u64;
+    for limb in a.iter_mut().rev() {
+        let value = (rem << 32) | u64::from(*limb);
+        *limb = (value / u64::from(small)) as u32;
+        rem = value % u64::from(small);
+    }
+    rem as u32
+}
+
+pub fn rem_small(a: &[u32], small: u32) -> u32 {
+    a.iter().rev().fold(0u64, |rem, &limb| {
+        ((rem << 32) | u64::from(limb)) % u64::from(small)
+    }) as u32
+}
+
+/// `out = a * b`, where `out` holds `a.len() + b.len()` limbs.
+pub fn mul(out: &mut [u32], a: &[u32], b: &[u32]) {
+    out.fill(0);
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = u64::from(out[i + j]) + u64::from(x) * u64::from(y) + carry;
+            out[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+}
+
+/// Montgomery arithmetic modulo an odd number of `k` limbs, which keeps
+/// modular multiplication free of division.
+pub struct Montgomery<'m> {
+    modulus: &'m [u32],
+    /// `-modulus⁻¹ mod 2³²`.
+    inv: u32,
+    /// `R² mod modulus`, where `R = 2^(32k)`.
+    r2: Limbs,
+}
+
+impl<'m> Montgomery<'m> {
+    pub fn new(modulus: &'m [u32]) -> Self {
+        debug_assert!(modulus[0] & 1 == 1, "modulus must be odd");
+
+        // Newton's iteration doubles the number of correct low bits each time.
+        let mut inv = 1u32;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u32.wrapping_sub(modulus[0].wrapping_mul(inv)));
+        }
+
+        // Double 1 until it becomes R² = 2^(64k), reducing as we go.
+        let k = modulus.len();
+        let mut r2 = [0; LIMBS];
+        r2[0] = 1;
+        for _ in 0..64 * k {
+            let carry = r2[k - 1] >> 31;
+            for i in (1..k).rev() {
+                r2[i] = (r2[i] << 1) | (r2[i - 1] >> 31);
+            }
+            r2[0] <<= 1;
+            if carry == 1 || cmp(&r2[..k], modulus) != Ordering::Less {
+                sub(&mut r2[..k], modulus);
+            }
+        }
+
+        Self {
+            modulus,
+            inv: inv.wrapping_neg(),
+            r2,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.modulus.len()
+    }
+
+    /// `out = a * b / R mod modulus`, for `a` and `b` below the modulus.
+    pub fn mul(&self, out: &mut [u32], a: &[u32], b: &[u32]) {
+        let k = self.len();
+        let n = self.modulus;
+        let mut t = [0u32; LIMBS + 2];
+        for &x in &a[..k] {
+            let mut carry = 0u64;
+            for j in 0..k {
+                let s = u64::from(t[j]) + u64::from(x) * u64::from(b[j]) + carry;
+                t[j] = s as u32;
+                carry = s >> 32;
+            }
+            let s = u64::from(t[k]) + carry;
+            t[k] = s as u32;
+            t[k + 1] = (s >> 32) as u32;
+
+            let m = t[0].wrapping_mul(self.inv);
+            let mut carry = (u64::from(t[0]) + u64::from(m) * u64::from(n[0])) >> 32;
+            for j in 1..k {
+                let s = u64::from(t[j]) + u64::from(m) * u64::from(n[j]) + carry;
+                t[j - 1] = s as u32;
+                carry = s >> 32;
+            }
+            let s = u64::from(t[k]) + carry;
+            t[k - 1] = s as u32;
+            t[k] = t[k + 1] + (s >> 32) as u32;
+        }
+
+        if t[k] != 0 || cmp(&t[..k], n) != Ordering::Less {
+            sub(&mut t[..k + 1], n);
+        }
+        out[..k].copy_from_slice(&t[..k]);
+    }
+
+    /// `out = a mod modulus`, for an `a` of twice the modulus' length whose
+    /// halves are each below twice the modulus.
+    pub fn reduce_wide(&self, out: &mut [u32], a: &[u32]) {
+        let k = self.len();
+        let mut lo = [0; LIMBS];
+        let mut hi = [0; LIMBS];
+        lo[..k].copy_from_slice(&a[..k]);
+        hi[..k].copy_from_slice(&a[k..2 * k]);
+        for half in [&mut lo, &mut hi] {
+            if cmp(&half[..k], self.modulus) != Ordering::Less {
+                sub(&mut half[..k], self.modulus);
+            }
+        }
+
+        // hi * R² / R = hi * R, which puts the high half back in its place.
+        self.mul(out, &hi, &self.r2);
+        if add(&mut out[..k], &lo[..k]) || cmp(&out[..k], self.modulus) != Ordering::Less {
+            sub(&mut out[..k], self.modulus);
+        }
+    }
+
+    /// Converts `a`, which must be below the modulus, into Montgomery form.
+    pub fn to_montgomery(&self, out: &mut [u32], a: &[u32]) {
+        self.mul(out, a, &self.r2);
+    }
+
+    /// Converts `a` out of Montgomery form.
+    pub fn to_normal(&self, out: &mut [u32], a: &[u32]) {
+        let mut one = [0; LIMBS];
+        one[0] = 1;
+        self.mul(out, a, &one);
+    }
+
+    /// `out = base^exp mod modulus` in Montgomery form, for `base` below the
+    /// modulus.
+    pub fn pow_montgomery(&self, out: &mut [u32], base: &[u32], exp: &[u32]) {
+        let k = self.len();
+        let mut base_m = [0; LIMBS];
+        self.to_montgomery(&mut base_m, base);
+
+        let mut one = [0; LIMBS];
+        one[0] = 1;
+        let mut x = [0; LIMBS];
+        self.to_montgomery(&mut x, &one);
+
+        let mut tmp = [0; LIMBS];
+        for i in (0..bits(exp)).rev() {
+            self.mul(&mut tmp, &x, &x);
+            x[..k].copy_from_slice(&tmp[..k]);
+            if bit(exp, i) {
+                self.mul(&mut tmp, &x, &base_m);
+                x[..k].copy_from_slice(&tmp[..k]);
+            }
+        }
+        out[..k].copy_from_slice(&x[..k]);
+    }
+
+    /// `out = base^exp mod modulus`, for `base` below the modulus.
+    pub fn pow(&self, out: &mut [u32], base: &[u32], exp: &[u32]) {
+        let mut x = [0; LIMBS];
+        self.pow_montgomery(&mut x, base, exp);
+        self.to_normal(out, &x);
+    }
+}
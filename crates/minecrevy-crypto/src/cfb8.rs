@@ -0,0 +1,101 @@
+use crate::aes::Aes128;
+
+/// AES-128 in 8-bit cipher feedback mode, as used by the Minecraft protocol
+/// once encryption is enabled.
+///
+/// Each byte is encrypted on its own, so a stream can be encrypted in chunks
+/// of any size. Each direction of a connection needs its own [`Cfb8`], since
+/// the state depends on every byte that went through it.
+#[derive(Clone, Debug)]
+pub struct Cfb8 {
+    cipher: Aes128,
+    register: [u8; 16],
+}
+
+impl Cfb8 {
+    pub fn new(key: &[u8; 16], iv: &[u8; 16]) -> Self {
+        Self {
+            cipher: Aes128::new(key),
+            register: *iv,
+        }
+    }
+
+    /// Creates the cipher for one direction of a connection, which uses the
+    /// shared secret as both the key and the IV.
+    pub fn from_shared_secret(secret: &[u8; 16]) -> Self {
+        Self::new(secret, secret)
+    }
+
+    pub fn encrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            *byte ^= self.keystream_byte();
+            self.shift_in(*byte);
+        }
+    }
+
+    pub fn decrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            let ciphertext = *byte;
+            *byte ^= self.keystream_byte();
+            self.shift_in(ciphertext);
+        }
+    }
+
+    fn keystream_byte(&self) -> u8 {
+        let mut block = self.register;
+        self.cipher.encrypt_block(&mut block);
+        block[0]
+    }
+
+    fn shift_in(&mut self, ciphertext: u8) {
+        self.register.copy_within(1.., 0);
+        self.register[15] = ciphertext;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// CFB8-AES128 from NIST SP 800-38A, appendix F.3.7 and F.3.8.
+    pub(crate) const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+    pub(crate) const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    pub(crate) const PLAINTEXT: [u8; 18] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d,
+    ];
+    pub(crate) const CIPHERTEXT: [u8; 18] = [
+        0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a,
+        0x4f, 0x32, 0xb9,
+    ];
+
+    #[test]
+    fn encrypt_matches_nist_vector() {
+        let mut buf = PLAINTEXT;
+        Cfb8::new(&KEY, &IV).encrypt(&mut buf);
+        assert_eq!(buf, CIPHERTEXT);
+    }
+
+    #[test]
+    fn decrypt_matches_nist_vector() {
+        let mut buf = CIPHERTEXT;
+        Cfb8::new(&KEY, &IV).decrypt(&mut buf);
+        assert_eq!(buf, PLAINTEXT);
+    }
+
+    #[test]
+    fn encrypt_in_chunks_matches_one_go() {
+        let mut cfb8 = Cfb8::new(&KEY, &IV);
+        let mut buf = PLAINTEXT;
+        let (head, tail) = buf.split_at_mut(5);
+        cfb8.encrypt(head);
+        cfb8.encrypt(tail);
+        assert_eq!(buf, CIPHERTEXT);
+    }
+}
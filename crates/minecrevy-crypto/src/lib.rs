@@ -0,0 +1,14 @@
+//! The cryptography used by online-mode Minecraft servers, written for
+//! `no_std` targets without an allocator.
+
+#![no_std]
+
+mod aes;
+mod bigint;
+mod cfb8;
+mod rsa;
+mod server_hash;
+mod sha1;
+mod stream;
+
+pub use self::{aes::*, cfb8::*, rsa::*, server_hash::*, sha1::*, stream::*};
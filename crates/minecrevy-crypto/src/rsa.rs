@@ -0,0 +1,397 @@
+//! 1024-bit RSA, as used to exchange the shared secret during login.
+//!
+//! Like the rest of this crate, it is a plain implementation that isn't
+//! hardened against timing side channels.
+
+use core::cmp::Ordering;
+
+use defmt::Format;
+use thiserror::Error;
+
+use crate::bigint::{self, LIMBS, Limbs, Montgomery};
+
+/// The number of limbs in each of the two primes.
+const HALF: usize = LIMBS / 2;
+
+/// The size of the modulus, and so of every ciphertext, in bytes.
+pub const RSA_SIZE: usize = LIMBS * 4;
+
+/// The size of [`RsaPrivateKey::public_key_der`].
+pub const PUBLIC_KEY_DER_SIZE: usize = 162;
+
+/// The public exponent, which vanilla servers use as well.
+const E: u32 = 65537;
+
+/// How many rounds of Miller-Rabin a prime candidate has to pass.
+const MILLER_RABIN_ROUNDS: usize = 8;
+
+/// The odd primes below 256, which cheaply rule out most prime candidates
+/// before Miller-Rabin has to run.
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Everything up to the modulus in the DER encoding of the public key, a
+/// `SubjectPublicKeyInfo` holding an `RSAPublicKey`.
+const DER_PREFIX: [u8; 29] = [
+    0x30, 0x81, 0x9f, // SubjectPublicKeyInfo
+    0x30, 0x0d, // AlgorithmIdentifier
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, // rsaEncryption
+    0x05, 0x00, // no parameters
+    0x03, 0x81, 0x8d, 0x00, // subjectPublicKey, no unused bits
+    0x30, 0x81, 0x89, // RSAPublicKey
+    0x02, 0x81, 0x81, 0x00, // modulus, with a zero byte to keep it positive
+];
+
+/// Everything after the modulus in the DER encoding of the public key.
+const DER_SUFFIX: [u8; 5] = [0x02, 0x03, 0x01, 0x00, 0x01];
+
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RsaError {
+    #[error("ciphertext is not {RSA_SIZE} bytes long")]
+    InvalidLength,
+    #[error("ciphertext is not below the modulus")]
+    OutOfRange,
+    #[error("invalid PKCS#1 v1.5 padding")]
+    InvalidPadding,
+    #[error("buffer too small")]
+    BufferTooSmall,
+}
+
+/// A 1024-bit RSA private key with a public exponent of 65537.
+///
+/// Decryption uses the Chinese remainder theorem, which makes it about four
+/// times faster than a single exponentiation modulo `n`.
+#[derive(Clone)]
+pub struct RsaPrivateKey {
+    n: Limbs,
+    p: [u32; HALF],
+    q: [u32; HALF],
+    /// `d mod (p - 1)`.
+    dp: [u32; HALF],
+    /// `d mod (q - 1)`.
+    dq: [u32; HALF],
+    /// `q⁻¹ mod p`, in Montgomery form.
+    q_inv: [u32; HALF],
+}
+
+impl RsaPrivateKey {
+    /// Generates a new key, taking randomness from `fill_bytes`.
+    ///
+    /// `fill_bytes` must be a cryptographically secure source such as a
+    /// hardware TRNG, since anyone who can predict its output can recover the
+    /// key.
+    pub fn generate(mut fill_bytes: impl FnMut(&mut [u8])) -> Self {
+        let p = generate_prime(&mut fill_bytes);
+        let q = loop {
+            let q = generate_prime(&mut fill_bytes);
+            if q != p {
+                break q;
+            }
+        };
+        Self::from_primes(p, q)
+    }
+
+    fn from_primes(p: [u32; HALF], q: [u32; HALF]) -> Self {
+        let mut n = [0; LIMBS];
+        bigint::mul(&mut n, &p, &q);
+
+        let mut p_minus_1 = p;
+        bigint::sub(&mut p_minus_1, &[1]);
+        let mut q_minus_1 = q;
+        bigint::sub(&mut q_minus_1, &[1]);
+
+        // Fermat's little theorem gives q⁻¹ = q^(p - 2) mod p.
+        let mut q_mod_p = q;
+        if bigint::cmp(&q_mod_p, &p) == Ordering::Greater {
+            bigint::sub(&mut q_mod_p, &p);
+        }
+        let mut p_minus_2 = p_minus_1;
+        bigint::sub(&mut p_minus_2, &[1]);
+        let mut q_inv = [0; LIMBS];
+        Montgomery::new(&p).pow_montgomery(&mut q_inv, &q_mod_p, &p_minus_2);
+
+        Self {
+            n,
+            p,
+            q,
+            dp: inverse_of_e(&p_minus_1),
+            dq: inverse_of_e(&q_minus_1),
+            q_inv: q_inv[..HALF].try_into().unwrap(),
+        }
+    }
+
+    /// Returns the public key as a DER-encoded `SubjectPublicKeyInfo`, which
+    /// is how the Encryption Request packet carries it.
+    pub fn public_key_der(&self) -> [u8; PUBLIC_KEY_DER_SIZE] {
+        let mut der = [0; PUBLIC_KEY_DER_SIZE];
+        let (prefix, rest) = der.split_at_mut(DER_PREFIX.len());
+        let (modulus, suffix) = rest.split_at_mut(RSA_SIZE);
+        prefix.copy_from_slice(&DER_PREFIX);
+        bigint::to_be_bytes(&self.n, modulus);
+        suffix.copy_from_slice(&DER_SUFFIX);
+        der
+    }
+
+    /// Decrypts a PKCS#1 v1.5 `ciphertext` into `out`, returning the part of
+    /// `out` that holds the message.
+    pub fn decrypt<'o>(&self, ciphertext: &[u8], out: &'o mut [u8]) -> Result<&'o [u8], RsaError> {
+        if ciphertext.len() != RSA_SIZE {
+            return Err(RsaError::InvalidLength);
+        }
+        let mut c = [0; LIMBS];
+        bigint::from_be_bytes(&mut c, ciphertext);
+        if bigint::cmp(&c, &self.n) != Ordering::Less {
+            return Err(RsaError::OutOfRange);
+        }
+
+        let mut block = [0; RSA_SIZE];
+        bigint::to_be_bytes(&self.raw_decrypt(&c), &mut block);
+
+        // 00 02 <at least eight non-zero padding bytes> 00 <message>
+        let [0x00, 0x02, padding @ ..] = &block else {
+            return Err(RsaError::InvalidPadding);
+        };
+        let message = match padding.iter().position(|&byte| byte == 0) {
+            Some(end) if end >= 8 => &padding[end + 1..],
+            _ => return Err(RsaError::InvalidPadding),
+        };
+
+        let out = out
+            .get_mut(..message.len())
+            .ok_or(RsaError::BufferTooSmall)?;
+        out.copy_from_slice(message);
+        Ok(out)
+    }
+
+    /// Computes `c^d mod n` from its residues modulo `p` and `q`.
+    fn raw_decrypt(&self, c: &Limbs) -> Limbs {
+        let p = Montgomery::new(&self.p);
+        let q = Montgomery::new(&self.q);
+
+        let mut m1 = [0; LIMBS];
+        let mut m2 = [0; LIMBS];
+        let mut reduced = [0; LIMBS];
+        p.reduce_wide(&mut reduced, c);
+        p.pow(&mut m1, &reduced, &self.dp);
+        q.reduce_wide(&mut reduced, c);
+        q.pow(&mut m2, &reduced, &self.dq);
+
+        // h = q⁻¹ (m1 - m2) mod p
+        let mut diff = m2;
+        if bigint::cmp(&diff[..HALF], &self.p) != Ordering::Less {
+            bigint::sub(&mut diff[..HALF], &self.p);
+        }
+        let mut h = m1;
+        if bigint::sub(&mut h[..HALF], &diff[..HALF]) {
+            bigint::add(&mut h[..HALF], &self.p);
+        }
+        p.mul(&mut diff, &h, &self.q_inv);
+
+        // m = m2 + h q
+        let mut m = [0; LIMBS];
+        bigint::mul(&mut m, &diff[..HALF], &self.q);
+        bigint::add(&mut m, &m2[..HALF]);
+        m
+    }
+}
+
+/// Leaves the key out so it doesn't end up in logs.
+impl core::fmt::Debug for RsaPrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RsaPrivateKey").finish_non_exhaustive()
+    }
+}
+
+/// Generates a random 512-bit prime `p` with the top two bits set, so that the
+/// product of two of them is a full 1024 bits, and with `p - 1` coprime to
+/// [`E`].
+fn generate_prime(fill_bytes: &mut impl FnMut(&mut [u8])) -> [u32; HALF] {
+    let mut bytes = [0; HALF * 4];
+    let mut candidate = [0; HALF];
+    loop {
+        fill_bytes(&mut bytes);
+        bigint::from_be_bytes(&mut candidate, &bytes);
+        candidate[HALF - 1] |= 0xc000_0000;
+        candidate[0] |= 1;
+
+        if SMALL_PRIMES
+            .iter()
+            .any(|&prime| bigint::rem_small(&candidate, prime) == 0)
+        {
+            continue;
+        }
+        // E is prime, so p - 1 is coprime to it unless it is a multiple.
+        if bigint::rem_small(&candidate, E) == 1 {
+            continue;
+        }
+        if is_probable_prime(&candidate, fill_bytes) {
+            return candidate;
+        }
+    }
+}
+
+/// The Miller-Rabin test with random bases, for an odd `n` above 2³².
+fn is_probable_prime(n: &[u32; HALF], fill_bytes: &mut impl FnMut(&mut [u8])) -> bool {
+    let mont = Montgomery::new(n);
+
+    // n - 1 = d 2^s
+    let mut n_minus_1 = *n;
+    bigint::sub(&mut n_minus_1, &[1]);
+    let s = (0..).find(|&i| bigint::bit(&n_minus_1, i)).unwrap();
+    let mut d = [0; HALF];
+    for (i, limb) in d.iter_mut().enumerate() {
+        let bit = i * 32 + s;
+        let lo = n_minus_1.get(bit / 32).copied().unwrap_or(0);
+        let hi = n_minus_1.get(bit / 32 + 1).copied().unwrap_or(0);
+        *limb = match bit % 32 {
+            0 => lo,
+            shift => (lo >> shift) | (hi << (32 - shift)),
+        };
+    }
+
+    let mut unit = [0; HALF];
+    unit[0] = 1;
+    let mut one = [0; LIMBS];
+    mont.to_montgomery(&mut one, &unit);
+    let mut minus_one = [0; LIMBS];
+    mont.to_montgomery(&mut minus_one, &n_minus_1);
+
+    'rounds: for _ in 0..MILLER_RABIN_ROUNDS {
+        let mut base = [0; HALF];
+        let mut bytes = [0; 4];
+        fill_bytes(&mut bytes);
+        base[0] = u32::from_be_bytes(bytes).max(2);
+
+        let mut x = [0; LIMBS];
+        mont.pow_montgomery(&mut x, &base, &d);
+        if x[..HALF] == one[..HALF] || x[..HALF] == minus_one[..HALF] {
+            continue;
+        }
+        let mut squared = [0; LIMBS];
+        for _ in 1..s {
+            mont.mul(&mut squared, &x, &x);
+            x = squared;
+            if x[..HALF] == minus_one[..HALF] {
+                continue 'rounds;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Returns `E⁻¹ mod phi`, for a `phi` that is coprime to [`E`].
+fn inverse_of_e(phi: &[u32; HALF]) -> [u32; HALF] {
+    // E d = 1 + k phi for some k below E, which makes 1 + k phi ≡ 0 (mod E),
+    // so k = -phi⁻¹ mod E. E is prime, so phi⁻¹ = phi^(E - 2) mod E.
+    let phi_mod_e = u64::from(bigint::rem_small(phi, E));
+    let mut phi_inv = 1;
+    let mut base = phi_mod_e;
+    let mut exp = E - 2;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            phi_inv = phi_inv * base % u64::from(E);
+        }
+        base = base * base % u64::from(E);
+        exp >>= 1;
+    }
+    let k = E - phi_inv as u32;
+
+    let mut d = [0; HALF + 1];
+    d[..HALF].copy_from_slice(phi);
+    d[HALF] = bigint::mul_small(&mut d[..HALF], k);
+    bigint::add_small(&mut d, 1);
+    bigint::div_small(&mut d, E);
+    d[..HALF].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: &str = "e011007d13b0f0e26711fb42c5413edd5c8165529c7000c259c542879215aa49\
+                     4ed47ae9589c861a2c0a7657a07f227b0a325ce972ba9d2b79de80cc3ec8532d";
+    const Q: &str = "c7a5485a1a4eef1bbe46d2c7d95e28663fd3d5e88f9ac80466d3f8ae2d62867c\
+                     eff379aa84a7435462fff1a1f93c5696ba374965a8b126c09bd6abb557294925";
+    const N: &str = "aebde1aa30106650f1e62bf0a10b7c88fdef2dd5a81483c69430ce65e41b2f5b\
+                     839b51920676a89879119f08fcc03a0b2573ae3662a1aabba4e98f39b9e0f236\
+                     5aaee9d92d58b1c2c855b52b180015966f9fdf0e801acf199ad0d6f1f1c8d00d\
+                     12e2d9a7d564bcc9591047298adce590810afc3ab9f8935b13bedb96cfe0da81";
+    /// The bytes 1 to 16, padded with PKCS#1 v1.5 type 2 and encrypted.
+    const CIPHERTEXT: &str = "60d7f3ae35777158b415b3594029eb0f059073a2c3ad0d822be15a9163c3a405\
+                              fc7770da03c92d6b4641c1b75f4405a2553a4253cf6b1b7daf52c222646dcbf1\
+                              94e3454d19abed63de0465c4bc03255a09c49339cb711c284259debe3606a694\
+                              978de81105497b31d863f4675cd3ff105be178528199241f8892e045c24e2098";
+    /// The same message padded with type 1, which is for signatures.
+    const BAD_PADDING: &str = "5832b2691e7b158da7c2db0a6a3561b8eb61ef9988808c488535222db0292b2b\
+                               3025d686f1689fab8ac514f16c24099e853e0bc9efde1cac1f65116f9012cade\
+                               0d4d5cc37e1e7eccd6e1f711e671305f09ea6ba6e7c44232b89d8e1e5401ffa3\
+                               08f307201a3a86f6ebc4c7f9a732a02b35ffbbba785dc37b61318d18eb45045b";
+
+    fn unhex<const LEN: usize>(hex: &str) -> [u8; LEN] {
+        let hex = hex.as_bytes();
+        assert_eq!(hex.len(), LEN * 2);
+        let mut bytes = [0; LEN];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+            let pair = core::str::from_utf8(pair).unwrap();
+            *byte = u8::from_str_radix(pair, 16).unwrap();
+        }
+        bytes
+    }
+
+    fn key() -> RsaPrivateKey {
+        let mut p = [0; HALF];
+        bigint::from_be_bytes(&mut p, &unhex::<64>(P));
+        let mut q = [0; HALF];
+        bigint::from_be_bytes(&mut q, &unhex::<64>(Q));
+        RsaPrivateKey::from_primes(p, q)
+    }
+
+    #[test]
+    fn public_key_der_holds_modulus() {
+        let der = key().public_key_der();
+        assert_eq!(der[..DER_PREFIX.len()], DER_PREFIX);
+        assert_eq!(der[DER_PREFIX.len()..][..RSA_SIZE], unhex::<RSA_SIZE>(N));
+        assert_eq!(der[DER_PREFIX.len() + RSA_SIZE..], DER_SUFFIX);
+    }
+
+    #[test]
+    fn decrypt_fixed_ciphertext() {
+        let mut out = [0; RSA_SIZE];
+        let message = key()
+            .decrypt(&unhex::<RSA_SIZE>(CIPHERTEXT), &mut out)
+            .unwrap();
+        assert_eq!(
+            message,
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+    }
+
+    #[test]
+    fn decrypt_rejects_bad_padding() {
+        let mut out = [0; RSA_SIZE];
+        let result = key().decrypt(&unhex::<RSA_SIZE>(BAD_PADDING), &mut out);
+        assert_eq!(result, Err(RsaError::InvalidPadding));
+    }
+
+    #[test]
+    fn decrypt_rejects_bad_input() {
+        let key = key();
+        let mut out = [0; RSA_SIZE];
+        assert_eq!(
+            key.decrypt(&[0; RSA_SIZE - 1], &mut out),
+            Err(RsaError::InvalidLength)
+        );
+        assert_eq!(
+            key.decrypt(&unhex::<RSA_SIZE>(N), &mut out),
+            Err(RsaError::OutOfRange)
+        );
+        assert_eq!(
+            key.decrypt(&unhex::<RSA_SIZE>(CIPHERTEXT), &mut [0; 15]),
+            Err(RsaError::BufferTooSmall)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use core::fmt;
+
+use crate::sha1::Sha1;
+
+/// The hash that the client and the server both send to the session server
+/// to prove they agreed on the same shared secret and public key.
+///
+/// It is the SHA-1 of the server ID, the shared secret and the DER-encoded
+/// public key. It is displayed the way Java's `BigInteger::toString(16)` would
+/// display it, as a signed two's complement number in lowercase hex without
+/// leading zeros.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ServerHash(pub [u8; 20]);
+
+impl ServerHash {
+    /// The longest the hash can be when displayed, a minus sign and 40 digits.
+    pub const MAX_LEN: usize = 41;
+
+    /// Hashes the parts of the login. Modern servers always send an empty
+    /// `server_id`.
+    pub fn new(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> Self {
+        let mut sha1 = Sha1::new();
+        sha1.update(server_id.as_bytes());
+        sha1.update(shared_secret);
+        sha1.update(public_key_der);
+        Self(sha1.finalize())
+    }
+}
+
+impl fmt::Display for ServerHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut magnitude = self.0;
+        if magnitude[0] & 0x80 != 0 {
+            f.write_str("-")?;
+            // Negate the two's complement by inverting and adding one.
+            let mut carry = true;
+            for byte in magnitude.iter_mut().rev() {
+                (*byte, carry) = (!*byte).overflowing_add(u8::from(carry));
+            }
+        }
+
+        match magnitude.iter().position(|&byte| byte != 0) {
+            Some(start) => {
+                write!(f, "{:x}", magnitude[start])?;
+                magnitude[start + 1..]
+                    .iter()
+                    .try_for_each(|byte| write!(f, "{byte:02x}"))
+            }
+            None => f.write_str("0"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::ToString;
+
+    use super::*;
+
+    fn hash(name: &str) -> std::string::String {
+        ServerHash::new(name, &[], &[]).to_string()
+    }
+
+    #[test]
+    fn displays_like_java_big_integer() {
+        assert_eq!(hash("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(hash("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(hash("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[test]
+    fn displays_zero_and_minus_one() {
+        assert_eq!(ServerHash([0; 20]).to_string(), "0");
+        assert_eq!(ServerHash([0xff; 20]).to_string(), "-1");
+    }
+}
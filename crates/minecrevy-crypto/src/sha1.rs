@@ -0,0 +1,138 @@
+//! SHA-1, which online-mode servers use to compute the [`ServerHash`].
+//!
+//! SHA-1 is not collision resistant, so don't use it for anything else that
+//! needs to be secure.
+//!
+//! [`ServerHash`]: crate::ServerHash
+
+/// An incremental SHA-1 hasher.
+#[derive(Clone, Debug)]
+pub struct Sha1 {
+    state: [u32; 5],
+    block: [u8; 64],
+    /// The number of bytes hashed so far.
+    len: u64,
+}
+
+impl Sha1 {
+    pub const fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            block: [0; 64],
+            len: 0,
+        }
+    }
+
+    /// Hashes `data` in one go.
+    pub fn digest(data: &[u8]) -> [u8; 20] {
+        let mut sha1 = Self::new();
+        sha1.update(data);
+        sha1.finalize()
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let offset = (self.len % 64) as usize;
+            let n = data.len().min(64 - offset);
+            self.block[offset..][..n].copy_from_slice(&data[..n]);
+            self.len += n as u64;
+            data = &data[n..];
+            if offset + n == 64 {
+                compress(&mut self.state, &self.block);
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 20] {
+        let bits = self.len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.len % 64 != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+
+        let mut digest = [0; 20];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut words = [0; 80];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..80 {
+        words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in words.into_iter().enumerate() {
+        let (f, k) = match i / 20 {
+            0 => ((b & c) | (!b & d), 0x5a827999),
+            1 => (b ^ c ^ d, 0x6ed9eba1),
+            2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(word);
+        (a, b, c, d, e) = (temp, a, b.rotate_left(30), c, d);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: [u8; 20]) -> [u8; 40] {
+        let mut out = [0; 40];
+        for (pair, byte) in out.chunks_exact_mut(2).zip(digest) {
+            pair[0] = b"0123456789abcdef"[usize::from(byte >> 4)];
+            pair[1] = b"0123456789abcdef"[usize::from(byte & 15)];
+        }
+        out
+    }
+
+    #[test]
+    fn digest_matches_known_answers() {
+        let cases: [(&[u8], &[u8; 40]); 3] = [
+            (b"", b"da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (b"abc", b"a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                b"84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(&hex(Sha1::digest(data)), expected);
+        }
+    }
+
+    #[test]
+    fn update_in_chunks_matches_digest() {
+        let mut sha1 = Sha1::new();
+        for _ in 0..1_000_000 / 1000 {
+            sha1.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            &hex(sha1.finalize()),
+            b"34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+}
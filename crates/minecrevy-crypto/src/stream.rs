@@ -0,0 +1,264 @@
+use embedded_byteorder::{AsyncRead, AsyncWrite, ErrorType, Read, Write};
+
+use crate::Cfb8;
+
+/// The size of the stack buffer that [`CipherWriter`] encrypts into.
+const CHUNK_SIZE: usize = 64;
+
+/// Decrypts everything read from the inner reader.
+///
+/// Bytes are decrypted in place in the caller's buffer, so this can be put
+/// under `read_packet` without any extra buffering.
+#[derive(Debug)]
+pub struct CipherReader<R> {
+    inner: R,
+    cipher: Cfb8,
+}
+
+impl<R> CipherReader<R> {
+    pub fn new(inner: R, cipher: Cfb8) -> Self {
+        Self { inner, cipher }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ErrorType> ErrorType for CipherReader<R> {
+    type Error = R::Error;
+}
+
+impl<R: Read> Read for CipherReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.inner.read(buf)?;
+        self.cipher.decrypt(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for CipherReader<R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.inner.read(buf).await?;
+        self.cipher.decrypt(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Encrypts everything written to the inner writer.
+///
+/// The data being written is borrowed immutably, so it is encrypted through a
+/// small stack buffer. Every encrypted byte is written out before returning,
+/// since the cipher has already moved past it.
+#[derive(Debug)]
+pub struct CipherWriter<W> {
+    inner: W,
+    cipher: Cfb8,
+}
+
+impl<W> CipherWriter<W> {
+    pub fn new(inner: W, cipher: Cfb8) -> Self {
+        Self { inner, cipher }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: ErrorType> ErrorType for CipherWriter<W> {
+    type Error = W::Error;
+}
+
+impl<W: Write> Write for CipherWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let n = buf.len().min(CHUNK_SIZE);
+        chunk[..n].copy_from_slice(&buf[..n]);
+        self.cipher.encrypt(&mut chunk[..n]);
+        self.inner.write_all(&chunk[..n])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for CipherWriter<W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let n = buf.len().min(CHUNK_SIZE);
+        chunk[..n].copy_from_slice(&buf[..n]);
+        self.cipher.encrypt(&mut chunk[..n]);
+        self.inner.write_all(&chunk[..n]).await?;
+        Ok(n)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        convert::Infallible,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::cfb8::tests::{CIPHERTEXT, IV, KEY, PLAINTEXT};
+
+    /// Runs a future that never waits on anything outside of memory.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Reads and writes at most `MAX` bytes at a time, like a socket might.
+    struct Trickle<'a, const MAX: usize>(&'a mut [u8]);
+
+    impl<const MAX: usize> ErrorType for Trickle<'_, MAX> {
+        type Error = Infallible;
+    }
+
+    impl<const MAX: usize> Read for Trickle<'_, MAX> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(self.0.len()).min(MAX);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &mut core::mem::take(&mut self.0)[n..];
+            Ok(n)
+        }
+    }
+
+    impl<const MAX: usize> AsyncRead for Trickle<'_, MAX> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            Read::read(self, buf)
+        }
+    }
+
+    impl<const MAX: usize> Write for Trickle<'_, MAX> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let n = buf.len().min(self.0.len()).min(MAX);
+            self.0[..n].copy_from_slice(&buf[..n]);
+            self.0 = &mut core::mem::take(&mut self.0)[n..];
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl<const MAX: usize> AsyncWrite for Trickle<'_, MAX> {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            Write::write(self, buf)
+        }
+    }
+
+    /// More than [`CHUNK_SIZE`], so that a single write is split into chunks,
+    /// and not a multiple of it.
+    fn message() -> [u8; 3 * CHUNK_SIZE + 17] {
+        core::array::from_fn(|i| (i * 31 + 7) as u8)
+    }
+
+    fn encrypted(message: &[u8]) -> [u8; 3 * CHUNK_SIZE + 17] {
+        let mut buf = [0; 3 * CHUNK_SIZE + 17];
+        buf.copy_from_slice(message);
+        Cfb8::new(&KEY, &IV).encrypt(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn writer_matches_nist_vector() {
+        let mut out = [0; 18];
+        let mut writer = CipherWriter::new(Trickle::<5>(&mut out), Cfb8::new(&KEY, &IV));
+        Write::write_all(&mut writer, &PLAINTEXT).unwrap();
+        assert_eq!(out, CIPHERTEXT);
+
+        let mut out = [0; 18];
+        let mut writer = CipherWriter::new(Trickle::<5>(&mut out), Cfb8::new(&KEY, &IV));
+        block_on(AsyncWrite::write_all(&mut writer, &PLAINTEXT)).unwrap();
+        assert_eq!(out, CIPHERTEXT);
+    }
+
+    #[test]
+    fn reader_matches_nist_vector() {
+        let mut ciphertext = CIPHERTEXT;
+        let mut reader = CipherReader::new(Trickle::<5>(&mut ciphertext), Cfb8::new(&KEY, &IV));
+        let mut buf = [0; 18];
+        Read::read_exact(&mut reader, &mut buf).unwrap();
+        assert_eq!(buf, PLAINTEXT);
+
+        let mut ciphertext = CIPHERTEXT;
+        let mut reader = CipherReader::new(Trickle::<5>(&mut ciphertext), Cfb8::new(&KEY, &IV));
+        let mut buf = [0; 18];
+        block_on(AsyncRead::read_exact(&mut reader, &mut buf)).unwrap();
+        assert_eq!(buf, PLAINTEXT);
+    }
+
+    #[test]
+    fn writer_splits_large_writes_into_chunks() {
+        let message = message();
+        let mut out = [0; 3 * CHUNK_SIZE + 17];
+        let mut writer = CipherWriter::new(Trickle::<7>(&mut out), Cfb8::new(&KEY, &IV));
+        // A single write takes at most a chunk, and all of it.
+        assert_eq!(Write::write(&mut writer, &message), Ok(CHUNK_SIZE));
+        Write::write_all(&mut writer, &message[CHUNK_SIZE..]).unwrap();
+        assert_eq!(out, encrypted(&message));
+
+        let mut out = [0; 3 * CHUNK_SIZE + 17];
+        let mut writer = CipherWriter::new(Trickle::<7>(&mut out), Cfb8::new(&KEY, &IV));
+        block_on(AsyncWrite::write_all(&mut writer, &message)).unwrap();
+        assert_eq!(out, encrypted(&message));
+    }
+
+    #[test]
+    fn reader_decrypts_partial_reads() {
+        let message = message();
+        let mut ciphertext = encrypted(&message);
+        let mut reader = CipherReader::new(Trickle::<7>(&mut ciphertext), Cfb8::new(&KEY, &IV));
+
+        // Reads of every size from 1 byte to more than a chunk, each of
+        // which the inner reader only partly fills.
+        let mut out = [0; 3 * CHUNK_SIZE + 17];
+        let mut len = 0;
+        let mut size = 1;
+        while len < out.len() {
+            let end = (len + size).min(out.len());
+            len += Read::read(&mut reader, &mut out[len..end]).unwrap();
+            size = size % (CHUNK_SIZE + 3) + 1;
+        }
+        assert_eq!(out, message);
+        assert_eq!(Read::read(&mut reader, &mut [0; 4]), Ok(0));
+
+        let mut ciphertext = encrypted(&message);
+        let mut reader = CipherReader::new(Trickle::<7>(&mut ciphertext), Cfb8::new(&KEY, &IV));
+        let mut out = [0; 3 * CHUNK_SIZE + 17];
+        block_on(AsyncRead::read_exact(&mut reader, &mut out)).unwrap();
+        assert_eq!(out, message);
+    }
+}
@@ -0,0 +1,486 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Generics, Ident, LitStr};
+
+use crate::{
+    fields::{self, Field, Variant},
+    generics,
+    options::{Tag, field_options},
+};
+
+/// Whether the derived trait is one of the `async` or blocking traits.
+#[derive(Clone, Copy)]
+pub enum Mode {
+    Async,
+    Blocking,
+}
+
+/// Whether the derived trait encodes or decodes.
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Encode,
+    Decode,
+}
+
+/// Describes one of the four codec traits.
+#[derive(Clone, Copy)]
+pub struct Codec {
+    pub mode: Mode,
+    pub direction: Direction,
+}
+
+impl Codec {
+    fn trait_ident(&self) -> Ident {
+        match (self.mode, self.direction) {
+            (Mode::Async, Direction::Encode) => format_ident!("AsyncEncode"),
+            (Mode::Async, Direction::Decode) => format_ident!("AsyncDecode"),
+            (Mode::Blocking, Direction::Encode) => format_ident!("Encode"),
+            (Mode::Blocking, Direction::Decode) => format_ident!("Decode"),
+        }
+    }
+
+    fn trait_path(&self) -> TokenStream {
+        let ident = self.trait_ident();
+        quote! { minecrevy_encdec::#ident }
+    }
+
+    fn io_trait_path(&self) -> TokenStream {
+        let ident = match (self.mode, self.direction) {
+            (Mode::Async, Direction::Encode) => format_ident!("AsyncWrite"),
+            (Mode::Async, Direction::Decode) => format_ident!("AsyncRead"),
+            (Mode::Blocking, Direction::Encode) => format_ident!("Write"),
+            (Mode::Blocking, Direction::Decode) => format_ident!("Read"),
+        };
+        quote! { minecrevy_encdec::__private::embedded_byteorder::#ident }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self.direction {
+            Direction::Encode => "encode",
+            Direction::Decode => "decode",
+        }
+    }
+
+    fn asyncness(&self) -> TokenStream {
+        match self.mode {
+            Mode::Async => quote! { async },
+            Mode::Blocking => quote! {},
+        }
+    }
+
+    fn dot_await(&self) -> TokenStream {
+        match self.mode {
+            Mode::Async => quote! { .await },
+            Mode::Blocking => quote! {},
+        }
+    }
+}
+
+/// A variant of a generated error type, wrapping the error of a single field.
+struct ErrorVariant {
+    ident: Ident,
+    ty: TokenStream,
+    message: String,
+}
+
+/// The body of the `encode` or `decode` method along with the variants of its
+/// error type.
+struct Method {
+    body: TokenStream,
+    errors: Vec<ErrorVariant>,
+}
+
+pub fn derive(input: DeriveInput, codec: Codec) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let trait_path = codec.trait_path();
+    let io_trait_path = codec.io_trait_path();
+    let asyncness = codec.asyncness();
+    let error_ident = format_ident!("{}{}Error", codec.trait_ident(), name);
+    let e = generics::fresh_ident(&input.generics, "E");
+
+    let generics = generics::with_bound(&input.generics, &trait_path);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut error_generics = generics::without_lifetimes(&generics);
+    error_generics.params.push(syn::parse_quote!(#e));
+    let (_, error_ty_generics, _) = error_generics.split_for_impl();
+
+    let Method { body, errors } = match &input.data {
+        Data::Struct(data_struct) => {
+            let fields = fields::collect(&data_struct.fields)?;
+            derive_struct(codec, name, &error_ident, &e, &fields)
+        }
+        Data::Enum(data_enum) => {
+            let tag = Tag::from_attrs(&input.attrs)?;
+            let variants = fields::collect_variants(data_enum)?;
+            derive_enum(codec, name, &error_ident, &e, &tag, &variants)
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                format!(
+                    "{} can only be derived for structs and enums",
+                    codec.trait_ident()
+                ),
+            ));
+        }
+    };
+
+    let (error_type, error_def) = if errors.is_empty() {
+        (quote! { ::core::convert::Infallible }, quote! {})
+    } else {
+        let doc = format!(
+            "Error returned by the derived [`{}`] implementation of [`{}`].",
+            codec.trait_ident(),
+            name,
+        );
+        (
+            quote! { #error_ident #error_ty_generics },
+            error_enum(&input.vis, &error_ident, &error_generics, &doc, &errors),
+        )
+    };
+
+    let method = match codec.direction {
+        Direction::Encode => {
+            let writer = io_ident(codec, !errors.is_empty());
+            let w = generics::fresh_ident(&input.generics, "W");
+            quote! {
+                #asyncness fn encode<#w: #io_trait_path>(
+                    &self,
+                    #writer: &mut #w,
+                    (): Self::Options,
+                ) -> ::core::result::Result<(), Self::Error<#w::Error>> {
+                    #body
+                }
+            }
+        }
+        Direction::Decode => {
+            let reader = io_ident(codec, !errors.is_empty());
+            let r = generics::fresh_ident(&input.generics, "R");
+            quote! {
+                #asyncness fn decode<#r: #io_trait_path>(
+                    #reader: &mut #r,
+                    (): Self::Options,
+                ) -> ::core::result::Result<Self, Self::Error<#r::Error>> {
+                    #body
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #error_def
+
+        #[automatically_derived]
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            type Options = ();
+            type Error<#e> = #error_type;
+
+            #method
+        }
+    })
+}
+
+/// The name of the `writer` or `reader` parameter, prefixed with an underscore
+/// if it is unused.
+fn io_ident(codec: Codec, used: bool) -> Ident {
+    let name = match codec.direction {
+        Direction::Encode => "writer",
+        Direction::Decode => "reader",
+    };
+    if used {
+        format_ident!("{}", name)
+    } else {
+        format_ident!("_{}", name)
+    }
+}
+
+fn derive_struct(
+    codec: Codec,
+    name: &Ident,
+    error_ident: &Ident,
+    e: &Ident,
+    fields: &[Field],
+) -> Method {
+    let errors = fields
+        .iter()
+        .map(|field| field_error(codec, field, e, field.variant(), &name.to_string()))
+        .collect();
+
+    let body = match codec.direction {
+        Direction::Encode => {
+            let encodes = fields.iter().map(|field| {
+                let member = &field.member;
+                encode_field(
+                    codec,
+                    field,
+                    quote! { &self.#member },
+                    error_ident,
+                    &field.variant(),
+                )
+            });
+            quote! {
+                #(#encodes)*
+                ::core::result::Result::Ok(())
+            }
+        }
+        Direction::Decode => {
+            let decodes = fields
+                .iter()
+                .map(|field| decode_field(codec, field, error_ident, &field.variant()));
+            let members = fields.iter().map(|field| {
+                let member = &field.member;
+                let binding = field.binding();
+                quote! { #member: #binding }
+            });
+            quote! {
+                #(#decodes)*
+                ::core::result::Result::Ok(Self { #(#members),* })
+            }
+        }
+    };
+
+    Method { body, errors }
+}
+
+fn derive_enum(
+    codec: Codec,
+    name: &Ident,
+    error_ident: &Ident,
+    e: &Ident,
+    tag: &Tag,
+    variants: &[Variant],
+) -> Method {
+    let trait_path = codec.trait_path();
+    let dot_await = codec.dot_await();
+    let tag_ty = &tag.ty;
+    let tag_options = field_options(&tag.options, tag_ty, &trait_path);
+
+    let mut errors = vec![ErrorVariant {
+        ident: format_ident!("Tag"),
+        ty: quote! { <#tag_ty as #trait_path>::Error<#e> },
+        message: format!("failed to {} discriminant of `{}`", codec.verb(), name),
+    }];
+    if let Direction::Decode = codec.direction {
+        errors.push(ErrorVariant {
+            ident: format_ident!("InvalidTag"),
+            ty: quote! { #tag_ty },
+            message: format!("invalid discriminant for `{}`", name),
+        });
+    }
+    for variant in variants {
+        let owner = format!("{}::{}", name, variant.ident);
+        errors.extend(variant.fields.iter().map(|field| {
+            field_error(codec, field, e, variant_error_ident(variant, field), &owner)
+        }));
+    }
+
+    let body = match codec.direction {
+        Direction::Encode => {
+            let arms = variants.iter().map(|variant| {
+                let pattern = variant.pattern();
+                let discriminant = &variant.discriminant;
+                let encodes = variant.fields.iter().map(|field| {
+                    let binding = field.binding();
+                    let error_variant = variant_error_ident(variant, field);
+                    encode_field(
+                        codec,
+                        field,
+                        quote! { #binding },
+                        error_ident,
+                        &error_variant,
+                    )
+                });
+                quote! {
+                    #pattern => {
+                        let tag: #tag_ty = #discriminant;
+                        <#tag_ty as #trait_path>::encode(&tag, writer, #tag_options)
+                            #dot_await
+                            .map_err(#error_ident::Tag)?;
+                        #(#encodes)*
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+        Direction::Decode => {
+            let branches = variants.iter().map(|variant| {
+                let pattern = variant.pattern();
+                let discriminant = &variant.discriminant;
+                let decodes = variant.fields.iter().map(|field| {
+                    let error_variant = variant_error_ident(variant, field);
+                    decode_field(codec, field, error_ident, &error_variant)
+                });
+                quote! {
+                    if tag == #discriminant {
+                        #(#decodes)*
+                        return ::core::result::Result::Ok(#pattern);
+                    }
+                }
+            });
+            quote! {
+                let tag = <#tag_ty as #trait_path>::decode(reader, #tag_options)
+                    #dot_await
+                    .map_err(#error_ident::Tag)?;
+                #(#branches)*
+                ::core::result::Result::Err(#error_ident::InvalidTag(tag))
+            }
+        }
+    };
+
+    Method { body, errors }
+}
+
+/// The name of the error variant for a field of an enum variant, e.g.
+/// `ChatMessage` for the `message` field of the `Chat` variant.
+fn variant_error_ident(variant: &Variant, field: &Field) -> Ident {
+    format_ident!("{}{}", variant.ident, field.variant())
+}
+
+fn field_error(codec: Codec, field: &Field, e: &Ident, ident: Ident, owner: &str) -> ErrorVariant {
+    let trait_path = codec.trait_path();
+    let ty = generics::with_static_lifetimes(field.ty);
+    ErrorVariant {
+        ident,
+        ty: quote! { <#ty as #trait_path>::Error<#e> },
+        message: format!(
+            "failed to {} field `{}` of `{}`",
+            codec.verb(),
+            field.name(),
+            owner,
+        ),
+    }
+}
+
+/// Encodes the field whose value is referenced by the expression `value`.
+fn encode_field(
+    codec: Codec,
+    field: &Field,
+    value: TokenStream,
+    error_ident: &Ident,
+    error_variant: &Ident,
+) -> TokenStream {
+    let trait_path = codec.trait_path();
+    let dot_await = codec.dot_await();
+    let ty = field.ty;
+    let options = field_options(&field.options, ty, &trait_path);
+    quote! {
+        <#ty as #trait_path>::encode(#value, writer, #options)
+            #dot_await
+            .map_err(#error_ident::#error_variant)?;
+    }
+}
+
+/// Decodes the field into a local variable named after its binding.
+fn decode_field(
+    codec: Codec,
+    field: &Field,
+    error_ident: &Ident,
+    error_variant: &Ident,
+) -> TokenStream {
+    let trait_path = codec.trait_path();
+    let dot_await = codec.dot_await();
+    let ty = field.ty;
+    let binding = field.binding();
+    let options = field_options(&field.options, ty, &trait_path);
+    quote! {
+        let #binding = <#ty as #trait_path>::decode(reader, #options)
+            #dot_await
+            .map_err(#error_ident::#error_variant)?;
+    }
+}
+
+/// Generates an error enum with one variant per field, along with `Debug`,
+/// `Display`, `Error` and `defmt::Format` implementations.
+fn error_enum(
+    vis: &syn::Visibility,
+    ident: &Ident,
+    generics: &Generics,
+    doc: &str,
+    variants: &[ErrorVariant],
+) -> TokenStream {
+    let defs = variants.iter().map(|ErrorVariant { ident, ty, .. }| {
+        quote! { #ident(#ty) }
+    });
+    let tys = variants
+        .iter()
+        .map(|variant| &variant.ty)
+        .collect::<Vec<_>>();
+    let debug_arms = variants.iter().map(|ErrorVariant { ident, .. }| {
+        let name = ident.to_string();
+        quote! { Self::#ident(e) => f.debug_tuple(#name).field(e).finish() }
+    });
+    let display_arms = variants.iter().map(|ErrorVariant { ident, message, .. }| {
+        let format = LitStr::new(&format!("{message}: {{}}"), ident.span());
+        quote! { Self::#ident(e) => ::core::write!(f, #format, e) }
+    });
+    let format_arms = variants.iter().map(|ErrorVariant { ident, message, .. }| {
+        let format = LitStr::new(&format!("{message}: {{}}"), ident.span());
+        quote! { Self::#ident(e) => defmt::write!(f, #format, e) }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates = where_clause
+        .map(|where_clause| where_clause.predicates.iter().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    quote! {
+        #[doc = #doc]
+        #vis enum #ident #impl_generics #where_clause {
+            #(#defs,)*
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #(#tys: ::core::fmt::Debug,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#debug_arms,)*
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #(#tys: ::core::fmt::Display,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#display_arms,)*
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::core::error::Error for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #(#tys: ::core::fmt::Debug + ::core::fmt::Display,)*
+        {
+        }
+
+        #[automatically_derived]
+        impl #impl_generics minecrevy_encdec::__private::defmt::Format for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #(#tys: minecrevy_encdec::__private::defmt::Format,)*
+        {
+            fn format(&self, f: minecrevy_encdec::__private::defmt::Formatter<'_>) {
+                use minecrevy_encdec::__private::defmt;
+
+                match self {
+                    #(#format_arms,)*
+                }
+            }
+        }
+    }
+}
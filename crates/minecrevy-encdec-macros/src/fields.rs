@@ -0,0 +1,123 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{DataEnum, Fields, Ident, Member, Type};
+
+use crate::options::Options;
+
+/// A struct field along with its `#[options(...)]` attribute, if any.
+pub struct Field<'a> {
+    /// The name or index used to access the field.
+    pub member: Member,
+    pub ty: &'a Type,
+    pub options: Option<Options>,
+}
+
+impl Field<'_> {
+    /// The identifier of a local variable holding this field's value.
+    pub fn binding(&self) -> Ident {
+        match &self.member {
+            Member::Named(ident) => format_ident!("__{}", unraw(ident)),
+            Member::Unnamed(index) => format_ident!("__field{}", index.index),
+        }
+    }
+
+    /// The name of the error variant for this field, e.g. `ProtocolVersion` for
+    /// `protocol_version` and `Field0` for the first field of a tuple struct.
+    pub fn variant(&self) -> Ident {
+        match &self.member {
+            Member::Named(ident) => Ident::new(&pascal_case(&unraw(ident)), Span::call_site()),
+            Member::Unnamed(index) => format_ident!("Field{}", index.index),
+        }
+    }
+
+    /// The name of the field as written in the source.
+    pub fn name(&self) -> String {
+        match &self.member {
+            Member::Named(ident) => unraw(ident),
+            Member::Unnamed(index) => index.index.to_string(),
+        }
+    }
+}
+
+/// Collects the fields of a struct or enum variant in declaration order.
+pub fn collect(fields: &Fields) -> syn::Result<Vec<Field<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            Ok(Field {
+                member: match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                },
+                ty: &field.ty,
+                options: Options::from_attrs(&field.attrs)?,
+            })
+        })
+        .collect()
+}
+
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_string();
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_owned(),
+        None => name,
+    }
+}
+
+/// Converts a `snake_case` identifier to `PascalCase`.
+pub fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// An enum variant along with its discriminant and fields.
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    /// An expression evaluating to the discriminant of the variant.
+    pub discriminant: TokenStream,
+    pub fields: Vec<Field<'a>>,
+}
+
+impl Variant<'_> {
+    /// A pattern matching this variant and binding each of its fields.
+    pub fn pattern(&self) -> TokenStream {
+        let ident = self.ident;
+        let members = self.fields.iter().map(|field| {
+            let member = &field.member;
+            let binding = field.binding();
+            quote! { #member: #binding }
+        });
+        quote! { Self::#ident { #(#members),* } }
+    }
+}
+
+/// Collects the variants of an enum in declaration order, computing implicit
+/// discriminants the same way the compiler does.
+pub fn collect_variants(data: &DataEnum) -> syn::Result<Vec<Variant<'_>>> {
+    let mut previous: Option<TokenStream> = None;
+    data.variants
+        .iter()
+        .map(|variant| {
+            let discriminant = match (&variant.discriminant, &previous) {
+                (Some((_, expr)), _) => quote! { (#expr) },
+                (None, Some(previous)) => quote! { (#previous + 1) },
+                (None, None) => quote! { 0 },
+            };
+            previous = Some(discriminant.clone());
+            Ok(Variant {
+                ident: &variant.ident,
+                discriminant,
+                fields: collect(&variant.fields)?,
+            })
+        })
+        .collect()
+}
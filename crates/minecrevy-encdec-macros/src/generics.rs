@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use quote::format_ident;
+use syn::{
+    GenericParam, Generics, Ident, Lifetime, Type,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+};
+
+/// Adds a bound on `trait_path` to every type parameter.
+pub fn with_bound(generics: &Generics, trait_path: &TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(#trait_path));
+    }
+    generics
+}
+
+/// Picks an identifier starting with `name` that doesn't clash with any of the
+/// type or const parameters of the deriving type.
+pub fn fresh_ident(generics: &Generics, name: &str) -> Ident {
+    let mut ident = format_ident!("{}", name);
+    while generics.params.iter().any(|param| match param {
+        GenericParam::Type(param) => param.ident == ident,
+        GenericParam::Const(param) => param.ident == ident,
+        GenericParam::Lifetime(_) => false,
+    }) {
+        ident = format_ident!("_{}", ident);
+    }
+    ident
+}
+
+/// Removes the lifetime parameters and makes every other lifetime `'static`.
+///
+/// Errors don't borrow from the value being encoded, so leaving its lifetimes
+/// out of the error type lets the error outlive it.
+pub fn without_lifetimes(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    generics.params = generics
+        .params
+        .into_iter()
+        .filter(|param| !matches!(param, GenericParam::Lifetime(_)))
+        .collect::<Punctuated<_, _>>();
+    StaticLifetimes.visit_generics_mut(&mut generics);
+    generics
+}
+
+/// Makes every lifetime in `ty` `'static`.
+pub fn with_static_lifetimes(ty: &Type) -> Type {
+    let mut ty = ty.clone();
+    StaticLifetimes.visit_type_mut(&mut ty);
+    ty
+}
+
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        *lifetime = syn::parse_quote!('static);
+        visit_mut::visit_lifetime_mut(self, lifetime);
+    }
+}
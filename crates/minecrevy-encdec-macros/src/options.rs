@@ -0,0 +1,128 @@
+use quote::quote;
+use syn::{
+    Attribute, Type,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+};
+
+/// The contents of an `#[options(...)]` attribute, e.g. `#[options(.varint = true)]`.
+pub struct Options(Punctuated<Assignment, syn::Token![,]>);
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self(
+            input.parse_terminated(Assignment::parse, syn::Token![,])?,
+        ))
+    }
+}
+
+impl Options {
+    /// Finds and parses the `#[options(...)]` attribute in the given list, if any.
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Option<Self>> {
+        attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("options"))
+            .map(|attr| attr.parse_args::<Options>())
+            .transpose()
+    }
+
+    /// Builds an expression evaluating to the options of `ty` for the trait
+    /// `trait_path`, starting from the default options and applying every
+    /// assignment in order.
+    pub fn to_tokens(
+        &self,
+        ty: &Type,
+        trait_path: &proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let options = self.0.iter();
+        quote! {
+            {
+                let mut opts = <#ty as #trait_path>::Options::default();
+                #(opts #options ;)*
+                opts
+            }
+        }
+    }
+}
+
+/// A single `.field = value` assignment inside an `#[options(...)]` attribute.
+/// The field may be nested, as in `.element.varint = true`.
+struct Assignment {
+    pub fields: Vec<syn::Ident>,
+    pub value: syn::Expr,
+}
+
+impl Parse for Assignment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut fields = Vec::new();
+        loop {
+            input.parse::<syn::Token![.]>()?;
+            fields.push(input.parse()?);
+            if !input.peek(syn::Token![.]) {
+                break;
+            }
+        }
+        input.parse::<syn::Token![=]>()?;
+        Ok(Assignment {
+            fields,
+            value: input.parse()?,
+        })
+    }
+}
+
+impl quote::ToTokens for Assignment {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let Assignment { fields, value } = self;
+        quote! {
+            #(. #fields)* = #value
+        }
+        .to_tokens(tokens);
+    }
+}
+
+/// Builds the options expression for a field, falling back to the default
+/// options if the field has no `#[options(...)]` attribute.
+pub fn field_options(
+    options: &Option<Options>,
+    ty: &Type,
+    trait_path: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match options {
+        Some(options) => options.to_tokens(ty, trait_path),
+        None => quote! { ::core::default::Default::default() },
+    }
+}
+
+/// The type and options used to encode the discriminant of an enum, given by
+/// an `#[tag(u8)]` or `#[tag(i32, .varint = false)]` attribute.
+///
+/// Defaults to a VarInt-encoded `i32` if the enum has no `#[tag(...)]`
+/// attribute.
+pub struct Tag {
+    pub ty: Type,
+    pub options: Option<Options>,
+}
+
+impl Parse for Tag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty = input.parse()?;
+        let options = if input.parse::<Option<syn::Token![,]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { ty, options })
+    }
+}
+
+impl Tag {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        match attrs.iter().find(|attr| attr.path().is_ident("tag")) {
+            Some(attr) => attr.parse_args(),
+            None => Ok(Self {
+                ty: syn::parse_quote!(i32),
+                options: Some(syn::parse_quote!(.varint = true)),
+            }),
+        }
+    }
+}
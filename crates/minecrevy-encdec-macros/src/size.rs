@@ -0,0 +1,85 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput};
+
+use crate::{
+    fields::{self, Field},
+    generics,
+    options::{Tag, field_options},
+};
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let trait_path = quote! { minecrevy_encdec::WireSize };
+
+    let body = match &input.data {
+        Data::Struct(data_struct) => {
+            let fields = fields::collect(&data_struct.fields)?;
+            let field_sizes = fields.iter().map(|field| {
+                let member = &field.member;
+                field_size(field, quote! { &self.#member }, &trait_path)
+            });
+            quote! {
+                let mut size = 0;
+                #(#field_sizes)*
+                size
+            }
+        }
+        Data::Enum(data_enum) => {
+            let tag = Tag::from_attrs(&input.attrs)?;
+            let tag_ty = &tag.ty;
+            let tag_options = field_options(&tag.options, tag_ty, &trait_path);
+            let variants = fields::collect_variants(data_enum)?;
+            let arms = variants.iter().map(|variant| {
+                let pattern = variant.pattern();
+                let discriminant = &variant.discriminant;
+                let field_sizes = variant.fields.iter().map(|field| {
+                    let binding = field.binding();
+                    field_size(field, quote! { #binding }, &trait_path)
+                });
+                quote! {
+                    #pattern => {
+                        let tag: #tag_ty = #discriminant;
+                        let mut size = tag.wire_size(#tag_options);
+                        #(#field_sizes)*
+                        size
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "WireSize can only be derived for structs and enums",
+            ));
+        }
+    };
+
+    let generics = generics::with_bound(&input.generics, &trait_path);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            type Options = ();
+
+            fn wire_size(&self, (): Self::Options) -> usize {
+                #body
+            }
+        }
+    })
+}
+
+/// Adds the size of the field referenced by the expression `value`.
+fn field_size(field: &Field, value: TokenStream, trait_path: &TokenStream) -> TokenStream {
+    let ty = field.ty;
+    let options = field_options(&field.options, ty, trait_path);
+    quote! {
+        size += <#ty as #trait_path>::wire_size(#value, #options);
+    }
+}
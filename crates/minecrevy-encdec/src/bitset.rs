@@ -0,0 +1,448 @@
+use core::ops::Range;
+
+use defmt::Format;
+
+/// A set of up to `64 * N` bits, sent as a VarInt-prefixed array of longs.
+///
+/// Bit `i` is bit `i % 64` of long `i / 64`. Trailing longs without any bits
+/// set are left out, as vanilla does.
+#[derive(Format, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BitSet<const N: usize> {
+    words: [u64; N],
+}
+
+impl<const N: usize> BitSet<N> {
+    /// The number of bits the set holds.
+    pub const BITS: usize = 64 * N;
+
+    pub const fn new() -> Self {
+        Self { words: [0; N] }
+    }
+
+    pub const fn from_words(words: [u64; N]) -> Self {
+        Self { words }
+    }
+
+    pub const fn words(&self) -> &[u64; N] {
+        &self.words
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn get(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn set(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn unset(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    /// Sets every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range ends past [`Self::BITS`].
+    pub const fn set_range(&mut self, range: Range<usize>) {
+        let mut start = range.start;
+        while start < range.end {
+            let (word, mask) = word_mask(start, range.end, 64);
+            self.words[word] |= mask;
+            start = (word + 1) * 64;
+        }
+    }
+
+    /// Unsets every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range ends past [`Self::BITS`].
+    pub const fn unset_range(&mut self, range: Range<usize>) {
+        let mut start = range.start;
+        while start < range.end {
+            let (word, mask) = word_mask(start, range.end, 64);
+            self.words[word] &= !mask;
+            start = (word + 1) * 64;
+        }
+    }
+
+    pub const fn clear(&mut self) {
+        self.words = [0; N];
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the indices of the set bits, in ascending order.
+    pub fn ones(&self) -> Ones<'_> {
+        let (&current, rest) = self.words.split_first().unwrap_or((&0, &[]));
+        Ones {
+            current,
+            rest,
+            offset: 0,
+        }
+    }
+
+    /// The longs that are sent, without the trailing empty ones.
+    pub(crate) fn trimmed(&self) -> &[u64] {
+        let len = self
+            .words
+            .iter()
+            .rposition(|&word| word != 0)
+            .map_or(0, |last| last + 1);
+        &self.words[..len]
+    }
+}
+
+impl<const N: usize> Default for BitSet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FromIterator<usize> for BitSet<N> {
+    /// # Panics
+    ///
+    /// If an index is not below [`Self::BITS`].
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for index in iter {
+            set.set(index);
+        }
+        set
+    }
+}
+
+/// A set of `8 * N` bits, sent as `N` bytes without a length prefix.
+///
+/// Bit `i` is bit `i % 8` of byte `i / 8`.
+#[derive(Format, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FixedBitSet<const N: usize> {
+    bytes: [u8; N],
+}
+
+impl<const N: usize> FixedBitSet<N> {
+    /// The number of bits the set holds.
+    pub const BITS: usize = 8 * N;
+
+    pub const fn new() -> Self {
+        Self { bytes: [0; N] }
+    }
+
+    pub const fn from_bytes(bytes: [u8; N]) -> Self {
+        Self { bytes }
+    }
+
+    pub const fn bytes(&self) -> &[u8; N] {
+        &self.bytes
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn get(&self, index: usize) -> bool {
+        self.bytes[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn set(&mut self, index: usize) {
+        self.bytes[index / 8] |= 1 << (index % 8);
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`Self::BITS`].
+    pub const fn unset(&mut self, index: usize) {
+        self.bytes[index / 8] &= !(1 << (index % 8));
+    }
+
+    /// Sets every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range ends past [`Self::BITS`].
+    pub const fn set_range(&mut self, range: Range<usize>) {
+        let mut start = range.start;
+        while start < range.end {
+            let (byte, mask) = word_mask(start, range.end, 8);
+            self.bytes[byte] |= mask as u8;
+            start = (byte + 1) * 8;
+        }
+    }
+
+    /// Unsets every bit in `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the range ends past [`Self::BITS`].
+    pub const fn unset_range(&mut self, range: Range<usize>) {
+        let mut start = range.start;
+        while start < range.end {
+            let (byte, mask) = word_mask(start, range.end, 8);
+            self.bytes[byte] &= !(mask as u8);
+            start = (byte + 1) * 8;
+        }
+    }
+
+    pub const fn clear(&mut self) {
+        self.bytes = [0; N];
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.iter().all(|&byte| byte == 0)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bytes
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the indices of the set bits, in ascending order.
+    pub fn ones(&self) -> FixedOnes<'_> {
+        let (&current, rest) = self.bytes.split_first().unwrap_or((&0, &[]));
+        FixedOnes {
+            current,
+            rest,
+            offset: 0,
+        }
+    }
+}
+
+impl<const N: usize> Default for FixedBitSet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FromIterator<usize> for FixedBitSet<N> {
+    /// # Panics
+    ///
+    /// If an index is not below [`Self::BITS`].
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for index in iter {
+            set.set(index);
+        }
+        set
+    }
+}
+
+/// The indices of the set bits of a [`BitSet`].
+#[derive(Clone, Debug)]
+pub struct Ones<'a> {
+    /// The bits of the current long that are still to be returned.
+    current: u64,
+    rest: &'a [u64],
+    /// The index of the first bit of `current`.
+    offset: usize,
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            let (&next, rest) = self.rest.split_first()?;
+            self.current = next;
+            self.rest = rest;
+            self.offset += 64;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.offset + bit)
+    }
+}
+
+/// The indices of the set bits of a [`FixedBitSet`].
+#[derive(Clone, Debug)]
+pub struct FixedOnes<'a> {
+    /// The bits of the current byte that are still to be returned.
+    current: u8,
+    rest: &'a [u8],
+    /// The index of the first bit of `current`.
+    offset: usize,
+}
+
+impl Iterator for FixedOnes<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            let (&next, rest) = self.rest.split_first()?;
+            self.current = next;
+            self.rest = rest;
+            self.offset += 8;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.offset + bit)
+    }
+}
+
+/// The index of the word of `width` bits that holds bit `start`, and the mask
+/// of its bits from `start` up to `end` or the end of the word.
+const fn word_mask(start: usize, end: usize, width: usize) -> (usize, u64) {
+    let word = start / width;
+    let low = start % width;
+    let high = if end - word * width < width {
+        end - word * width
+    } else {
+        width
+    };
+    let mask = (u64::MAX >> (64 - (high - low))) << low;
+    (word, mask)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use embedded_byteorder::ReadExactError;
+
+    use super::*;
+    use crate::{
+        AsyncDecode, Decode, ReadMinecraftError,
+        test_util::{block_on, encode},
+    };
+
+    #[test]
+    fn set_range_spans_words() {
+        let mut set = BitSet::<3>::new();
+        set.set_range(60..130);
+        assert_eq!(set.words(), &[0xF << 60, u64::MAX, 0b11]);
+        assert_eq!(set.count_ones(), 70);
+
+        set.unset_range(62..66);
+        assert_eq!(set.words(), &[0x3 << 60, !0b11, 0b11]);
+        assert!(!set.get(63) && !set.get(64) && set.get(66));
+
+        // An empty range touches nothing.
+        set.set_range(5..5);
+        set.unset_range(100..100);
+        assert_eq!(set.count_ones(), 66);
+    }
+
+    #[test]
+    fn set_range_reaches_the_last_bit() {
+        let mut set = BitSet::<3>::new();
+        set.set_range(191..192);
+        assert_eq!(set.words(), &[0, 0, 1 << 63]);
+        assert!(set.get(BitSet::<3>::BITS - 1));
+
+        set.set_range(0..192);
+        assert_eq!(set.words(), &[u64::MAX; 3]);
+        set.unset_range(0..192);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn ones_walks_every_word() {
+        let indices = [0, 1, 63, 64, 130, 191];
+        let set: BitSet<3> = indices.into_iter().collect();
+        assert_eq!(set.ones().collect::<Vec<_>>(), indices);
+        assert_eq!(BitSet::<3>::new().ones().next(), None);
+        assert_eq!(BitSet::<0>::new().ones().next(), None);
+
+        // Empty words in the middle and at the end are skipped.
+        let set: BitSet<4> = [129].into_iter().collect();
+        assert_eq!(set.ones().collect::<Vec<_>>(), [129]);
+    }
+
+    #[test]
+    fn encode_trims_trailing_empty_longs() {
+        assert_eq!(encode(&BitSet::<4>::new()), [0]);
+
+        let set: BitSet<4> = [70].into_iter().collect();
+        assert_eq!(
+            encode(&set),
+            [2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x40]
+        );
+
+        // Only the trailing empty longs are left out.
+        let set: BitSet<4> = [0, 128].into_iter().collect();
+        let bytes = encode(&set);
+        assert_eq!(bytes[0], 3);
+        assert_eq!(bytes.len(), 1 + 3 * 8);
+    }
+
+    #[test]
+    fn decode_fills_the_missing_longs() {
+        let bytes = [1, 0, 0, 0, 0, 0, 0, 0, 0x05];
+        let mut reader = &bytes[..];
+        let set = <BitSet<3> as Decode>::decode(&mut reader, ()).unwrap();
+        assert_eq!(set.words(), &[5, 0, 0]);
+
+        let mut reader = &bytes[..];
+        let set = block_on(<BitSet<3> as AsyncDecode>::decode(&mut reader, ())).unwrap();
+        assert_eq!(set.words(), &[5, 0, 0]);
+    }
+
+    #[test]
+    fn decode_rejects_too_many_longs() {
+        // Four longs for a set of two, and a negative length.
+        for bytes in [&[4][..], &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]] {
+            let mut reader = bytes;
+            let error = <BitSet<2> as Decode>::decode(&mut reader, ()).unwrap_err();
+            assert!(matches!(error, ReadMinecraftError::LengthExceeded));
+
+            let mut reader = bytes;
+            let error = block_on(<BitSet<2> as AsyncDecode>::decode(&mut reader, ())).unwrap_err();
+            assert!(matches!(error, ReadMinecraftError::LengthExceeded));
+        }
+    }
+
+    #[test]
+    fn fixed_set_range_spans_bytes() {
+        let mut set = FixedBitSet::<3>::new();
+        set.set_range(5..19);
+        assert_eq!(set.bytes(), &[0xE0, 0xFF, 0x07]);
+        assert_eq!(set.ones().collect::<Vec<_>>(), (5..19).collect::<Vec<_>>());
+
+        set.unset_range(7..9);
+        assert_eq!(set.bytes(), &[0x60, 0xFE, 0x07]);
+
+        set.set_range(23..24);
+        assert_eq!(set.bytes()[2], 0x87);
+        assert!(set.get(FixedBitSet::<3>::BITS - 1));
+    }
+
+    #[test]
+    fn fixed_set_is_sent_as_its_bytes() {
+        // No length prefix, and no trimming of the empty bytes.
+        let set: FixedBitSet<3> = [0, 9].into_iter().collect();
+        assert_eq!(encode(&set), [0x01, 0x02, 0x00]);
+        assert_eq!(encode(&FixedBitSet::<3>::new()), [0, 0, 0]);
+
+        let mut reader = &[0x01, 0x02, 0x00][..];
+        assert_eq!(<FixedBitSet<3> as Decode>::decode(&mut reader, ()), Ok(set));
+        assert!(reader.is_empty());
+
+        let mut reader = &[0x01, 0x02][..];
+        let error = block_on(<FixedBitSet<3> as AsyncDecode>::decode(&mut reader, ())).unwrap_err();
+        assert!(matches!(error, ReadExactError::UnexpectedEof));
+    }
+}
@@ -0,0 +1,371 @@
+//! Packet compression, which the server turns on during login with the Set
+//! Compression packet.
+//!
+//! Once it is on, every packet is framed as its length, the length of its
+//! uncompressed ID and body (or 0 if they aren't compressed) and then the ID
+//! and body, compressed with zlib if they are at least as long as the
+//! threshold.
+//!
+//! Both directions use fixed-size windows rather than zlib's 32 KiB, see
+//! [`INFLATE_WINDOW_SIZE`] and [`DEFLATE_WINDOW_SIZE`].
+
+use core::marker::PhantomData;
+
+use defmt::Format;
+use embedded_byteorder::{AsyncRead, AsyncWrite, Error, ErrorType};
+
+use crate::{ReadMinecraftError, ReadPacketError};
+
+mod deflate;
+mod inflate;
+
+pub use self::{
+    deflate::DEFLATE_WINDOW_SIZE,
+    inflate::{INFLATE_WINDOW_SIZE, InflateError},
+};
+pub(crate) use self::{
+    deflate::Deflater,
+    inflate::{Inflater, Source},
+};
+
+/// Whether packets are compressed.
+#[derive(Format, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Compression {
+    #[default]
+    Disabled,
+    /// Packets whose ID and body take at least `threshold` bytes are
+    /// compressed.
+    Enabled { threshold: usize },
+}
+
+impl Compression {
+    /// Converts the threshold sent in Set Compression, where a negative
+    /// threshold disables compression.
+    pub fn from_threshold(threshold: i32) -> Self {
+        match usize::try_from(threshold) {
+            Ok(threshold) => Compression::Enabled { threshold },
+            Err(_) => Compression::Disabled,
+        }
+    }
+}
+
+/// The length of the shortest back-reference with each length symbol.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+/// The number of extra bits after each length symbol.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// The shortest distance with each distance symbol.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// The number of extra bits after each distance symbol.
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The checksum at the end of a zlib stream.
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    const MODULUS: u32 = 65521;
+
+    const fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        // The sums can't overflow within this many bytes.
+        for chunk in data.chunks(5552) {
+            for &byte in chunk {
+                self.a += u32::from(byte);
+                self.b += self.a;
+            }
+            self.a %= Self::MODULUS;
+            self.b %= Self::MODULUS;
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+/// The part of a packet frame that [`PacketReader`] hasn't read yet.
+pub(crate) struct Frame {
+    /// The number of bytes of the frame left in the reader.
+    pub(crate) remaining: usize,
+    /// The length of the ID and body once decompressed.
+    pub(crate) data_len: usize,
+    pub(crate) inflater: Option<Inflater>,
+}
+
+impl Frame {
+    pub(crate) fn uncompressed(len: usize) -> Self {
+        Self {
+            remaining: len,
+            data_len: 0,
+            inflater: None,
+        }
+    }
+
+    pub(crate) fn compressed(len: usize, data_len: usize) -> Self {
+        Self {
+            remaining: len,
+            data_len,
+            inflater: Some(Inflater::new()),
+        }
+    }
+
+    /// The error that stopped decompression early, if any.
+    pub(crate) fn error(&self) -> Option<InflateError> {
+        self.inflater.as_ref().and_then(Inflater::error)
+    }
+
+    /// Skips what is left of the frame, so that the next packet can be read,
+    /// and checks that the compressed data was intact.
+    pub(crate) async fn finish<R: AsyncRead + ?Sized, E>(
+        &mut self,
+        reader: &mut R,
+    ) -> Result<(), ReadPacketError<R::Error, E>> {
+        let mut scratch = [0; 64];
+        if let Some(inflater) = &mut self.inflater {
+            let mut source = Source {
+                reader: &mut *reader,
+                remaining: &mut self.remaining,
+            };
+            while inflater
+                .read(&mut source, &mut scratch)
+                .await
+                .map_err(|e| ReadPacketError::Skip(e.into()))?
+                != 0
+            {}
+            if let Some(e) = inflater.error() {
+                return Err(ReadPacketError::Decompress(e));
+            }
+            if inflater.total() != self.data_len {
+                return Err(ReadPacketError::Decompress(InflateError::SizeMismatch));
+            }
+        }
+
+        while self.remaining > 0 {
+            let len = self.remaining.min(scratch.len());
+            reader
+                .read_exact(&mut scratch[..len])
+                .await
+                .map_err(|e| ReadPacketError::Skip(ReadMinecraftError::from(e)))?;
+            self.remaining -= len;
+        }
+        Ok(())
+    }
+}
+
+/// The ID and body of a packet, which
+/// [`read_packet`](crate::AsyncReadMinecraftExt::read_packet) reads from.
+///
+/// It ends where the packet ends and decompresses the packet if it is
+/// compressed. Compressed data that turns out to be invalid also ends it, and
+/// `read_packet` then reports why.
+pub struct PacketReader<'p, R: ?Sized> {
+    pub(crate) reader: &'p mut R,
+    pub(crate) frame: &'p mut Frame,
+}
+
+impl<R: AsyncRead + ?Sized> ErrorType for PacketReader<'_, R> {
+    type Error = R::Error;
+}
+
+impl<R: AsyncRead + ?Sized> AsyncRead for PacketReader<'_, R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frame = &mut *self.frame;
+        match &mut frame.inflater {
+            None => {
+                let len = buf.len().min(frame.remaining);
+                if len == 0 {
+                    return Ok(0);
+                }
+                let n = self.reader.read(&mut buf[..len]).await?;
+                frame.remaining -= n;
+                Ok(n)
+            }
+            Some(inflater) => {
+                let len = buf
+                    .len()
+                    .min(frame.data_len.saturating_sub(inflater.total()));
+                if len == 0 {
+                    return Ok(0);
+                }
+                let mut source = Source {
+                    reader: &mut *self.reader,
+                    remaining: &mut frame.remaining,
+                };
+                inflater.read(&mut source, &mut buf[..len]).await
+            }
+        }
+    }
+}
+
+/// An [`AsyncWrite`] that compresses what is written to it.
+pub(crate) struct DeflateWriter<'d, W: ?Sized> {
+    pub(crate) deflater: &'d mut Deflater,
+    pub(crate) writer: &'d mut W,
+}
+
+impl<W: AsyncWrite + ?Sized> DeflateWriter<'_, W> {
+    /// Compresses the rest of the input and ends the stream.
+    pub(crate) async fn finish(&mut self) -> Result<(), W::Error> {
+        while !self.deflater.is_finished() {
+            self.deflater.compress(true);
+            self.drain().await?;
+        }
+        Ok(())
+    }
+
+    async fn drain(&mut self) -> Result<(), W::Error> {
+        self.writer.write_all(self.deflater.output()).await?;
+        self.deflater.clear_output();
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite + ?Sized> ErrorType for DeflateWriter<'_, W> {
+    type Error = W::Error;
+}
+
+impl<W: AsyncWrite + ?Sized> AsyncWrite for DeflateWriter<'_, W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.deflater.input(buf);
+            self.deflater.compress(false);
+            self.drain().await?;
+            if n > 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// An [`AsyncWrite`] that only counts the bytes written to it, standing in
+/// for a writer with errors of type `E`.
+pub(crate) struct Counter<E> {
+    count: usize,
+    _error: PhantomData<E>,
+}
+
+impl<E> Counter<E> {
+    pub(crate) fn new() -> Self {
+        Self {
+            count: 0,
+            _error: PhantomData,
+        }
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<E: Error> ErrorType for Counter<E> {
+    type Error = E;
+}
+
+impl<E: Error> AsyncWrite for Counter<E> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use super::inflate::tests::FAR_BACK_REFERENCE;
+    use super::*;
+    use crate::{AsyncReadMinecraftExt, AsyncWriteMinecraftExt, RawPacket, test_util::block_on};
+
+    const COMPRESSED: Compression = Compression::Enabled { threshold: 256 };
+
+    /// Writes `data` as the body of a packet and reads it back, returning the
+    /// length of the frame.
+    fn round_trip(data: &[u8]) -> usize {
+        let packet = RawPacket::<8192> {
+            id: 0x2a,
+            data: Vec::from_slice(data).unwrap(),
+        };
+        let mut buf = [0; 16384];
+        let mut writer = &mut buf[..];
+        block_on(writer.write_raw_packet(COMPRESSED, packet.clone())).unwrap();
+        let len = 16384 - writer.len();
+
+        let mut reader = &buf[..len];
+        let read = block_on(reader.read_raw_packet::<8192>(COMPRESSED)).unwrap();
+        assert_eq!(read, packet);
+        assert!(reader.is_empty());
+        len
+    }
+
+    #[test]
+    fn round_trips_below_threshold() {
+        let len = round_trip(b"short");
+        // Length, a data length of 0, the ID and the body.
+        assert_eq!(len, 1 + 1 + 1 + 5);
+    }
+
+    #[test]
+    fn round_trips_compressible() {
+        let mut data = [0; 6000];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = b"0123456789abcdef"[(i % 16) ^ (i / 512 % 16)];
+        }
+        let len = round_trip(&data);
+        assert!(len < data.len() / 4);
+    }
+
+    #[test]
+    fn round_trips_incompressible() {
+        let mut data = [0; 3000];
+        let mut state = 0x1234_5678_u32;
+        for byte in &mut data {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            *byte = state as u8;
+        }
+        round_trip(&data);
+    }
+
+    #[test]
+    fn read_rejects_back_reference_past_window() {
+        // The length, a data length of 5042 and the zlib stream.
+        let mut frame = Vec::<u8, 64>::from_slice(&[58, 0xb2, 0x27]).unwrap();
+        frame.extend_from_slice(&FAR_BACK_REFERENCE).unwrap();
+
+        let mut reader = &frame[..];
+        let result = block_on(reader.read_raw_packet::<8192>(COMPRESSED));
+        assert_eq!(
+            result,
+            Err(ReadPacketError::Decompress(InflateError::DistanceTooFar))
+        );
+    }
+}
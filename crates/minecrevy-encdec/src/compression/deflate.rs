@@ -0,0 +1,216 @@
+use super::{Adler32, DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+/// How far back the deflater looks for repeated bytes.
+///
+/// A longer window finds more matches, but the deflater keeps twice this many
+/// bytes of input.
+pub const DEFLATE_WINDOW_SIZE: usize = 2048;
+
+const BUFFER_SIZE: usize = 2 * DEFLATE_WINDOW_SIZE;
+const HASH_BITS: u32 = 10;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// The most bytes one literal or back-reference can take, including what is
+/// left over from the previous one.
+const MAX_SYMBOL_SIZE: usize = 8;
+
+/// A streaming zlib compressor with a fixed-size window.
+///
+/// It finds repeats with a single-entry hash table and encodes everything as
+/// one block with the fixed Huffman codes, which compresses worse than zlib
+/// but needs no per-block statistics. Feed it with [`input`](Self::input),
+/// run [`compress`](Self::compress) and drain [`output`](Self::output).
+pub(crate) struct Deflater {
+    buf: [u8; BUFFER_SIZE],
+    len: usize,
+    /// The position of the first byte that hasn't been compressed yet.
+    pos: usize,
+    /// The position plus one of the last three bytes with each hash, or 0.
+    head: [u16; 1 << HASH_BITS],
+    bits: u64,
+    bit_count: u32,
+    out: [u8; 64],
+    out_len: usize,
+    adler: Adler32,
+    finished: bool,
+}
+
+impl Deflater {
+    pub(crate) const fn new() -> Self {
+        let mut deflater = Self {
+            buf: [0; BUFFER_SIZE],
+            len: 0,
+            pos: 0,
+            head: [0; 1 << HASH_BITS],
+            bits: 0,
+            bit_count: 0,
+            out: [0; 64],
+            out_len: 0,
+            adler: Adler32::new(),
+            finished: false,
+        };
+        // The zlib header says deflate with a 32 KiB window and the fastest
+        // compression level, followed by the header of the one and only block:
+        // final, fixed Huffman codes.
+        deflater.out[0] = 0x78;
+        deflater.out[1] = 0x01;
+        deflater.out_len = 2;
+        deflater.put_bits(0b011, 3);
+        deflater
+    }
+
+    /// Takes as much of `data` as fits, returning how many bytes were taken.
+    pub(crate) fn input(&mut self, data: &[u8]) -> usize {
+        if self.len == BUFFER_SIZE {
+            // Forget the input that is too far back to be matched.
+            let shift = self.pos.saturating_sub(DEFLATE_WINDOW_SIZE);
+            self.buf.copy_within(shift..self.len, 0);
+            self.len -= shift;
+            self.pos -= shift;
+            for head in &mut self.head {
+                *head = head.saturating_sub(shift as u16);
+            }
+        }
+
+        let n = data.len().min(BUFFER_SIZE - self.len);
+        self.buf[self.len..][..n].copy_from_slice(&data[..n]);
+        self.adler.update(&data[..n]);
+        self.len += n;
+        n
+    }
+
+    /// Compresses the input until the output is full or, unless `finish` is
+    /// set, there is too little input left to find the longest match.
+    ///
+    /// When `finish` is set and all input has been compressed, the stream is
+    /// ended.
+    pub(crate) fn compress(&mut self, finish: bool) {
+        while !self.finished && self.out_len + MAX_SYMBOL_SIZE <= self.out.len() {
+            let lookahead = self.len - self.pos;
+            if lookahead == 0 || (!finish && lookahead < MAX_MATCH) {
+                break;
+            }
+
+            let (len, distance) = self.find_match(lookahead);
+            if len >= MIN_MATCH {
+                self.put_length(len);
+                self.put_distance(distance);
+                // Remember the bytes inside the match too, to find later
+                // repeats of them.
+                for pos in self.pos + 1..(self.pos + len).min(self.len - MIN_MATCH + 1) {
+                    self.head[hash(&self.buf[pos..])] = pos as u16 + 1;
+                }
+                self.pos += len;
+            } else {
+                self.put_literal(self.buf[self.pos]);
+                self.pos += 1;
+            }
+        }
+
+        if finish
+            && !self.finished
+            && self.pos == self.len
+            && self.out_len + MAX_SYMBOL_SIZE <= self.out.len()
+        {
+            self.finish();
+        }
+    }
+
+    /// Whether the stream has been ended, though its output may not have been
+    /// drained yet.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The compressed bytes that are ready to be written.
+    pub(crate) fn output(&self) -> &[u8] {
+        &self.out[..self.out_len]
+    }
+
+    pub(crate) fn clear_output(&mut self) {
+        self.out_len = 0;
+    }
+
+    fn find_match(&mut self, lookahead: usize) -> (usize, usize) {
+        if lookahead < MIN_MATCH {
+            return (0, 0);
+        }
+        let head = &mut self.head[hash(&self.buf[self.pos..])];
+        let candidate = usize::from(*head);
+        *head = self.pos as u16 + 1;
+        if candidate == 0 {
+            return (0, 0);
+        }
+
+        let candidate = candidate - 1;
+        let max = lookahead.min(MAX_MATCH);
+        let len = self.buf[candidate..]
+            .iter()
+            .zip(&self.buf[self.pos..][..max])
+            .take_while(|(a, b)| a == b)
+            .count();
+        (len, self.pos - candidate)
+    }
+
+    fn finish(&mut self) {
+        // End of block, then the checksum on a byte boundary.
+        self.put_code(0, 7);
+        self.put_bits(0, (8 - self.bit_count % 8) % 8);
+        for byte in self.adler.finish().to_be_bytes() {
+            self.put_bits(byte.into(), 8);
+        }
+        self.finished = true;
+    }
+
+    fn put_literal(&mut self, byte: u8) {
+        match byte {
+            0..144 => self.put_code(0x30 + u32::from(byte), 8),
+            _ => self.put_code(0x190 + u32::from(byte - 144), 9),
+        }
+    }
+
+    fn put_length(&mut self, len: usize) {
+        let index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= len)
+            .unwrap();
+        match 257 + index as u32 {
+            symbol @ 257..280 => self.put_code(symbol - 256, 7),
+            symbol => self.put_code(0xc0 + symbol - 280, 8),
+        }
+        let extra = len - usize::from(LENGTH_BASE[index]);
+        self.put_bits(extra as u32, LENGTH_EXTRA[index].into());
+    }
+
+    fn put_distance(&mut self, distance: usize) {
+        let index = DIST_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= distance)
+            .unwrap();
+        self.put_code(index as u32, 5);
+        let extra = distance - usize::from(DIST_BASE[index]);
+        self.put_bits(extra as u32, DIST_EXTRA[index].into());
+    }
+
+    /// Huffman codes are packed starting from their most significant bit.
+    fn put_code(&mut self, code: u32, len: u32) {
+        self.put_bits(code.reverse_bits() >> (32 - len), len);
+    }
+
+    /// Everything else is packed starting from its least significant bit.
+    const fn put_bits(&mut self, value: u32, len: u32) {
+        self.bits |= (value as u64) << self.bit_count;
+        self.bit_count += len;
+        while self.bit_count >= 8 {
+            self.out[self.out_len] = self.bits as u8;
+            self.out_len += 1;
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+    (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
@@ -0,0 +1,588 @@
+//! A zlib decompressor for serverbound packets that keeps only a
+//! [`INFLATE_WINDOW_SIZE`] window of output instead of zlib's 32 KiB.
+//!
+//! Vanilla clients compress with the full 32 KiB window, so a packet that
+//! decompresses to more than 4 KiB, such as a book edit or a large plugin
+//! message, can refer back further than the window reaches and then fails
+//! with [`InflateError::DistanceTooFar`].
+
+use defmt::Format;
+use embedded_byteorder::AsyncRead;
+use thiserror::Error;
+
+use super::{Adler32, DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+/// How much decompressed output the inflater keeps for back-references.
+///
+/// zlib lets a back-reference reach 32 KiB back, but keeping that much per
+/// connection doesn't fit in a microcontroller's RAM. Packets that decompress
+/// to at most this many bytes always inflate, and larger ones do as long as
+/// the sender's back-references don't reach further than this.
+pub const INFLATE_WINDOW_SIZE: usize = 4096;
+
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InflateError {
+    #[error("invalid zlib header")]
+    InvalidHeader,
+    #[error("invalid block type")]
+    InvalidBlockType,
+    #[error("invalid stored block length")]
+    InvalidStoredLength,
+    #[error("invalid Huffman code")]
+    InvalidCode,
+    #[error("back-reference outside the window")]
+    DistanceTooFar,
+    #[error("checksum mismatch")]
+    ChecksumMismatch,
+    #[error("decompressed size doesn't match the data length")]
+    SizeMismatch,
+    #[error("unexpected end of compressed data")]
+    UnexpectedEof,
+}
+
+/// Why inflating stopped.
+enum Stop<E> {
+    Read(E),
+    Inflate(InflateError),
+}
+
+impl<E> From<InflateError> for Stop<E> {
+    fn from(value: InflateError) -> Self {
+        Stop::Inflate(value)
+    }
+}
+
+const MAX_BITS: usize = 15;
+const LITLEN_CODES: usize = 288;
+const DIST_CODES: usize = 32;
+const CODE_LENGTH_CODES: usize = 19;
+/// The order in which a dynamic block lists the code length code lengths.
+const CODE_LENGTH_ORDER: [usize; CODE_LENGTH_CODES] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// A canonical Huffman code, decoded one bit at a time.
+///
+/// Slower than a lookup table, but it only takes a few hundred bytes.
+struct Huffman<const N: usize> {
+    /// The number of codes of each length.
+    counts: [u16; MAX_BITS + 1],
+    /// The symbols, ordered by code.
+    symbols: [u16; N],
+}
+
+impl<const N: usize> Huffman<N> {
+    const fn empty() -> Self {
+        Self {
+            counts: [0; MAX_BITS + 1],
+            symbols: [0; N],
+        }
+    }
+
+    /// Builds the code from the code length of each symbol, where 0 means the
+    /// symbol isn't used.
+    fn build(&mut self, lengths: &[u8]) -> Result<(), InflateError> {
+        self.counts = [0; MAX_BITS + 1];
+        for &len in lengths {
+            self.counts[usize::from(len)] += 1;
+        }
+
+        // Incomplete codes are allowed, but more codes than fit are not.
+        let mut left = 1i32;
+        for &count in &self.counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(InflateError::InvalidCode);
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + self.counts[len];
+        }
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                let offset = &mut offsets[usize::from(len)];
+                self.symbols[usize::from(*offset)] = symbol as u16;
+                *offset += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+enum State {
+    Header,
+    BlockHeader,
+    Stored { len: u16 },
+    Codes,
+    Copy { len: u16, distance: u16 },
+    Trailer,
+    Done,
+}
+
+/// Where an [`Inflater`] reads compressed bytes from.
+pub(crate) struct Source<'s, R: ?Sized> {
+    pub(crate) reader: &'s mut R,
+    /// The number of compressed bytes left in `reader`.
+    pub(crate) remaining: &'s mut usize,
+}
+
+/// Reads the bits of a deflate stream, least significant bit first.
+struct BitReader {
+    input: [u8; 64],
+    pos: usize,
+    len: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader {
+    const fn new() -> Self {
+        Self {
+            input: [0; 64],
+            pos: 0,
+            len: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    /// Takes the next `n` bits, where `n` is at most 16.
+    async fn take<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+        n: u32,
+    ) -> Result<u32, Stop<R::Error>> {
+        while self.count < n {
+            if self.pos == self.len {
+                let len = self.input.len().min(*source.remaining);
+                if len == 0 {
+                    return Err(InflateError::UnexpectedEof.into());
+                }
+                let read = source
+                    .reader
+                    .read(&mut self.input[..len])
+                    .await
+                    .map_err(Stop::Read)?;
+                if read == 0 {
+                    return Err(InflateError::UnexpectedEof.into());
+                }
+                *source.remaining -= read;
+                self.pos = 0;
+                self.len = read;
+            }
+            self.bits |= u32::from(self.input[self.pos]) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+
+        let value = self.bits & ((1 << n) - 1);
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Skips to the next byte boundary.
+    async fn align<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+    ) -> Result<(), Stop<R::Error>> {
+        self.take(source, self.count % 8).await?;
+        Ok(())
+    }
+
+    async fn decode<R: AsyncRead + ?Sized, const N: usize>(
+        &mut self,
+        source: &mut Source<'_, R>,
+        huffman: &Huffman<N>,
+    ) -> Result<u16, Stop<R::Error>> {
+        // Codes of the same length are consecutive numbers, so reading one bit
+        // at a time, the code is complete once it is below the first code of
+        // its length plus the number of codes of that length.
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+        for &count in &huffman.counts[1..] {
+            code |= self.take(source, 1).await? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(InflateError::InvalidCode.into())
+    }
+}
+
+/// A streaming zlib decompressor that reads its input on demand and only
+/// remembers the last [`INFLATE_WINDOW_SIZE`] bytes of output.
+pub(crate) struct Inflater {
+    state: State,
+    last_block: bool,
+    window: [u8; INFLATE_WINDOW_SIZE],
+    /// The number of bytes decompressed so far.
+    total: usize,
+    bits: BitReader,
+    litlen: Huffman<LITLEN_CODES>,
+    dist: Huffman<DIST_CODES>,
+    adler: Adler32,
+    error: Option<InflateError>,
+}
+
+impl Inflater {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: State::Header,
+            last_block: false,
+            window: [0; INFLATE_WINDOW_SIZE],
+            total: 0,
+            bits: BitReader::new(),
+            litlen: Huffman::empty(),
+            dist: Huffman::empty(),
+            adler: Adler32::new(),
+            error: None,
+        }
+    }
+
+    /// The number of bytes decompressed so far.
+    pub(crate) fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The error that stopped decompression, if any.
+    pub(crate) fn error(&self) -> Option<InflateError> {
+        self.error
+    }
+
+    /// Decompresses into `out`.
+    ///
+    /// Like a reader, it returns 0 at the end of the stream. Invalid data also
+    /// ends the stream early, and [`error`](Self::error) says why.
+    pub(crate) async fn read<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+        out: &mut [u8],
+    ) -> Result<usize, R::Error> {
+        if self.error.is_some() {
+            return Ok(0);
+        }
+        let mut len = 0;
+        match self.inflate(source, out, &mut len).await {
+            Ok(()) => Ok(len),
+            Err(Stop::Read(e)) => Err(e),
+            Err(Stop::Inflate(e)) => {
+                self.error = Some(e);
+                Ok(len)
+            }
+        }
+    }
+
+    async fn inflate<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+        out: &mut [u8],
+        len: &mut usize,
+    ) -> Result<(), Stop<R::Error>> {
+        while *len < out.len() {
+            match self.state {
+                State::Header => {
+                    let cmf = self.bits.take(source, 8).await?;
+                    let flg = self.bits.take(source, 8).await?;
+                    // Deflate with a window of at most 32 KiB and no preset
+                    // dictionary.
+                    if cmf & 0x0f != 8 || cmf >> 4 > 7 || flg & 0x20 != 0 {
+                        return Err(InflateError::InvalidHeader.into());
+                    }
+                    if ((cmf << 8) | flg) % 31 != 0 {
+                        return Err(InflateError::InvalidHeader.into());
+                    }
+                    self.state = State::BlockHeader;
+                }
+                State::BlockHeader if self.last_block => self.state = State::Trailer,
+                State::BlockHeader => {
+                    self.last_block = self.bits.take(source, 1).await? == 1;
+                    self.state = match self.bits.take(source, 2).await? {
+                        0 => self.stored_header(source).await?,
+                        1 => {
+                            self.fixed_codes()?;
+                            State::Codes
+                        }
+                        2 => {
+                            self.dynamic_codes(source).await?;
+                            State::Codes
+                        }
+                        _ => return Err(InflateError::InvalidBlockType.into()),
+                    };
+                }
+                State::Stored { len: 0 } => self.state = State::BlockHeader,
+                State::Stored { len: stored_len } => {
+                    let byte = self.bits.take(source, 8).await? as u8;
+                    self.push(byte, out, len);
+                    self.state = State::Stored {
+                        len: stored_len - 1,
+                    };
+                }
+                State::Codes => match self.bits.decode(source, &self.litlen).await? {
+                    symbol @ 0..256 => self.push(symbol as u8, out, len),
+                    256 => self.state = State::BlockHeader,
+                    symbol => self.state = self.back_reference(source, symbol).await?,
+                },
+                State::Copy { len: 0, .. } => self.state = State::Codes,
+                State::Copy {
+                    len: copy_len,
+                    distance,
+                } => {
+                    let index = (self.total - usize::from(distance)) % INFLATE_WINDOW_SIZE;
+                    self.push(self.window[index], out, len);
+                    self.state = State::Copy {
+                        len: copy_len - 1,
+                        distance,
+                    };
+                }
+                State::Trailer => {
+                    // The Adler-32 checksum starts at the next byte boundary.
+                    self.bits.align(source).await?;
+                    let mut checksum = 0;
+                    for _ in 0..4 {
+                        checksum = (checksum << 8) | self.bits.take(source, 8).await?;
+                    }
+                    if checksum != self.adler.finish() {
+                        return Err(InflateError::ChecksumMismatch.into());
+                    }
+                    self.state = State::Done;
+                }
+                State::Done => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, byte: u8, out: &mut [u8], len: &mut usize) {
+        self.window[self.total % INFLATE_WINDOW_SIZE] = byte;
+        self.total += 1;
+        self.adler.update(&[byte]);
+        out[*len] = byte;
+        *len += 1;
+    }
+
+    async fn stored_header<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+    ) -> Result<State, Stop<R::Error>> {
+        // A stored block starts at the next byte boundary.
+        self.bits.align(source).await?;
+        let len = self.bits.take(source, 16).await?;
+        let len_complement = self.bits.take(source, 16).await?;
+        if len != !len_complement & 0xffff {
+            return Err(InflateError::InvalidStoredLength.into());
+        }
+        Ok(State::Stored { len: len as u16 })
+    }
+
+    fn fixed_codes(&mut self) -> Result<(), InflateError> {
+        let mut lengths = [0; LITLEN_CODES];
+        lengths[..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..].fill(8);
+        self.litlen.build(&lengths)?;
+        self.dist.build(&[5; 30])
+    }
+
+    async fn dynamic_codes<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+    ) -> Result<(), Stop<R::Error>> {
+        let litlen_count = self.bits.take(source, 5).await? as usize + 257;
+        let dist_count = self.bits.take(source, 5).await? as usize + 1;
+        let code_length_count = self.bits.take(source, 4).await? as usize + 4;
+        if litlen_count > 286 || dist_count > 30 {
+            return Err(InflateError::InvalidCode.into());
+        }
+
+        let mut code_lengths = [0; CODE_LENGTH_CODES];
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[symbol] = self.bits.take(source, 3).await? as u8;
+        }
+        let mut code_length_code = Huffman::<CODE_LENGTH_CODES>::empty();
+        code_length_code.build(&code_lengths)?;
+
+        // The literal/length and distance code lengths are one sequence, and a
+        // repeat may cross from one into the other.
+        let mut lengths = [0; LITLEN_CODES + DIST_CODES];
+        let lengths = &mut lengths[..litlen_count + dist_count];
+        let mut i = 0;
+        while i < lengths.len() {
+            let (len, repeat) = match self.bits.decode(source, &code_length_code).await? {
+                symbol @ 0..16 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths[..i].last().ok_or(InflateError::InvalidCode)?;
+                    (previous, 3 + self.bits.take(source, 2).await?)
+                }
+                17 => (0, 3 + self.bits.take(source, 3).await?),
+                _ => (0, 11 + self.bits.take(source, 7).await?),
+            };
+            let end = i + repeat as usize;
+            lengths
+                .get_mut(i..end)
+                .ok_or(InflateError::InvalidCode)?
+                .fill(len);
+            i = end;
+        }
+
+        // Without an end-of-block code the block would never end.
+        if lengths[256] == 0 {
+            return Err(InflateError::InvalidCode.into());
+        }
+        self.litlen.build(&lengths[..litlen_count])?;
+        self.dist.build(&lengths[litlen_count..])?;
+        Ok(())
+    }
+
+    /// Reads the rest of a back-reference whose length symbol has just been
+    /// decoded.
+    async fn back_reference<R: AsyncRead + ?Sized>(
+        &mut self,
+        source: &mut Source<'_, R>,
+        symbol: u16,
+    ) -> Result<State, Stop<R::Error>> {
+        let index = usize::from(symbol - 257);
+        if index >= LENGTH_BASE.len() {
+            return Err(InflateError::InvalidCode.into());
+        }
+        let extra = self.bits.take(source, LENGTH_EXTRA[index].into()).await?;
+        let len = LENGTH_BASE[index] + extra as u16;
+
+        let index = usize::from(self.bits.decode(source, &self.dist).await?);
+        if index >= DIST_BASE.len() {
+            return Err(InflateError::InvalidCode.into());
+        }
+        let extra = self.bits.take(source, DIST_EXTRA[index].into()).await?;
+        let distance = DIST_BASE[index] + extra as u16;
+
+        if usize::from(distance) > self.total.min(INFLATE_WINDOW_SIZE) {
+            return Err(InflateError::DistanceTooFar.into());
+        }
+        Ok(State::Copy { len, distance })
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use core::fmt::Write as _;
+
+    use super::*;
+    use crate::test_util::block_on;
+
+    /// Inflates `stream` into `out`, returning how much was inflated and the
+    /// error that stopped it, if any.
+    fn inflate(stream: &[u8], out: &mut [u8]) -> (usize, Option<InflateError>) {
+        let mut inflater = Inflater::new();
+        let mut reader = stream;
+        let mut remaining = stream.len();
+        let mut source = Source {
+            reader: &mut reader,
+            remaining: &mut remaining,
+        };
+        let mut len = 0;
+        while let n @ 1.. = block_on(inflater.read(&mut source, &mut out[len..])).unwrap() {
+            len += n;
+        }
+        (len, inflater.error())
+    }
+
+    #[test]
+    fn inflates_fixed_huffman() {
+        // zlib.compress(b"hello hello hello hello", 9)
+        let stream = [
+            0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03,
+            0x08, 0xb1,
+        ];
+        let mut out = [0; 64];
+        let (len, error) = inflate(&stream, &mut out);
+        assert_eq!(error, None);
+        assert_eq!(&out[..len], b"hello hello hello hello");
+    }
+
+    #[test]
+    fn inflates_stored() {
+        // zlib.compress(b"stored", 0)
+        let stream = [
+            0x78, 0x01, 0x01, 0x06, 0x00, 0xf9, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x09,
+            0x3c, 0x02, 0x92,
+        ];
+        let mut out = [0; 64];
+        let (len, error) = inflate(&stream, &mut out);
+        assert_eq!(error, None);
+        assert_eq!(&out[..len], b"stored");
+    }
+
+    #[test]
+    fn inflates_dynamic_huffman() {
+        // zlib.compress(b"".join(b"%d bottles of milk on the wall, take one down\n" % i
+        //     for i in range(40)), 9)
+        let stream = [
+            0x78, 0xda, 0x95, 0xd4, 0xb1, 0x0d, 0xc2, 0x40, 0x10, 0x44, 0xd1, 0x9c, 0x2a, 0xb6,
+            0x00, 0x02, 0xef, 0x8c, 0xc1, 0x50, 0x8e, 0x91, 0xcf, 0x02, 0xf9, 0xec, 0x0b, 0x38,
+            0xc9, 0xed, 0x43, 0x0b, 0x3f, 0x5d, 0xfd, 0xec, 0x69, 0x76, 0x88, 0x57, 0xeb, 0xbd,
+            0x96, 0x6f, 0xb4, 0x35, 0xf6, 0x4f, 0xdd, 0xa2, 0x1d, 0xd1, 0xdf, 0x25, 0xce, 0xb9,
+            0xd6, 0x6b, 0xf4, 0x79, 0x2b, 0xff, 0x4b, 0x89, 0xa5, 0x9d, 0xc7, 0x25, 0x49, 0x2c,
+            0x12, 0x9b, 0xc4, 0x23, 0x89, 0x6f, 0x24, 0xbe, 0x93, 0x78, 0x22, 0xf1, 0x83, 0xc4,
+            0x4f, 0x84, 0x32, 0xa0, 0x1a, 0x19, 0x26, 0x42, 0x4c, 0xa4, 0x98, 0x88, 0x31, 0x91,
+            0x63, 0x22, 0xc8, 0x44, 0x92, 0x89, 0x28, 0x13, 0x59, 0x0a, 0x59, 0x8a, 0xed, 0x11,
+            0x59, 0x0a, 0x59, 0x0a, 0x59, 0x0a, 0x59, 0x0a, 0x59, 0x0a, 0x59, 0x0a, 0x59, 0x0a,
+            0x59, 0x1a, 0x59, 0x1a, 0x59, 0x9a, 0x3d, 0x57, 0x64, 0x69, 0x64, 0x69, 0x64, 0x69,
+            0x64, 0x69, 0x64, 0x69, 0x64, 0x69, 0x64, 0xf9, 0x03, 0xf7, 0xe8, 0x70, 0x7f,
+        ];
+        let mut expected = heapless::String::<2048>::new();
+        for i in 0..40 {
+            writeln!(expected, "{i} bottles of milk on the wall, take one down").unwrap();
+        }
+
+        let mut out = [0; 2048];
+        let (len, error) = inflate(&stream, &mut out);
+        assert_eq!(error, None);
+        assert_eq!(&out[..len], expected.as_bytes());
+    }
+
+    /// zlib.compress(b"minecrevy-window-test" + bytes(5000) + b"minecrevy-window-test", 9),
+    /// whose second copy of the string refers back to the first, 5021 bytes
+    /// before it.
+    pub(in crate::compression) const FAR_BACK_REFERENCE: [u8; 56] = [
+        0x78, 0xda, 0xed, 0xd8, 0xb1, 0x0d, 0x80, 0x20, 0x10, 0x00, 0x40, 0x17, 0x62, 0x2a, 0xfd,
+        0x82, 0x02, 0x4c, 0x80, 0x40, 0xdc, 0x9e, 0x05, 0x68, 0xed, 0xee, 0xc6, 0xb8, 0x92, 0x6b,
+        0xdc, 0x2d, 0xe6, 0x97, 0x56, 0xae, 0xcf, 0xbb, 0xd2, 0x88, 0x3e, 0x2e, 0x00, 0x00, 0x00,
+        0x00, 0xe0, 0x77, 0xe5, 0x94, 0x73, 0x1b, 0xde, 0x6b, 0x11, 0x09,
+    ];
+
+    #[test]
+    fn rejects_back_reference_past_window() {
+        let mut out = [0; 8192];
+        let (len, error) = inflate(&FAR_BACK_REFERENCE, &mut out);
+        assert_eq!(error, Some(InflateError::DistanceTooFar));
+        assert_eq!(&out[..21], b"minecrevy-window-test");
+        assert!(len < 5042);
+    }
+
+    #[test]
+    fn rejects_corrupt_checksum() {
+        let mut stream = [
+            0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03,
+            0x08, 0xb1,
+        ];
+        stream[15] ^= 1;
+        let mut out = [0; 64];
+        let (_, error) = inflate(&stream, &mut out);
+        assert_eq!(error, Some(InflateError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        let mut out = [0; 64];
+        let (_, error) = inflate(&[0x78, 0x00, 0x03, 0x00], &mut out);
+        assert_eq!(error, Some(InflateError::InvalidHeader));
+    }
+}
@@ -1,7 +1,11 @@
+use core::convert::Infallible;
+
 use defmt::Format;
 use embedded_byteorder::ReadExactError;
 use thiserror::Error;
 
+use crate::InflateError;
+
 #[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
 pub enum ReadMinecraftError<E> {
     #[error("invalid UTF-8")]
@@ -14,6 +18,8 @@ pub enum ReadMinecraftError<E> {
     VarIntIncomplete,
     #[error("unexpected EOF")]
     UnexpectedEof,
+    #[error("invalid identifier")]
+    InvalidIdentifier,
     #[error("other error: {0}")]
     Other(#[from] E),
 }
@@ -27,6 +33,66 @@ impl<E> From<ReadExactError<E>> for ReadMinecraftError<E> {
     }
 }
 
+/// Error type for decoding an array, such as a `heapless::Vec`.
+///
+/// `E` is the reader's error and `TE` the error of its elements.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum ReadArrayError<E, TE> {
+    #[error("failed to read array length: {0}")]
+    Length(ReadMinecraftError<E>),
+    /// There are more elements than fit.
+    #[error("array length exceeded")]
+    LengthExceeded,
+    /// A fixed-size array got fewer elements than its size.
+    #[error("array too short")]
+    TooShort,
+    #[error("failed to read array element {0}: {1}")]
+    Element(usize, TE),
+}
+
+/// Error type for encoding an array.
+///
+/// `E` is the writer's error and `TE` the error of its elements.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum WriteArrayError<E, TE> {
+    #[error("failed to write array length: {0}")]
+    Length(E),
+    /// There are more elements than a VarInt length can count.
+    #[error("array length exceeded")]
+    LengthExceeded,
+    /// A `heapless::Vec<T, N>` with [`Framing::Fixed`] doesn't hold exactly
+    /// `N` elements, so it couldn't be read back.
+    ///
+    /// [`Framing::Fixed`]: crate::options::Framing::Fixed
+    #[error("array not full")]
+    NotFull,
+    #[error("failed to write array element {0}: {1}")]
+    Element(usize, TE),
+}
+
+/// Error type for decoding an [`Option`], which is prefixed with whether it is
+/// present.
+///
+/// `E` is the reader's error and `TE` the error of its value.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum ReadOptionError<E, TE> {
+    #[error("failed to read whether value is present: {0}")]
+    Present(ReadMinecraftError<E>),
+    #[error("failed to read value: {0}")]
+    Value(TE),
+}
+
+/// Error type for encoding an [`Option`].
+///
+/// `E` is the writer's error and `TE` the error of its value.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum WriteOptionError<E, TE> {
+    #[error("failed to write whether value is present: {0}")]
+    Present(E),
+    #[error("failed to write value: {0}")]
+    Value(TE),
+}
+
 /// Error type for reading a Minecraft packet.
 ///
 /// `FE` stands for "frame error" and `DE` stands for "data error".
@@ -34,10 +100,38 @@ impl<E> From<ReadExactError<E>> for ReadMinecraftError<E> {
 pub enum ReadPacketError<FE, DE> {
     #[error("failed to read packet length: {0}")]
     Length(ReadMinecraftError<FE>),
+    #[error("failed to read packet data length: {0}")]
+    DataLength(ReadMinecraftError<FE>),
     #[error("failed to read packet id: {0}")]
     Id(ReadMinecraftError<FE>),
     #[error("failed to read packet body with id {0}: {1}")]
     Body(i32, DE),
+    #[error("failed to decompress packet: {0}")]
+    Decompress(InflateError),
+    #[error("failed to skip the rest of the packet: {0}")]
+    Skip(ReadMinecraftError<FE>),
+}
+
+/// Error type for reading a [`RawPacket`](crate::RawPacket), whose body is
+/// copied verbatim.
+pub type ReadRawPacketError<E> = ReadPacketError<E, ReadMinecraftError<E>>;
+
+/// Error type for [`BorrowedPacket::read`](crate::BorrowedPacket::read).
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum ReadBorrowedPacketError {
+    /// The buffer doesn't hold the whole frame yet.
+    #[error("incomplete packet")]
+    Incomplete,
+    #[error("failed to read packet length: {0}")]
+    Length(ReadMinecraftError<Infallible>),
+    #[error("failed to read packet data length: {0}")]
+    DataLength(ReadMinecraftError<Infallible>),
+    #[error("failed to read packet id: {0}")]
+    Id(ReadMinecraftError<Infallible>),
+    /// The packet is compressed, so it has to be inflated into a buffer of
+    /// its own before it can be decoded.
+    #[error("packet is compressed")]
+    Compressed,
 }
 
 #[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
@@ -55,8 +149,12 @@ pub enum WriteMinecraftError<E> {
 pub enum WritePacketError<FE, DE> {
     #[error("failed to write packet length: {0}")]
     Length(FE),
+    #[error("failed to write packet data length: {0}")]
+    DataLength(FE),
     #[error("failed to write packet id: {0}")]
     Id(FE),
     #[error("failed to write packet body with id {0}: {1}")]
     Body(i32, DE),
+    #[error("failed to write compressed packet with id {0}: {1}")]
+    Compress(i32, FE),
 }
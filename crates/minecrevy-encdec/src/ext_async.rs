@@ -1,13 +1,13 @@
 #![expect(async_fn_in_trait)]
 
-use embedded_byteorder::{
-    AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian, Limit,
-};
+use embedded_byteorder::{AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian};
 use heapless::{String, Vec};
 use uuid::Uuid;
 
 use crate::{
-    AsyncEncode, RawPacket, ReadMinecraftError, ReadPacketError, WireSize, WritePacketError,
+    AsyncEncode, Compression, OwnedIdentifier, PacketReader, RawPacket, ReadMinecraftError,
+    ReadPacketError, ReadRawPacketError, WireSize, WritePacketError,
+    compression::{Counter, DeflateWriter, Deflater, Frame},
     var_i32_size,
 };
 
@@ -33,9 +33,41 @@ pub trait AsyncReadMinecraftExt: AsyncRead {
         Err(ReadMinecraftError::VarIntTooBig)
     }
 
+    /// Asynchronously reads a variable-length-encoded `i64` from the underlying
+    /// reader.
+    async fn read_var_i64(&mut self) -> Result<i64, ReadMinecraftError<Self::Error>> {
+        const CONTINUE_BIT: u8 = 0x80;
+        const SEGMENT_MASK: u8 = 0x7F;
+
+        let mut value = 0;
+        for i in 0..10 {
+            let byte = self
+                .read_u8()
+                .await
+                .map_err(|_| ReadMinecraftError::VarIntIncomplete)?;
+            // The tenth byte holds only the top bit of the value.
+            if i == 9 && byte > 1 {
+                return Err(ReadMinecraftError::VarIntTooBig);
+            }
+            value |= ((byte & SEGMENT_MASK) as i64) << (i * 7);
+            if (byte & CONTINUE_BIT) == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReadMinecraftError::VarIntTooBig)
+    }
+
     async fn read_string<const MAX: usize>(
         &mut self,
     ) -> Result<String<MAX>, ReadMinecraftError<Self::Error>> {
+        let buf = self.read_bytes().await?;
+        String::from_utf8(buf).map_err(|_| ReadMinecraftError::InvalidUtf8)
+    }
+
+    /// Reads a VarInt-prefixed byte array of at most `MAX` bytes.
+    async fn read_bytes<const MAX: usize>(
+        &mut self,
+    ) -> Result<Vec<u8, MAX>, ReadMinecraftError<Self::Error>> {
         let len_i32 = self.read_var_i32().await?;
         let len_usize = usize::try_from(len_i32).map_err(|_| ReadMinecraftError::LengthExceeded)?;
 
@@ -43,8 +75,32 @@ pub trait AsyncReadMinecraftExt: AsyncRead {
         buf.resize(len_usize, 0)
             .map_err(|_| ReadMinecraftError::LengthExceeded)?;
         self.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
 
-        String::from_utf8(buf).map_err(|_| ReadMinecraftError::InvalidUtf8)
+    /// Reads until the end of the reader, which is usually the end of a packet
+    /// body, failing if there are more than `MAX` bytes left.
+    async fn read_remaining<const MAX: usize>(
+        &mut self,
+    ) -> Result<Vec<u8, MAX>, ReadMinecraftError<Self::Error>> {
+        let mut buf = Vec::new();
+        let mut chunk = [0; 64];
+        loop {
+            let n = self.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(buf);
+            }
+            buf.extend_from_slice(&chunk[..n])
+                .map_err(|_| ReadMinecraftError::LengthExceeded)?;
+        }
+    }
+
+    /// Reads an identifier of at most `MAX` bytes, checking its characters.
+    async fn read_identifier<const MAX: usize>(
+        &mut self,
+    ) -> Result<OwnedIdentifier<MAX>, ReadMinecraftError<Self::Error>> {
+        let string = self.read_string().await?;
+        OwnedIdentifier::new(string).map_err(|_| ReadMinecraftError::InvalidIdentifier)
     }
 
     async fn read_uuid(&mut self) -> Result<Uuid, ReadMinecraftError<Self::Error>> {
@@ -53,40 +109,80 @@ pub trait AsyncReadMinecraftExt: AsyncRead {
         Ok(Uuid::from_u64_pair(msb, lsb))
     }
 
+    /// Reads a packet, framed according to `compression`, and decodes its
+    /// body with `decode`, which is given the packet ID.
+    ///
+    /// Whatever `decode` leaves unread is skipped.
+    ///
+    /// A compressed packet that refers back further than
+    /// [`INFLATE_WINDOW_SIZE`](crate::INFLATE_WINDOW_SIZE) bytes fails with
+    /// [`InflateError::DistanceTooFar`](crate::InflateError::DistanceTooFar).
+    /// Vanilla clients compress with zlib's 32 KiB window, so any of their
+    /// packets that decompresses to more than the window can fail this way.
     async fn read_packet<T, E>(
-        &'_ mut self,
-        decode: impl AsyncFnOnce(i32, Limit<&'_ mut Self>) -> Result<T, E>,
+        &mut self,
+        compression: Compression,
+        decode: impl AsyncFnOnce(i32, PacketReader<'_, Self>) -> Result<T, E>,
     ) -> Result<T, ReadPacketError<Self::Error, E>> {
         let len_i32 = self.read_var_i32().await.map_err(ReadPacketError::Length)?;
         let len_usize = usize::try_from(len_i32)
             .map_err(|_| ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
 
-        let id = self.read_var_i32().await.map_err(ReadPacketError::Id)?;
-        let body = self.limit(len_usize - var_i32_size(id));
+        let mut frame = match compression {
+            Compression::Disabled => Frame::uncompressed(len_usize),
+            Compression::Enabled { .. } => {
+                let data_len_i32 = self
+                    .read_var_i32()
+                    .await
+                    .map_err(ReadPacketError::DataLength)?;
+                let data_len_usize = usize::try_from(data_len_i32)
+                    .map_err(|_| ReadPacketError::DataLength(ReadMinecraftError::LengthExceeded))?;
+                let len_usize = len_usize
+                    .checked_sub(var_i32_size(data_len_i32))
+                    .ok_or(ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
+                // Packets below the threshold aren't compressed and say so
+                // with a data length of 0.
+                match data_len_usize {
+                    0 => Frame::uncompressed(len_usize),
+                    _ => Frame::compressed(len_usize, data_len_usize),
+                }
+            }
+        };
+
+        let mut body = PacketReader {
+            reader: &mut *self,
+            frame: &mut frame,
+        };
+        let id = match body.read_var_i32().await {
+            Ok(id) => id,
+            Err(e) => {
+                return Err(match frame.error() {
+                    Some(e) => ReadPacketError::Decompress(e),
+                    None => ReadPacketError::Id(e),
+                });
+            }
+        };
 
-        decode(id, body)
-            .await
-            .map_err(|e| ReadPacketError::Body(id, e))
+        let value = decode(id, body).await;
+        if let Some(e) = frame.error() {
+            return Err(ReadPacketError::Decompress(e));
+        }
+        let value = value.map_err(|e| ReadPacketError::Body(id, e))?;
+        frame.finish(self).await?;
+        Ok(value)
     }
 
+    /// Reads a packet, framed according to `compression`, without decoding
+    /// its body.
     async fn read_raw_packet<const MAX: usize>(
         &mut self,
-    ) -> Result<RawPacket<MAX>, ReadPacketError<Self::Error, ReadMinecraftError<Self::Error>>> {
-        let len_i32 = self.read_var_i32().await.map_err(ReadPacketError::Length)?;
-        let len_usize = usize::try_from(len_i32)
-            .map_err(|_| ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
-
-        let id = self.read_var_i32().await.map_err(ReadPacketError::Id)?;
-        let mut data = Vec::new();
-        let body_size = len_usize - var_i32_size(id);
-        data.resize(body_size, 0)
-            .map_err(|_| ReadPacketError::Body(id, ReadMinecraftError::LengthExceeded))?;
-        self.read_exact(&mut data[..body_size])
-            .await
-            .map_err(ReadMinecraftError::from)
-            .map_err(|e| ReadPacketError::Body(id, e))?;
-
-        Ok(RawPacket { id, data })
+        compression: Compression,
+    ) -> Result<RawPacket<MAX>, ReadRawPacketError<Self::Error>> {
+        self.read_packet(compression, async |id, mut body| {
+            let data = body.read_remaining().await?;
+            Ok(RawPacket { id, data })
+        })
+        .await
     }
 }
 
@@ -127,6 +223,26 @@ pub trait AsyncWriteMinecraftExt: AsyncWrite {
         Ok(())
     }
 
+    async fn write_var_i64(&mut self, value: i64) -> Result<(), Self::Error> {
+        const CONTINUE_BIT: u64 = 0x80;
+        const SEGMENT_MASK: u64 = 0x7F;
+
+        let mut value = value as u64;
+        let mut buf = [0; 10];
+        let mut len = 0;
+        loop {
+            if (value & !SEGMENT_MASK) == 0 {
+                buf[len] = value as u8;
+                len += 1;
+                break;
+            }
+            buf[len] = (value & SEGMENT_MASK | CONTINUE_BIT) as u8;
+            len += 1;
+            value >>= 7;
+        }
+        self.write_all(&buf[..len]).await
+    }
+
     async fn write_string(&mut self, value: &str) -> Result<(), Self::Error> {
         let len_i32 = i32::try_from(value.len()).unwrap();
         self.write_var_i32(len_i32).await?;
@@ -134,8 +250,31 @@ pub trait AsyncWriteMinecraftExt: AsyncWrite {
         Ok(())
     }
 
+    /// Writes a VarInt-prefixed byte array.
+    async fn write_bytes(&mut self, value: &[u8]) -> Result<(), Self::Error> {
+        let len_i32 = i32::try_from(value.len()).unwrap();
+        self.write_var_i32(len_i32).await?;
+        self.write_all(value).await?;
+        Ok(())
+    }
+
+    async fn write_uuid(&mut self, value: Uuid) -> Result<(), Self::Error> {
+        let (msb, lsb) = value.as_u64_pair();
+        self.write_u64::<BigEndian>(msb).await?;
+        self.write_u64::<BigEndian>(lsb).await?;
+        Ok(())
+    }
+
+    /// Writes a packet, framed and compressed according to `compression`.
+    ///
+    /// A compressed packet is compressed twice, first to find out how long
+    /// it is and then to write it, so that it doesn't need to be buffered.
+    /// Every packet at or above the threshold therefore costs twice the CPU
+    /// time of a single deflate; raising the threshold makes fewer packets
+    /// pay for it.
     async fn write_packet<P>(
         &mut self,
+        compression: Compression,
         id: i32,
         packet: P,
         options: <P as AsyncEncode>::Options,
@@ -143,29 +282,160 @@ pub trait AsyncWriteMinecraftExt: AsyncWrite {
     where
         P: AsyncEncode + WireSize<Options = <P as AsyncEncode>::Options>,
     {
-        let len_usize = var_i32_size(id) + packet.wire_size(options.clone());
-        let len_i32 = i32::try_from(len_usize).unwrap();
-        self.write_var_i32(len_i32)
-            .await
-            .map_err(WritePacketError::Length)?;
-        self.write_var_i32(id).await.map_err(WritePacketError::Id)?;
-        packet
-            .encode(&mut self.limit(len_usize), options)
-            .await
-            .map_err(|e| WritePacketError::Body(id, e))?;
-        Ok(())
+        let body_len = packet.wire_size(options.clone());
+        let data_len_usize = var_i32_size(id) + body_len;
+        let data_len_i32 = i32::try_from(data_len_usize).unwrap();
+
+        match compression {
+            Compression::Enabled { threshold } if data_len_usize >= threshold => {
+                // The frame starts with the compressed length, so the packet
+                // is deflated once into a counter and then again for real.
+                // Buffering the output instead would need RAM for the largest
+                // compressed packet on every connection.
+                let mut deflater = Deflater::new();
+                let mut counter = Counter::new();
+                write_compressed(
+                    &mut counter,
+                    &mut deflater,
+                    id,
+                    &packet,
+                    body_len,
+                    options.clone(),
+                )
+                .await?;
+
+                let len_usize = var_i32_size(data_len_i32) + counter.count();
+                let len_i32 = i32::try_from(len_usize).unwrap();
+                self.write_var_i32(len_i32)
+                    .await
+                    .map_err(WritePacketError::Length)?;
+                self.write_var_i32(data_len_i32)
+                    .await
+                    .map_err(WritePacketError::DataLength)?;
+                deflater = Deflater::new();
+                write_compressed(self, &mut deflater, id, &packet, body_len, options).await
+            }
+            Compression::Enabled { .. } => {
+                // A data length of 0 says that the packet isn't compressed.
+                let len_i32 = i32::try_from(var_i32_size(0) + data_len_usize).unwrap();
+                self.write_var_i32(len_i32)
+                    .await
+                    .map_err(WritePacketError::Length)?;
+                self.write_var_i32(0)
+                    .await
+                    .map_err(WritePacketError::DataLength)?;
+                self.write_var_i32(id).await.map_err(WritePacketError::Id)?;
+                packet
+                    .encode(&mut self.limit(body_len), options)
+                    .await
+                    .map_err(|e| WritePacketError::Body(id, e))
+            }
+            Compression::Disabled => {
+                self.write_var_i32(data_len_i32)
+                    .await
+                    .map_err(WritePacketError::Length)?;
+                self.write_var_i32(id).await.map_err(WritePacketError::Id)?;
+                packet
+                    .encode(&mut self.limit(body_len), options)
+                    .await
+                    .map_err(|e| WritePacketError::Body(id, e))
+            }
+        }
     }
 
+    /// Writes a packet whose body is already encoded, framed and compressed
+    /// according to `compression`.
     async fn write_raw_packet<const N: usize>(
         &mut self,
+        compression: Compression,
         packet: RawPacket<N>,
     ) -> Result<(), Self::Error> {
-        let len_i32 = i32::try_from(var_i32_size(packet.id) + packet.data.len()).unwrap();
-        self.write_var_i32(len_i32).await?;
-        self.write_var_i32(packet.id).await?;
-        self.write_all(&packet.data).await?;
-        Ok(())
+        self.write_packet(compression, packet.id, RawBody(&packet.data), ())
+            .await
+            .map_err(|e| match e {
+                WritePacketError::Length(e)
+                | WritePacketError::DataLength(e)
+                | WritePacketError::Id(e)
+                | WritePacketError::Body(_, e)
+                | WritePacketError::Compress(_, e) => e,
+            })
     }
 }
 
 impl<W: AsyncWrite + ?Sized> AsyncWriteMinecraftExt for W {}
+
+/// Compresses the ID and body of a packet into `writer`.
+async fn write_compressed<W, P>(
+    writer: &mut W,
+    deflater: &mut Deflater,
+    id: i32,
+    packet: &P,
+    body_len: usize,
+    options: <P as AsyncEncode>::Options,
+) -> Result<(), WritePacketError<W::Error, P::Error<W::Error>>>
+where
+    W: AsyncWrite + ?Sized,
+    P: AsyncEncode,
+{
+    let mut writer = DeflateWriter { deflater, writer };
+    writer
+        .write_var_i32(id)
+        .await
+        .map_err(WritePacketError::Id)?;
+    packet
+        .encode(&mut (&mut writer).limit(body_len), options)
+        .await
+        .map_err(|e| WritePacketError::Body(id, e))?;
+    writer
+        .finish()
+        .await
+        .map_err(|e| WritePacketError::Compress(id, e))
+}
+
+/// A body that is already encoded.
+struct RawBody<'a>(&'a [u8]);
+
+impl AsyncEncode for RawBody<'_> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(&self, writer: &mut W, (): ()) -> Result<(), W::Error> {
+        writer.write_all(self.0).await
+    }
+}
+
+impl WireSize for RawBody<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): ()) -> usize {
+        self.0.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{VAR_LONGS, block_on};
+
+    #[test]
+    fn read_var_i64_round_trips() {
+        for (value, bytes) in VAR_LONGS {
+            let mut reader = bytes;
+            assert_eq!(block_on(reader.read_var_i64()), Ok(value));
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn read_var_i64_rejects_bits_past_the_tenth_byte() {
+        let mut bytes = [0xFF; 10];
+        for last in [0x02, 0x7F, 0x81] {
+            bytes[9] = last;
+            let mut reader = &bytes[..];
+            assert_eq!(
+                block_on(reader.read_var_i64()),
+                Err(ReadMinecraftError::VarIntTooBig)
+            );
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use embedded_byteorder::{BigEndian, Read, ReadBytesExt, Write, WriteBytesExt};
+use embedded_byteorder::{BigEndian, Limit, Read, ReadBytesExt, Write, WriteBytesExt};
 use heapless::{String, Vec};
 use uuid::Uuid;
 
-use crate::{RawPacket, ReadMinecraftError, var_i32_size};
+use crate::{
+    Encode, OwnedIdentifier, RawPacket, ReadMinecraftError, ReadPacketError, ReadRawPacketError,
+    WireSize, WritePacketError, var_i32_size,
+};
 
 /// Extends [`Read`] with methods for reading Minecraft-specific data types.
 pub trait ReadMinecraftExt: Read {
@@ -31,9 +34,46 @@ pub trait ReadMinecraftExt: Read {
         Ok(value)
     }
 
+    fn read_var_i64(&mut self) -> Result<i64, ReadMinecraftError<Self::Error>> {
+        const CONTINUE_BIT: u8 = 0x80;
+        const SEGMENT_MASK: u8 = 0x7F;
+
+        let mut value = 0;
+        let mut position = 0;
+        let mut byte;
+
+        loop {
+            byte = self.read_u8()?;
+            // The tenth byte holds only the top bit of the value.
+            if position == 63 && byte > 1 {
+                return Err(ReadMinecraftError::VarIntTooBig);
+            }
+            value |= ((byte & SEGMENT_MASK) as i64) << position;
+
+            if (byte & CONTINUE_BIT) != CONTINUE_BIT {
+                break;
+            }
+
+            position += 7;
+
+            if position >= 64 {
+                return Err(ReadMinecraftError::VarIntTooBig);
+            }
+        }
+        Ok(value)
+    }
+
     fn read_string<const MAX: usize>(
         &mut self,
     ) -> Result<String<MAX>, ReadMinecraftError<Self::Error>> {
+        let buf = self.read_bytes()?;
+        String::from_utf8(buf).map_err(|_| ReadMinecraftError::InvalidUtf8)
+    }
+
+    /// Reads a VarInt-prefixed byte array of at most `MAX` bytes.
+    fn read_bytes<const MAX: usize>(
+        &mut self,
+    ) -> Result<Vec<u8, MAX>, ReadMinecraftError<Self::Error>> {
         let len_i32 = self.read_var_i32()?;
         let len_usize = usize::try_from(len_i32).map_err(|_| ReadMinecraftError::LengthExceeded)?;
 
@@ -41,8 +81,32 @@ pub trait ReadMinecraftExt: Read {
         buf.resize(len_usize, 0)
             .map_err(|_| ReadMinecraftError::LengthExceeded)?;
         self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
 
-        String::from_utf8(buf).map_err(|_| ReadMinecraftError::InvalidUtf8)
+    /// Reads until the end of the reader, which is usually the end of a packet
+    /// body, failing if there are more than `MAX` bytes left.
+    fn read_remaining<const MAX: usize>(
+        &mut self,
+    ) -> Result<Vec<u8, MAX>, ReadMinecraftError<Self::Error>> {
+        let mut buf = Vec::new();
+        let mut chunk = [0; 64];
+        loop {
+            let n = self.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(buf);
+            }
+            buf.extend_from_slice(&chunk[..n])
+                .map_err(|_| ReadMinecraftError::LengthExceeded)?;
+        }
+    }
+
+    /// Reads an identifier of at most `MAX` bytes, checking its characters.
+    fn read_identifier<const MAX: usize>(
+        &mut self,
+    ) -> Result<OwnedIdentifier<MAX>, ReadMinecraftError<Self::Error>> {
+        let string = self.read_string()?;
+        OwnedIdentifier::new(string).map_err(|_| ReadMinecraftError::InvalidIdentifier)
     }
 
     fn read_uuid(&mut self) -> Result<Uuid, ReadMinecraftError<Self::Error>> {
@@ -51,17 +115,53 @@ pub trait ReadMinecraftExt: Read {
         Ok(Uuid::from_u64_pair(msb, lsb))
     }
 
+    /// Reads an uncompressed packet and decodes its body with `decode`, which
+    /// is given the packet ID.
+    ///
+    /// Whatever `decode` leaves unread is skipped.
+    fn read_packet<T, E>(
+        &mut self,
+        decode: impl FnOnce(i32, &mut Limit<&mut Self>) -> Result<T, E>,
+    ) -> Result<T, ReadPacketError<Self::Error, E>> {
+        let len_i32 = self.read_var_i32().map_err(ReadPacketError::Length)?;
+        let len_usize = usize::try_from(len_i32)
+            .map_err(|_| ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
+
+        let id = self.read_var_i32().map_err(ReadPacketError::Id)?;
+        let body_size = len_usize
+            .checked_sub(var_i32_size(id))
+            .ok_or(ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
+        let mut body = self.limit(body_size);
+
+        let value = decode(id, &mut body).map_err(|e| ReadPacketError::Body(id, e))?;
+        let mut remaining = Limit::limit(&body);
+        let mut scratch = [0; 64];
+        while remaining > 0 {
+            let len = remaining.min(scratch.len());
+            self.read_exact(&mut scratch[..len])
+                .map_err(|e| ReadPacketError::Skip(ReadMinecraftError::from(e)))?;
+            remaining -= len;
+        }
+        Ok(value)
+    }
+
     fn read_raw_packet<const MAX: usize>(
         &mut self,
-    ) -> Result<RawPacket<MAX>, ReadMinecraftError<Self::Error>> {
-        let len_i32 = self.read_var_i32()?;
-        let len_usize = usize::try_from(len_i32).map_err(|_| ReadMinecraftError::LengthExceeded)?;
+    ) -> Result<RawPacket<MAX>, ReadRawPacketError<Self::Error>> {
+        let len_i32 = self.read_var_i32().map_err(ReadPacketError::Length)?;
+        let len_usize = usize::try_from(len_i32)
+            .map_err(|_| ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
 
-        let id = self.read_var_i32()?;
+        let id = self.read_var_i32().map_err(ReadPacketError::Id)?;
         let mut data = Vec::new();
-        data.resize(len_usize - var_i32_size(id), 0)
-            .map_err(|_| ReadMinecraftError::LengthExceeded)?;
-        self.read_exact(&mut data)?;
+        let body_size = len_usize
+            .checked_sub(var_i32_size(id))
+            .ok_or(ReadPacketError::Length(ReadMinecraftError::LengthExceeded))?;
+        data.resize(body_size, 0)
+            .map_err(|_| ReadPacketError::Body(id, ReadMinecraftError::LengthExceeded))?;
+        self.read_exact(&mut data[..body_size])
+            .map_err(ReadMinecraftError::from)
+            .map_err(|e| ReadPacketError::Body(id, e))?;
 
         Ok(RawPacket { id, data })
     }
@@ -69,7 +169,7 @@ pub trait ReadMinecraftExt: Read {
 
 impl<R: Read + ?Sized> ReadMinecraftExt for R {}
 
-/// Extends [`AsyncWrite`] with methods for writing Minecraft-specific data types.
+/// Extends [`Write`] with methods for writing Minecraft-specific data types.
 pub trait WriteMinecraftExt: Write {
     fn write_var_i32(&mut self, value: i32) -> Result<(), Self::Error> {
         const CONTINUE_BIT: u32 = 0x80;
@@ -104,12 +204,178 @@ pub trait WriteMinecraftExt: Write {
         Ok(())
     }
 
-    fn write_string<const MAX: usize>(&mut self, value: &str) -> Result<(), Self::Error> {
+    fn write_var_i64(&mut self, value: i64) -> Result<(), Self::Error> {
+        const CONTINUE_BIT: u64 = 0x80;
+        const SEGMENT_MASK: u64 = 0x7F;
+
+        let mut value = value as u64;
+        let mut buf = [0; 10];
+        let mut len = 0;
+        loop {
+            if (value & !SEGMENT_MASK) == 0 {
+                buf[len] = value as u8;
+                len += 1;
+                break;
+            }
+            buf[len] = (value & SEGMENT_MASK | CONTINUE_BIT) as u8;
+            len += 1;
+            value >>= 7;
+        }
+        self.write_all(&buf[..len])
+    }
+
+    fn write_string(&mut self, value: &str) -> Result<(), Self::Error> {
         let len_i32 = i32::try_from(value.len()).unwrap();
         self.write_var_i32(len_i32)?;
         self.write_all(value.as_bytes())?;
         Ok(())
     }
+
+    /// Writes a VarInt-prefixed byte array.
+    fn write_bytes(&mut self, value: &[u8]) -> Result<(), Self::Error> {
+        let len_i32 = i32::try_from(value.len()).unwrap();
+        self.write_var_i32(len_i32)?;
+        self.write_all(value)?;
+        Ok(())
+    }
+
+    fn write_uuid(&mut self, value: Uuid) -> Result<(), Self::Error> {
+        let (msb, lsb) = value.as_u64_pair();
+        self.write_u64::<BigEndian>(msb)?;
+        self.write_u64::<BigEndian>(lsb)?;
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn write_packet<P>(
+        &mut self,
+        id: i32,
+        packet: P,
+        options: <P as Encode>::Options,
+    ) -> Result<(), WritePacketError<Self::Error, P::Error<Self::Error>>>
+    where
+        P: Encode + WireSize<Options = <P as Encode>::Options>,
+    {
+        let body_len = packet.wire_size(options.clone());
+        let len_i32 = i32::try_from(var_i32_size(id) + body_len).unwrap();
+        self.write_var_i32(len_i32)
+            .map_err(WritePacketError::Length)?;
+        self.write_var_i32(id).map_err(WritePacketError::Id)?;
+        packet
+            .encode(&mut self.limit(body_len), options)
+            .map_err(|e| WritePacketError::Body(id, e))?;
+        Ok(())
+    }
+
+    fn write_raw_packet<const N: usize>(
+        &mut self,
+        packet: RawPacket<N>,
+    ) -> Result<(), Self::Error> {
+        let len_i32 = i32::try_from(var_i32_size(packet.id) + packet.data.len()).unwrap();
+        self.write_var_i32(len_i32)?;
+        self.write_var_i32(packet.id)?;
+        self.write_all(&packet.data)?;
+        Ok(())
+    }
 }
 
 impl<W: Write + ?Sized> WriteMinecraftExt for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::IntOptions,
+        test_util::{VAR_LONGS, encode_with},
+        var_i64_size,
+    };
+
+    #[test]
+    fn var_i64_round_trips() {
+        for (value, bytes) in VAR_LONGS {
+            let mut buf = [0; 10];
+            let mut writer = &mut buf[..];
+            writer.write_var_i64(value).unwrap();
+            let len = 10 - writer.len();
+            assert_eq!(&buf[..len], bytes, "{value}");
+            assert_eq!(var_i64_size(value), len, "{value}");
+
+            let mut reader = bytes;
+            assert_eq!(reader.read_var_i64(), Ok(value));
+            assert!(reader.is_empty());
+
+            // Also checks the async encoder and the wire size.
+            assert_eq!(encode_with(&value, IntOptions { varint: true }), bytes);
+        }
+    }
+
+    #[test]
+    fn var_i64_rejects_bits_past_the_tenth_byte() {
+        let mut bytes = [0xFF; 10];
+        // Bit 1 of the tenth byte would be bit 64 of the value, and its
+        // continue bit would ask for an eleventh byte.
+        for last in [0x02, 0x7F, 0x81] {
+            bytes[9] = last;
+
+            let mut reader = &bytes[..];
+            assert_eq!(reader.read_var_i64(), Err(ReadMinecraftError::VarIntTooBig));
+        }
+    }
+
+    #[test]
+    fn read_packet_rejects_frame_shorter_than_id() {
+        for frame in [&[0x01, 0x80, 0x01][..], &[0x00, 0x00][..]] {
+            let mut reader = frame;
+            let result = reader.read_packet(|_, _| Ok::<_, ()>(()));
+            assert!(matches!(
+                result,
+                Err(ReadPacketError::Length(ReadMinecraftError::LengthExceeded))
+            ));
+
+            let mut reader = frame;
+            let result = reader.read_raw_packet::<16>();
+            assert!(matches!(
+                result,
+                Err(ReadPacketError::Length(ReadMinecraftError::LengthExceeded))
+            ));
+        }
+    }
+
+    #[test]
+    fn read_packet_skips_what_decode_leaves() {
+        // Two packets, the first with two bytes of body left unread.
+        let frames = [0x04, 0x01, 0xAA, 0xBB, 0xCC, 0x02, 0x02, 0xDD];
+        let mut reader = &frames[..];
+        let first = reader
+            .read_packet(|id, body| body.read_u8().map(|byte| (id, byte)))
+            .unwrap();
+        assert_eq!(first, (0x01, 0xAA));
+
+        let second = reader
+            .read_packet(|id, body| body.read_u8().map(|byte| (id, byte)))
+            .unwrap();
+        assert_eq!(second, (0x02, 0xDD));
+        assert!(reader.is_empty());
+
+        // The frame says there is more than the reader holds.
+        let mut reader = &[0x04, 0x01, 0xAA][..];
+        let result = reader.read_packet(|_, _| Ok::<_, ()>(()));
+        assert!(matches!(
+            result,
+            Err(ReadPacketError::Skip(ReadMinecraftError::UnexpectedEof))
+        ));
+    }
+
+    #[test]
+    fn write_packet_round_trips() {
+        let mut buf = [0u8; 8];
+        let mut writer = &mut buf[..];
+        writer.write_packet(0x02, 0x1234_u16, ()).unwrap();
+        assert_eq!(&buf[..4], &[0x03, 0x02, 0x12, 0x34]);
+
+        let mut reader = &buf[..4];
+        let packet = reader.read_raw_packet::<16>().unwrap();
+        assert_eq!(packet.id, 0x02);
+        assert_eq!(&packet.data[..], &[0x12, 0x34]);
+    }
+}
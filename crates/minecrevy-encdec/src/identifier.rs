@@ -0,0 +1,288 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use defmt::Format;
+use heapless::String;
+use thiserror::Error;
+
+/// The namespace of an identifier that does not name one.
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// Returned when a string is not a valid [`Identifier`].
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+#[error("invalid identifier")]
+pub struct InvalidIdentifier;
+
+/// Returned when an [`Identifier`] is too long for an [`OwnedIdentifier`].
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+#[error("identifier too long")]
+pub struct IdentifierTooLong;
+
+/// A resource location such as `minecraft:stone`, which names registry
+/// entries, plugin channels, sounds and tags.
+///
+/// The namespace may only hold `a-z`, `0-9`, `_`, `-` and `.`, and the path
+/// `/` as well. It is sent as a string, `namespace:path`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Identifier<'a> {
+    namespace: &'a str,
+    path: &'a str,
+}
+
+impl<'a> Identifier<'a> {
+    /// An identifier in `namespace`, or in the `minecraft` namespace if it is
+    /// empty.
+    pub const fn new(namespace: &'a str, path: &'a str) -> Result<Self, InvalidIdentifier> {
+        let namespace = if namespace.is_empty() {
+            DEFAULT_NAMESPACE
+        } else {
+            namespace
+        };
+        if is_valid(namespace, false) && is_valid(path, true) {
+            Ok(Self { namespace, path })
+        } else {
+            Err(InvalidIdentifier)
+        }
+    }
+
+    /// An identifier in the `minecraft` namespace, for use in constants.
+    ///
+    /// # Panics
+    ///
+    /// If `path` is not a valid path.
+    pub const fn minecraft(path: &'a str) -> Self {
+        match Self::new(DEFAULT_NAMESPACE, path) {
+            Ok(identifier) => identifier,
+            Err(_) => panic!("invalid identifier path"),
+        }
+    }
+
+    /// Parses `namespace:path`, or just `path` in the `minecraft` namespace.
+    pub const fn parse(identifier: &'a str) -> Result<Self, InvalidIdentifier> {
+        let (namespace, path) = split(identifier);
+        Self::new(namespace, path)
+    }
+
+    pub const fn namespace(&self) -> &'a str {
+        self.namespace
+    }
+
+    pub const fn path(&self) -> &'a str {
+        self.path
+    }
+
+    /// The length of `namespace:path`, which prefixes it.
+    pub(crate) fn wire_len(&self) -> i32 {
+        i32::try_from(self.namespace.len() + 1 + self.path.len()).unwrap()
+    }
+}
+
+impl fmt::Display for Identifier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl Format for Identifier<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=str}:{=str}", self.namespace, self.path)
+    }
+}
+
+/// An [`Identifier`] of at most `N` bytes, as it was read.
+#[derive(Clone, Debug)]
+pub struct OwnedIdentifier<const N: usize>(String<N>);
+
+impl<const N: usize> OwnedIdentifier<N> {
+    /// Checks that `string` is a valid identifier.
+    pub fn new(string: String<N>) -> Result<Self, InvalidIdentifier> {
+        Identifier::parse(&string)?;
+        Ok(Self(string))
+    }
+
+    pub fn as_identifier(&self) -> Identifier<'_> {
+        // Already checked by `new`.
+        let (namespace, path) = split(&self.0);
+        Identifier { namespace, path }
+    }
+
+    /// The identifier as it was read, which may leave out the namespace.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> TryFrom<Identifier<'_>> for OwnedIdentifier<N> {
+    /// `namespace:path` is longer than `N` bytes.
+    type Error = IdentifierTooLong;
+
+    fn try_from(identifier: Identifier<'_>) -> Result<Self, Self::Error> {
+        let mut string = String::new();
+        string
+            .push_str(identifier.namespace)
+            .and_then(|()| string.push(':'))
+            .and_then(|()| string.push_str(identifier.path))
+            .map_err(|()| IdentifierTooLong)?;
+        Ok(Self(string))
+    }
+}
+
+/// Compares the identifiers, so that `stone` equals `minecraft:stone`.
+impl<const N: usize> PartialEq for OwnedIdentifier<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_identifier() == other.as_identifier()
+    }
+}
+
+impl<const N: usize> Eq for OwnedIdentifier<N> {}
+
+impl<const N: usize> PartialEq<Identifier<'_>> for OwnedIdentifier<N> {
+    fn eq(&self, other: &Identifier<'_>) -> bool {
+        self.as_identifier() == *other
+    }
+}
+
+impl<const N: usize> Hash for OwnedIdentifier<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_identifier().hash(state);
+    }
+}
+
+impl<const N: usize> fmt::Display for OwnedIdentifier<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_identifier().fmt(f)
+    }
+}
+
+impl<const N: usize> Format for OwnedIdentifier<N> {
+    fn format(&self, f: defmt::Formatter) {
+        self.as_identifier().format(f)
+    }
+}
+
+/// Splits an identifier into its namespace and path, without checking them.
+const fn split(identifier: &str) -> (&str, &str) {
+    let bytes = identifier.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b':' {
+            let (namespace, rest) = identifier.split_at(i);
+            let (_, path) = rest.split_at(1);
+            if namespace.is_empty() {
+                return (DEFAULT_NAMESPACE, path);
+            }
+            return (namespace, path);
+        }
+        i += 1;
+    }
+    (DEFAULT_NAMESPACE, identifier)
+}
+
+const fn is_valid(s: &str, is_path: bool) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' => {}
+            b'/' if is_path => {}
+            _ => return false,
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::ToString;
+
+    use super::*;
+    use crate::{
+        AsyncDecode, BorrowDecode, Decode, ReadMinecraftError,
+        test_util::{block_on, encode},
+    };
+
+    #[test]
+    fn defaults_to_the_minecraft_namespace() {
+        let stone = Identifier::parse("stone").unwrap();
+        assert_eq!(stone.namespace(), "minecraft");
+        assert_eq!(stone.path(), "stone");
+        assert_eq!(stone, Identifier::minecraft("stone"));
+        assert_eq!(stone, Identifier::parse("minecraft:stone").unwrap());
+        // It is always sent with its namespace.
+        assert_eq!(encode(&stone), b"\x0fminecraft:stone");
+
+        let owned = OwnedIdentifier::<16>::new(String::try_from("stone").unwrap()).unwrap();
+        assert_eq!(owned.as_str(), "stone");
+        assert_eq!(owned, stone);
+        assert_eq!(owned.to_string(), "minecraft:stone");
+    }
+
+    #[test]
+    fn empty_namespace_is_minecraft() {
+        let stone = Identifier::parse(":stone").unwrap();
+        assert_eq!(stone, Identifier::minecraft("stone"));
+        assert_eq!(Identifier::new("", "stone"), Ok(stone));
+        assert_eq!(encode(&stone), b"\x0fminecraft:stone");
+    }
+
+    #[test]
+    fn allows_only_some_characters() {
+        let valid = Identifier::parse("my_mod-1.0:block/a_b-c.d").unwrap();
+        assert_eq!(valid.namespace(), "my_mod-1.0");
+        assert_eq!(valid.path(), "block/a_b-c.d");
+        assert!(Identifier::parse("0123456789abcdefghijklmnopqrstuvwxyz").is_ok());
+
+        for invalid in [
+            "Stone",
+            "minecraft:Stone",
+            "a b",
+            "my/mod:stone",
+            "a:b:c",
+            "é",
+            "a\0",
+            "#minecraft:logs",
+        ] {
+            assert_eq!(
+                Identifier::parse(invalid),
+                Err(InvalidIdentifier),
+                "{invalid}"
+            );
+        }
+        assert_eq!(Identifier::new("a", "b:c"), Err(InvalidIdentifier));
+    }
+
+    #[test]
+    fn decoding_rejects_invalid_identifiers() {
+        let bytes: &[u8] = b"\x0fminecraft:Stone";
+
+        let result = <OwnedIdentifier<32> as Decode>::decode(&mut &bytes[..], ());
+        assert!(matches!(result, Err(ReadMinecraftError::InvalidIdentifier)));
+        let result = block_on(<OwnedIdentifier<32> as AsyncDecode>::decode(
+            &mut &bytes[..],
+            (),
+        ));
+        assert!(matches!(result, Err(ReadMinecraftError::InvalidIdentifier)));
+        let result = <Identifier as BorrowDecode>::decode(&mut &bytes[..], ());
+        assert_eq!(result, Err(ReadMinecraftError::InvalidIdentifier));
+
+        let mut reader: &[u8] = b"\x05stone";
+        let stone = <OwnedIdentifier<32> as Decode>::decode(&mut reader, ()).unwrap();
+        assert_eq!(stone, Identifier::minecraft("stone"));
+    }
+
+    #[test]
+    fn owned_identifier_from_too_long_identifier() {
+        let stone = Identifier::minecraft("stone");
+        let owned = OwnedIdentifier::<15>::try_from(stone).unwrap();
+        assert_eq!(owned.as_str(), "minecraft:stone");
+        assert_eq!(
+            OwnedIdentifier::<14>::try_from(stone).unwrap_err(),
+            IdentifierTooLong
+        );
+    }
+}
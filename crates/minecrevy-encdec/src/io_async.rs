@@ -2,8 +2,14 @@
 
 use embedded_byteorder::{AsyncRead, AsyncWrite};
 
+mod bitset;
 mod core;
 mod heapless;
+mod identifier;
+mod position;
+mod uuid;
+
+pub use minecrevy_encdec_macros::{AsyncDecode, AsyncEncode};
 
 pub trait AsyncDecode: Sized {
     type Options: Clone + Default;
@@ -0,0 +1,76 @@
+use embedded_byteorder::{
+    AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian, ReadExactError,
+};
+
+use crate::{
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, BitSet, FixedBitSet,
+    ReadMinecraftError,
+};
+
+impl<const N: usize> AsyncDecode for BitSet<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let len = reader.read_var_i32().await?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= N)
+            .ok_or(ReadMinecraftError::LengthExceeded)?;
+
+        let mut words = [0; N];
+        for word in &mut words[..len] {
+            *word = reader.read_u64::<BigEndian>().await?;
+        }
+        Ok(Self::from_words(words))
+    }
+}
+
+impl<const N: usize> AsyncEncode for BitSet<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let words = self.trimmed();
+        // At most `N` longs, which is never near `i32::MAX`.
+        writer.write_var_i32(words.len() as i32).await?;
+        for &word in words {
+            writer.write_u64::<BigEndian>(word).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> AsyncDecode for FixedBitSet<N> {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let mut bytes = [0; N];
+        reader.read_exact(&mut bytes).await?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl<const N: usize> AsyncEncode for FixedBitSet<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_all(self.bytes()).await
+    }
+}
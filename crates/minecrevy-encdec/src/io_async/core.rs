@@ -2,9 +2,12 @@ use embedded_byteorder::{
     AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian, ReadExactError,
 };
 
+use heapless::Vec;
+
 use crate::{
-    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, ReadMinecraftError,
-    options::IntOptions,
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, ReadArrayError,
+    ReadMinecraftError, ReadOptionError, WriteArrayError, WriteOptionError,
+    options::{ArrayOptions, FixedArrayOptions, Framing, IntOptions},
 };
 
 macro_rules! impl_primitive {
@@ -42,11 +45,38 @@ impl_primitive!(
     i16: AsyncReadBytesExt::read_i16::<BigEndian>, AsyncWriteBytesExt::write_i16::<BigEndian>;
     u32: AsyncReadBytesExt::read_u32::<BigEndian>, AsyncWriteBytesExt::write_u32::<BigEndian>;
     u64: AsyncReadBytesExt::read_u64::<BigEndian>, AsyncWriteBytesExt::write_u64::<BigEndian>;
-    i64: AsyncReadBytesExt::read_i64::<BigEndian>, AsyncWriteBytesExt::write_i64::<BigEndian>;
     f32: AsyncReadBytesExt::read_f32::<BigEndian>, AsyncWriteBytesExt::write_f32::<BigEndian>;
     f64: AsyncReadBytesExt::read_f64::<BigEndian>, AsyncWriteBytesExt::write_f64::<BigEndian>;
 );
 
+/// Any non-zero byte is `true`, like Java's `DataInput::readBoolean`.
+impl AsyncDecode for bool {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    #[inline]
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        Ok(reader.read_u8().await? != 0)
+    }
+}
+
+impl AsyncEncode for bool {
+    type Options = ();
+    type Error<E> = E;
+
+    #[inline]
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_u8(u8::from(*self)).await
+    }
+}
+
 impl AsyncDecode for i32 {
     type Options = IntOptions;
     type Error<E> = ReadMinecraftError<E>;
@@ -81,3 +111,166 @@ impl AsyncEncode for i32 {
         }
     }
 }
+
+impl AsyncDecode for i64 {
+    type Options = IntOptions;
+    type Error<E> = ReadMinecraftError<E>;
+
+    #[inline]
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        if varint {
+            reader.read_var_i64().await
+        } else {
+            Ok(reader.read_i64::<BigEndian>().await?)
+        }
+    }
+}
+
+impl AsyncEncode for i64 {
+    type Options = IntOptions;
+    type Error<E> = E;
+
+    #[inline]
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if varint {
+            writer.write_var_i64(*self).await
+        } else {
+            writer.write_i64::<BigEndian>(*self).await
+        }
+    }
+}
+
+impl AsyncEncode for str {
+    type Options = ();
+    type Error<E> = E;
+
+    #[inline]
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_string(self).await
+    }
+}
+
+impl<T: AsyncEncode + ?Sized> AsyncEncode for &T {
+    type Options = T::Options;
+    type Error<E> = T::Error<E>;
+
+    #[inline]
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        T::encode(self, writer, options).await
+    }
+}
+
+/// Prefixed with a boolean that says whether the value is present.
+impl<T: AsyncDecode> AsyncDecode for Option<T> {
+    type Options = T::Options;
+    type Error<E> = ReadOptionError<E, T::Error<E>>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        options: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let present = reader
+            .read_u8()
+            .await
+            .map_err(|e| ReadOptionError::Present(e.into()))?;
+        if present == 0 {
+            return Ok(None);
+        }
+        T::decode(reader, options)
+            .await
+            .map(Some)
+            .map_err(ReadOptionError::Value)
+    }
+}
+
+impl<T: AsyncEncode> AsyncEncode for Option<T> {
+    type Options = T::Options;
+    type Error<E> = WriteOptionError<E, T::Error<E>>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer
+            .write_u8(u8::from(self.is_some()))
+            .await
+            .map_err(WriteOptionError::Present)?;
+        match self {
+            Some(value) => value
+                .encode(writer, options)
+                .await
+                .map_err(WriteOptionError::Value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: AsyncEncode> AsyncEncode for [T] {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        ArrayOptions { framing, element }: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if framing == Framing::VarInt {
+            let len = i32::try_from(self.len()).map_err(|_| WriteArrayError::LengthExceeded)?;
+            writer
+                .write_var_i32(len)
+                .await
+                .map_err(WriteArrayError::Length)?;
+        }
+        for (i, item) in self.iter().enumerate() {
+            item.encode(writer, element.clone())
+                .await
+                .map_err(|e| WriteArrayError::Element(i, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsyncDecode, const N: usize> AsyncDecode for [T; N] {
+    type Options = FixedArrayOptions<T::Options>;
+    type Error<E> = ReadArrayError<E, T::Error<E>>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        options: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        Vec::<T, N>::decode(reader, options.into())
+            .await?
+            .into_array()
+            .map_err(|_| ReadArrayError::TooShort)
+    }
+}
+
+impl<T: AsyncEncode, const N: usize> AsyncEncode for [T; N] {
+    type Options = FixedArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    #[inline]
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        self.as_slice().encode(writer, options.into()).await
+    }
+}
@@ -1,8 +1,11 @@
 use embedded_byteorder::{AsyncRead, AsyncWrite};
-use heapless::String;
+use heapless::{String, Vec};
 
 use crate::{
-    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, ReadMinecraftError,
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, ReadArrayError,
+    ReadMinecraftError, WriteArrayError,
+    options::{ArrayOptions, Framing},
+    peek::Peeked,
 };
 
 impl<const N: usize> AsyncDecode for String<N> {
@@ -29,3 +32,111 @@ impl<const N: usize> AsyncEncode for String<N> {
         writer.write_string(self).await
     }
 }
+
+impl<T: AsyncDecode, const N: usize> AsyncDecode for Vec<T, N> {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = ReadArrayError<E, T::Error<E>>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        ArrayOptions { framing, element }: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let mut items = Vec::new();
+        let len = match framing {
+            Framing::VarInt => {
+                let len = reader
+                    .read_var_i32()
+                    .await
+                    .map_err(ReadArrayError::Length)?;
+                usize::try_from(len).map_err(|_| ReadArrayError::LengthExceeded)?
+            }
+            Framing::Fixed => N,
+            Framing::Remaining => {
+                let mut byte = [0];
+                while reader
+                    .read(&mut byte)
+                    .await
+                    .map_err(|e| ReadArrayError::Length(ReadMinecraftError::Other(e)))?
+                    != 0
+                {
+                    let mut reader = Peeked::new(byte[0], reader);
+                    let item = T::decode(&mut reader, element.clone())
+                        .await
+                        .map_err(|e| ReadArrayError::Element(items.len(), e))?;
+                    items
+                        .push(item)
+                        .map_err(|_| ReadArrayError::LengthExceeded)?;
+                }
+                return Ok(items);
+            }
+        };
+        if len > N {
+            return Err(ReadArrayError::LengthExceeded);
+        }
+        for i in 0..len {
+            let item = T::decode(reader, element.clone())
+                .await
+                .map_err(|e| ReadArrayError::Element(i, e))?;
+            // Cannot fail, as `len` is at most `N`.
+            let _ = items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: AsyncEncode, const N: usize> AsyncEncode for Vec<T, N> {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if options.framing == Framing::Fixed && !self.is_full() {
+            return Err(WriteArrayError::NotFull);
+        }
+        self.as_slice().encode(writer, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::block_on;
+
+    fn options(framing: Framing) -> ArrayOptions<()> {
+        ArrayOptions {
+            framing,
+            element: (),
+        }
+    }
+
+    #[test]
+    fn round_trips_each_framing() {
+        let full = Vec::<u8, 4>::from_slice(&[1, 2, 3, 4]).unwrap();
+        let partial = Vec::<u8, 4>::from_slice(&[1, 2]).unwrap();
+        for (vec, framing) in [
+            (&partial, Framing::VarInt),
+            (&full, Framing::Fixed),
+            (&partial, Framing::Remaining),
+        ] {
+            let mut buf = [0; 8];
+            let mut writer = &mut buf[..];
+            block_on(vec.encode(&mut writer, options(framing))).unwrap();
+            let len = 8 - writer.len();
+
+            let mut reader = &buf[..len];
+            let decoded = block_on(Vec::<u8, 4>::decode(&mut reader, options(framing))).unwrap();
+            assert_eq!(&decoded, vec);
+        }
+    }
+
+    #[test]
+    fn fixed_rejects_vec_that_is_not_full() {
+        let vec = Vec::<u8, 4>::from_slice(&[1, 2]).unwrap();
+        let mut buf = [0; 8];
+        let result = block_on(vec.encode(&mut &mut buf[..], options(Framing::Fixed)));
+        assert_eq!(result, Err(WriteArrayError::NotFull));
+    }
+}
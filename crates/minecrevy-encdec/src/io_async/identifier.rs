@@ -0,0 +1,47 @@
+use embedded_byteorder::{AsyncRead, AsyncWrite};
+
+use crate::{
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, Identifier,
+    OwnedIdentifier, ReadMinecraftError,
+};
+
+impl AsyncEncode for Identifier<'_> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_var_i32(self.wire_len()).await?;
+        writer.write_all(self.namespace().as_bytes()).await?;
+        writer.write_all(b":").await?;
+        writer.write_all(self.path().as_bytes()).await
+    }
+}
+
+impl<const N: usize> AsyncDecode for OwnedIdentifier<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        reader.read_identifier().await
+    }
+}
+
+impl<const N: usize> AsyncEncode for OwnedIdentifier<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        self.as_identifier().encode(writer, ()).await
+    }
+}
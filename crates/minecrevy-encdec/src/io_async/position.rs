@@ -0,0 +1,30 @@
+use embedded_byteorder::{
+    AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian, ReadExactError,
+};
+
+use crate::{AsyncDecode, AsyncEncode, Position};
+
+impl AsyncDecode for Position {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        Ok(Self::unpack(reader.read_i64::<BigEndian>().await?))
+    }
+}
+
+impl AsyncEncode for Position {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_i64::<BigEndian>(self.pack()).await
+    }
+}
@@ -0,0 +1,31 @@
+use embedded_byteorder::{AsyncRead, AsyncWrite};
+use uuid::Uuid;
+
+use crate::{
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, ReadMinecraftError,
+};
+
+impl AsyncDecode for Uuid {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        reader.read_uuid().await
+    }
+}
+
+impl AsyncEncode for Uuid {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_uuid(*self).await
+    }
+}
@@ -1,5 +1,14 @@
 use embedded_byteorder::{Read, Write};
 
+mod bitset;
+mod core;
+mod heapless;
+mod identifier;
+mod position;
+mod uuid;
+
+pub use minecrevy_encdec_macros::{Decode, Encode};
+
 pub trait Decode: Sized {
     type Options: Clone + Default;
     type Error<E>;
@@ -0,0 +1,67 @@
+use embedded_byteorder::{BigEndian, Read, ReadBytesExt, ReadExactError, Write, WriteBytesExt};
+
+use crate::{
+    BitSet, Decode, Encode, FixedBitSet, ReadMinecraftError, ReadMinecraftExt, WriteMinecraftExt,
+};
+
+impl<const N: usize> Decode for BitSet<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        let len = reader.read_var_i32()?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= N)
+            .ok_or(ReadMinecraftError::LengthExceeded)?;
+
+        let mut words = [0; N];
+        for word in &mut words[..len] {
+            *word = reader.read_u64::<BigEndian>()?;
+        }
+        Ok(Self::from_words(words))
+    }
+}
+
+impl<const N: usize> Encode for BitSet<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let words = self.trimmed();
+        // At most `N` longs, which is never near `i32::MAX`.
+        writer.write_var_i32(words.len() as i32)?;
+        for &word in words {
+            writer.write_u64::<BigEndian>(word)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Decode for FixedBitSet<N> {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        let mut bytes = [0; N];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl<const N: usize> Encode for FixedBitSet<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_all(self.bytes())
+    }
+}
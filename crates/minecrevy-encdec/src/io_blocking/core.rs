@@ -0,0 +1,400 @@
+use embedded_byteorder::{BigEndian, Read, ReadBytesExt, ReadExactError, Write, WriteBytesExt};
+
+use heapless::Vec;
+
+use crate::{
+    Decode, Encode, ReadArrayError, ReadMinecraftError, ReadMinecraftExt, ReadOptionError,
+    WriteArrayError, WriteMinecraftExt, WriteOptionError,
+    options::{ArrayOptions, FixedArrayOptions, Framing, IntOptions},
+};
+
+macro_rules! impl_primitive {
+    ($($ty:ty: $dec:expr, $enc:expr;)*) => {
+        $(
+            impl Decode for $ty {
+                type Options = ();
+                type Error<E> = ReadExactError<E>;
+
+                #[inline]
+                fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>>
+                {
+                    $dec(reader)
+                }
+            }
+
+            impl Encode for $ty {
+                type Options = ();
+                type Error<E> = E;
+
+                #[inline]
+                fn encode<W: Write>(&self, writer: &mut W, (): Self::Options) -> Result<(), Self::Error<W::Error>>
+                {
+                    $enc(writer, *self)
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(
+    u8: ReadBytesExt::read_u8, WriteBytesExt::write_u8;
+    i8: ReadBytesExt::read_i8, WriteBytesExt::write_i8;
+    u16: ReadBytesExt::read_u16::<BigEndian>, WriteBytesExt::write_u16::<BigEndian>;
+    i16: ReadBytesExt::read_i16::<BigEndian>, WriteBytesExt::write_i16::<BigEndian>;
+    u32: ReadBytesExt::read_u32::<BigEndian>, WriteBytesExt::write_u32::<BigEndian>;
+    u64: ReadBytesExt::read_u64::<BigEndian>, WriteBytesExt::write_u64::<BigEndian>;
+    f32: ReadBytesExt::read_f32::<BigEndian>, WriteBytesExt::write_f32::<BigEndian>;
+    f64: ReadBytesExt::read_f64::<BigEndian>, WriteBytesExt::write_f64::<BigEndian>;
+);
+
+/// Any non-zero byte is `true`, like Java's `DataInput::readBoolean`.
+impl Decode for bool {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    #[inline]
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        Ok(reader.read_u8()? != 0)
+    }
+}
+
+impl Encode for bool {
+    type Options = ();
+    type Error<E> = E;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_u8(u8::from(*self))
+    }
+}
+
+impl Decode for i32 {
+    type Options = IntOptions;
+    type Error<E> = ReadMinecraftError<E>;
+
+    #[inline]
+    fn decode<R: Read>(
+        reader: &mut R,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        if varint {
+            reader.read_var_i32()
+        } else {
+            Ok(reader.read_i32::<BigEndian>()?)
+        }
+    }
+}
+
+impl Encode for i32 {
+    type Options = IntOptions;
+    type Error<E> = E;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if varint {
+            writer.write_var_i32(*self)
+        } else {
+            writer.write_i32::<BigEndian>(*self)
+        }
+    }
+}
+
+impl Decode for i64 {
+    type Options = IntOptions;
+    type Error<E> = ReadMinecraftError<E>;
+
+    #[inline]
+    fn decode<R: Read>(
+        reader: &mut R,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        if varint {
+            reader.read_var_i64()
+        } else {
+            Ok(reader.read_i64::<BigEndian>()?)
+        }
+    }
+}
+
+impl Encode for i64 {
+    type Options = IntOptions;
+    type Error<E> = E;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        IntOptions { varint }: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if varint {
+            writer.write_var_i64(*self)
+        } else {
+            writer.write_i64::<BigEndian>(*self)
+        }
+    }
+}
+
+impl Encode for str {
+    type Options = ();
+    type Error<E> = E;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_string(self)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    type Options = T::Options;
+    type Error<E> = T::Error<E>;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        T::encode(self, writer, options)
+    }
+}
+
+/// Prefixed with a boolean that says whether the value is present.
+impl<T: Decode> Decode for Option<T> {
+    type Options = T::Options;
+    type Error<E> = ReadOptionError<E, T::Error<E>>;
+
+    fn decode<R: Read>(
+        reader: &mut R,
+        options: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let present = reader
+            .read_u8()
+            .map_err(|e| ReadOptionError::Present(e.into()))?;
+        if present == 0 {
+            return Ok(None);
+        }
+        T::decode(reader, options)
+            .map(Some)
+            .map_err(ReadOptionError::Value)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    type Options = T::Options;
+    type Error<E> = WriteOptionError<E, T::Error<E>>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer
+            .write_u8(u8::from(self.is_some()))
+            .map_err(WriteOptionError::Present)?;
+        match self {
+            Some(value) => value
+                .encode(writer, options)
+                .map_err(WriteOptionError::Value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        ArrayOptions { framing, element }: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if framing == Framing::VarInt {
+            let len = i32::try_from(self.len()).map_err(|_| WriteArrayError::LengthExceeded)?;
+            writer.write_var_i32(len).map_err(WriteArrayError::Length)?;
+        }
+        for (i, item) in self.iter().enumerate() {
+            item.encode(writer, element.clone())
+                .map_err(|e| WriteArrayError::Element(i, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    type Options = FixedArrayOptions<T::Options>;
+    type Error<E> = ReadArrayError<E, T::Error<E>>;
+
+    fn decode<R: Read>(
+        reader: &mut R,
+        options: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        Vec::<T, N>::decode(reader, options.into())?
+            .into_array()
+            .map_err(|_| ReadArrayError::TooShort)
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    type Options = FixedArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    #[inline]
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        self.as_slice().encode(writer, options.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{convert::Infallible, fmt::Debug};
+
+    use super::*;
+    use crate::{
+        AsyncDecode,
+        test_util::{block_on, encode_with},
+    };
+
+    /// Decodes `bytes` with both the blocking and the async decoder, checking
+    /// that they agree and consume every byte.
+    fn decode_with<T, O: Clone>(bytes: &[u8], options: O) -> T
+    where
+        T: Decode<Options = O> + AsyncDecode<Options = O> + PartialEq + Debug,
+        <T as Decode>::Error<Infallible>: Debug,
+        <T as AsyncDecode>::Error<Infallible>: Debug,
+    {
+        let mut reader = bytes;
+        let blocking = <T as Decode>::decode(&mut reader, options.clone()).unwrap();
+        assert!(reader.is_empty());
+
+        let mut reader = bytes;
+        let decoded = block_on(<T as AsyncDecode>::decode(&mut reader, options)).unwrap();
+        assert!(reader.is_empty());
+
+        assert_eq!(decoded, blocking);
+        decoded
+    }
+
+    fn array(framing: Framing) -> FixedArrayOptions<IntOptions> {
+        FixedArrayOptions {
+            framing,
+            element: IntOptions { varint: true },
+        }
+    }
+
+    fn vec(framing: Framing) -> ArrayOptions<IntOptions> {
+        array(framing).into()
+    }
+
+    #[test]
+    fn array_defaults_to_fixed() {
+        assert_eq!(FixedArrayOptions::<()>::default().framing, Framing::Fixed);
+        assert_eq!(ArrayOptions::<()>::default().framing, Framing::VarInt);
+
+        let value = [1u8, 2, 3];
+        assert_eq!(encode_with(&value, Default::default()), [1, 2, 3]);
+        assert_eq!(
+            decode_with::<[u8; 3], _>(&[1, 2, 3], Default::default()),
+            value
+        );
+    }
+
+    #[test]
+    fn array_framings() {
+        let value = [1, 300];
+        let cases: [(Framing, &[u8]); 3] = [
+            (Framing::VarInt, &[2, 1, 0xAC, 0x02]),
+            (Framing::Fixed, &[1, 0xAC, 0x02]),
+            (Framing::Remaining, &[1, 0xAC, 0x02]),
+        ];
+        for (framing, bytes) in cases {
+            assert_eq!(encode_with(&value, array(framing)), bytes, "{framing:?}");
+            assert_eq!(decode_with::<[i32; 2], _>(bytes, array(framing)), value);
+        }
+    }
+
+    #[test]
+    fn array_rejects_wrong_number_of_elements() {
+        // Too few elements, whether the prefix or the end of the reader says so.
+        for (framing, bytes) in [(Framing::VarInt, &[1, 7][..]), (Framing::Remaining, &[7])] {
+            let mut reader = bytes;
+            let result = <[i32; 2] as Decode>::decode(&mut reader, array(framing));
+            assert!(
+                matches!(result, Err(ReadArrayError::TooShort)),
+                "{framing:?}"
+            );
+        }
+
+        let mut reader = &[3, 1, 2, 3][..];
+        let result = <[i32; 2] as Decode>::decode(&mut reader, array(Framing::VarInt));
+        assert!(matches!(result, Err(ReadArrayError::LengthExceeded)));
+    }
+
+    #[test]
+    fn vec_framings() {
+        let value = Vec::<i32, 4>::from_slice(&[1, 300]).unwrap();
+        let cases: [(Framing, &[u8]); 2] = [
+            (Framing::VarInt, &[2, 1, 0xAC, 0x02]),
+            (Framing::Remaining, &[1, 0xAC, 0x02]),
+        ];
+        for (framing, bytes) in cases {
+            assert_eq!(encode_with(&value, vec(framing)), bytes, "{framing:?}");
+            assert_eq!(decode_with::<Vec<i32, 4>, _>(bytes, vec(framing)), value);
+        }
+
+        let full = Vec::<i32, 2>::from_slice(&[1, 300]).unwrap();
+        let bytes = [1, 0xAC, 0x02];
+        assert_eq!(encode_with(&full, vec(Framing::Fixed)), bytes);
+        assert_eq!(
+            decode_with::<Vec<i32, 2>, _>(&bytes, vec(Framing::Fixed)),
+            full
+        );
+    }
+
+    #[test]
+    fn option_passes_framing_through() {
+        let cases: [(Framing, &[u8]); 3] = [
+            (Framing::VarInt, &[1, 2, 1, 0xAC, 0x02]),
+            (Framing::Fixed, &[1, 1, 0xAC, 0x02]),
+            (Framing::Remaining, &[1, 1, 0xAC, 0x02]),
+        ];
+        for (framing, bytes) in cases {
+            let value = Some([1, 300]);
+            assert_eq!(encode_with(&value, array(framing)), bytes, "{framing:?}");
+            assert_eq!(
+                decode_with::<Option<[i32; 2]>, _>(bytes, array(framing)),
+                value
+            );
+
+            let value = Some(Vec::<i32, 2>::from_slice(&[1, 300]).unwrap());
+            assert_eq!(encode_with(&value, vec(framing)), bytes, "{framing:?}");
+            assert_eq!(
+                decode_with::<Option<Vec<i32, 2>>, _>(bytes, vec(framing)),
+                value
+            );
+
+            let none: Option<[i32; 2]> = None;
+            assert_eq!(encode_with(&none, array(framing)), [0]);
+            assert_eq!(
+                decode_with::<Option<[i32; 2]>, _>(&[0], array(framing)),
+                none
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use embedded_byteorder::{Read, Write};
+use heapless::{String, Vec};
+
+use crate::{
+    Decode, Encode, ReadArrayError, ReadMinecraftError, ReadMinecraftExt, WriteArrayError,
+    WriteMinecraftExt,
+    options::{ArrayOptions, Framing},
+    peek::Peeked,
+};
+
+impl<const N: usize> Decode for String<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        reader.read_string()
+    }
+}
+
+impl<const N: usize> Encode for String<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_string(self)
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for Vec<T, N> {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = ReadArrayError<E, T::Error<E>>;
+
+    fn decode<R: Read>(
+        reader: &mut R,
+        ArrayOptions { framing, element }: Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let mut items = Vec::new();
+        let len = match framing {
+            Framing::VarInt => {
+                let len = reader.read_var_i32().map_err(ReadArrayError::Length)?;
+                usize::try_from(len).map_err(|_| ReadArrayError::LengthExceeded)?
+            }
+            Framing::Fixed => N,
+            Framing::Remaining => {
+                let mut byte = [0];
+                while reader
+                    .read(&mut byte)
+                    .map_err(|e| ReadArrayError::Length(ReadMinecraftError::Other(e)))?
+                    != 0
+                {
+                    let mut reader = Peeked::new(byte[0], reader);
+                    let item = T::decode(&mut reader, element.clone())
+                        .map_err(|e| ReadArrayError::Element(items.len(), e))?;
+                    items
+                        .push(item)
+                        .map_err(|_| ReadArrayError::LengthExceeded)?;
+                }
+                return Ok(items);
+            }
+        };
+        if len > N {
+            return Err(ReadArrayError::LengthExceeded);
+        }
+        for i in 0..len {
+            let item =
+                T::decode(reader, element.clone()).map_err(|e| ReadArrayError::Element(i, e))?;
+            // Cannot fail, as `len` is at most `N`.
+            let _ = items.push(item);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for Vec<T, N> {
+    type Options = ArrayOptions<T::Options>;
+    type Error<E> = WriteArrayError<E, T::Error<E>>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        options: Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        if options.framing == Framing::Fixed && !self.is_full() {
+            return Err(WriteArrayError::NotFull);
+        }
+        self.as_slice().encode(writer, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(framing: Framing) -> ArrayOptions<()> {
+        ArrayOptions {
+            framing,
+            element: (),
+        }
+    }
+
+    fn round_trip(vec: &Vec<u8, 4>, framing: Framing, expected: &[u8]) {
+        let mut buf = [0; 8];
+        let mut writer = &mut buf[..];
+        vec.encode(&mut writer, options(framing)).unwrap();
+        let len = 8 - writer.len();
+        assert_eq!(&buf[..len], expected);
+
+        let mut reader = &buf[..len];
+        let decoded = Vec::<u8, 4>::decode(&mut reader, options(framing)).unwrap();
+        assert_eq!(&decoded, vec);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn round_trips_var_int() {
+        round_trip(
+            &Vec::from_slice(&[1, 2]).unwrap(),
+            Framing::VarInt,
+            &[2, 1, 2],
+        );
+        round_trip(&Vec::new(), Framing::VarInt, &[0]);
+    }
+
+    #[test]
+    fn round_trips_fixed() {
+        round_trip(
+            &Vec::from_slice(&[1, 2, 3, 4]).unwrap(),
+            Framing::Fixed,
+            &[1, 2, 3, 4],
+        );
+    }
+
+    #[test]
+    fn round_trips_remaining() {
+        round_trip(
+            &Vec::from_slice(&[1, 2]).unwrap(),
+            Framing::Remaining,
+            &[1, 2],
+        );
+        round_trip(&Vec::new(), Framing::Remaining, &[]);
+    }
+
+    #[test]
+    fn fixed_rejects_vec_that_is_not_full() {
+        let vec = Vec::<u8, 4>::from_slice(&[1, 2]).unwrap();
+        let mut buf = [0; 8];
+        let result = vec.encode(&mut &mut buf[..], options(Framing::Fixed));
+        assert_eq!(result, Err(WriteArrayError::NotFull));
+    }
+
+    #[test]
+    fn var_int_rejects_too_many_elements() {
+        let mut reader = &[5, 1, 2, 3, 4, 5][..];
+        let result = Vec::<u8, 4>::decode(&mut reader, options(Framing::VarInt));
+        assert!(matches!(result, Err(ReadArrayError::LengthExceeded)));
+    }
+}
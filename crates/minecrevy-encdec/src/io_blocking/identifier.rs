@@ -0,0 +1,44 @@
+use embedded_byteorder::{Read, Write};
+
+use crate::{
+    Decode, Encode, Identifier, OwnedIdentifier, ReadMinecraftError, ReadMinecraftExt,
+    WriteMinecraftExt,
+};
+
+impl Encode for Identifier<'_> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_var_i32(self.wire_len())?;
+        writer.write_all(self.namespace().as_bytes())?;
+        writer.write_all(b":")?;
+        writer.write_all(self.path().as_bytes())
+    }
+}
+
+impl<const N: usize> Decode for OwnedIdentifier<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        reader.read_identifier()
+    }
+}
+
+impl<const N: usize> Encode for OwnedIdentifier<N> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        self.as_identifier().encode(writer, ())
+    }
+}
@@ -0,0 +1,25 @@
+use embedded_byteorder::{BigEndian, Read, ReadBytesExt, ReadExactError, Write, WriteBytesExt};
+
+use crate::{Decode, Encode, Position};
+
+impl Decode for Position {
+    type Options = ();
+    type Error<E> = ReadExactError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        Ok(Self::unpack(reader.read_i64::<BigEndian>()?))
+    }
+}
+
+impl Encode for Position {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_i64::<BigEndian>(self.pack())
+    }
+}
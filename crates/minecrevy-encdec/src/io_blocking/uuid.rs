@@ -0,0 +1,26 @@
+use embedded_byteorder::{Read, Write};
+use uuid::Uuid;
+
+use crate::{Decode, Encode, ReadMinecraftError, ReadMinecraftExt, WriteMinecraftExt};
+
+impl Decode for Uuid {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        reader.read_uuid()
+    }
+}
+
+impl Encode for Uuid {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_uuid(*self)
+    }
+}
@@ -0,0 +1,177 @@
+use ::core::convert::Infallible;
+
+use crate::{
+    BorrowedPacket, Compression, Decode, RawPacket, ReadBorrowedPacketError, ReadMinecraftError,
+    ReadMinecraftExt,
+};
+
+mod core;
+mod identifier;
+
+/// Decodes a value that can point into the bytes it is decoded from, so that
+/// strings and byte arrays are not copied.
+///
+/// Every [`Decode`] type implements it too, by copying as usual.
+pub trait BorrowDecode<'a>: Sized {
+    type Options: Clone + Default;
+    type Error;
+
+    fn decode(reader: &mut &'a [u8], options: Self::Options) -> Result<Self, Self::Error>;
+}
+
+impl<'a, T: Decode> BorrowDecode<'a> for T {
+    type Options = T::Options;
+    type Error = T::Error<Infallible>;
+
+    #[inline]
+    fn decode(reader: &mut &'a [u8], options: Self::Options) -> Result<Self, Self::Error> {
+        Decode::decode(reader, options)
+    }
+}
+
+impl<'a> BorrowedPacket<'a> {
+    /// Reads a packet frame, framed according to `compression`, from the
+    /// start of `buf`, and advances `buf` past it.
+    ///
+    /// `buf` is only advanced when a packet is returned, so after
+    /// [`ReadBorrowedPacketError::Incomplete`] the caller can receive more
+    /// bytes and try again. A compressed packet has no body to borrow from,
+    /// so it fails with [`ReadBorrowedPacketError::Compressed`] and is left
+    /// in `buf` for [`AsyncReadMinecraftExt::read_packet`] to inflate.
+    ///
+    /// [`AsyncReadMinecraftExt::read_packet`]: crate::AsyncReadMinecraftExt::read_packet
+    pub fn read(
+        buf: &mut &'a [u8],
+        compression: Compression,
+    ) -> Result<Self, ReadBorrowedPacketError> {
+        let mut rest = *buf;
+        let len = rest.read_var_i32().map_err(|e| match e {
+            ReadMinecraftError::UnexpectedEof => ReadBorrowedPacketError::Incomplete,
+            e => ReadBorrowedPacketError::Length(e),
+        })?;
+        let mut frame = take(&mut rest, len).map_err(|e| match e {
+            ReadMinecraftError::UnexpectedEof => ReadBorrowedPacketError::Incomplete,
+            e => ReadBorrowedPacketError::Length(e),
+        })?;
+
+        if let Compression::Enabled { .. } = compression {
+            // Packets below the threshold aren't compressed and say so with a
+            // data length of 0.
+            let data_len = frame
+                .read_var_i32()
+                .map_err(ReadBorrowedPacketError::DataLength)?;
+            if data_len != 0 {
+                return Err(ReadBorrowedPacketError::Compressed);
+            }
+        }
+
+        let id = frame.read_var_i32().map_err(ReadBorrowedPacketError::Id)?;
+        *buf = rest;
+        Ok(Self { id, data: frame })
+    }
+
+    /// Decodes the body, leaving whatever `T` doesn't read.
+    pub fn decode<T: BorrowDecode<'a>>(&self, options: T::Options) -> Result<T, T::Error> {
+        T::decode(&mut &self.data[..], options)
+    }
+}
+
+impl<const N: usize> RawPacket<N> {
+    /// The packet, borrowing the body.
+    pub fn as_borrowed(&self) -> BorrowedPacket<'_> {
+        BorrowedPacket {
+            id: self.id,
+            data: &self.data,
+        }
+    }
+}
+
+/// Splits the first `len` bytes off `reader`.
+pub(crate) fn take<'a>(
+    reader: &mut &'a [u8],
+    len: i32,
+) -> Result<&'a [u8], ReadMinecraftError<Infallible>> {
+    let len = usize::try_from(len).map_err(|_| ReadMinecraftError::LengthExceeded)?;
+    if len > reader.len() {
+        return Err(ReadMinecraftError::UnexpectedEof);
+    }
+    let (taken, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPRESSED: Compression = Compression::Enabled { threshold: 256 };
+
+    #[test]
+    fn read_advances_past_frame() {
+        let mut buf = &[0x03, 0x01, 0xaa, 0xbb, 0x02, 0x02, 0xcc][..];
+        let packet = BorrowedPacket::read(&mut buf, Compression::Disabled).unwrap();
+        assert_eq!(
+            packet,
+            BorrowedPacket {
+                id: 1,
+                data: &[0xaa, 0xbb]
+            }
+        );
+        assert_eq!(buf, &[0x02, 0x02, 0xcc]);
+    }
+
+    #[test]
+    fn read_leaves_incomplete_frame() {
+        for frame in [&[][..], &[0x80][..], &[0x03, 0x01, 0xaa][..]] {
+            let mut buf = frame;
+            let result = BorrowedPacket::read(&mut buf, Compression::Disabled);
+            assert_eq!(result, Err(ReadBorrowedPacketError::Incomplete));
+            assert_eq!(buf, frame);
+        }
+    }
+
+    #[test]
+    fn read_rejects_malformed_frame() {
+        let mut buf = &[0x00, 0x00][..];
+        let result = BorrowedPacket::read(&mut buf, Compression::Disabled);
+        assert_eq!(
+            result,
+            Err(ReadBorrowedPacketError::Id(
+                ReadMinecraftError::UnexpectedEof
+            ))
+        );
+        assert_eq!(buf, &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn read_uncompressed_frame_with_compression_enabled() {
+        let mut buf = &[0x03, 0x00, 0x01, 0xaa][..];
+        let packet = BorrowedPacket::read(&mut buf, COMPRESSED).unwrap();
+        assert_eq!(
+            packet,
+            BorrowedPacket {
+                id: 1,
+                data: &[0xaa]
+            }
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn read_rejects_compressed_frame() {
+        let frame = &[0x04, 0x80, 0x02, 0x78, 0x9c][..];
+        let mut buf = frame;
+        let result = BorrowedPacket::read(&mut buf, COMPRESSED);
+        assert_eq!(result, Err(ReadBorrowedPacketError::Compressed));
+        assert_eq!(buf, frame);
+    }
+
+    #[test]
+    fn decode_borrows_body() {
+        let packet = BorrowedPacket {
+            id: 0,
+            data: &[0x02, b'h', b'i', 0x01],
+        };
+        assert_eq!(packet.decode::<&str>(()), Ok("hi"));
+    }
+}
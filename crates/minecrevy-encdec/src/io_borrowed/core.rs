@@ -0,0 +1,42 @@
+use core::convert::Infallible;
+
+use crate::{
+    BorrowDecode, ReadMinecraftError, ReadMinecraftExt, io_borrowed::take, options::BytesOptions,
+};
+
+impl<'a> BorrowDecode<'a> for &'a str {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        let bytes = <&[u8]>::decode(reader, BytesOptions::default())?;
+        core::str::from_utf8(bytes).map_err(|_| ReadMinecraftError::InvalidUtf8)
+    }
+}
+
+impl<'a> BorrowDecode<'a> for &'a [u8] {
+    type Options = BytesOptions;
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], options: Self::Options) -> Result<Self, Self::Error> {
+        if options.remaining {
+            return Ok(core::mem::take(reader));
+        }
+        let len = reader.read_var_i32()?;
+        take(reader, len)
+    }
+}
+
+/// Not prefixed, like `[u8; N]`.
+impl<'a, const N: usize> BorrowDecode<'a> for &'a [u8; N] {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        let (bytes, rest) = reader
+            .split_first_chunk()
+            .ok_or(ReadMinecraftError::UnexpectedEof)?;
+        *reader = rest;
+        Ok(bytes)
+    }
+}
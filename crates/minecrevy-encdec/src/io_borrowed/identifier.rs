@@ -0,0 +1,13 @@
+use core::convert::Infallible;
+
+use crate::{BorrowDecode, Identifier, ReadMinecraftError};
+
+impl<'a> BorrowDecode<'a> for Identifier<'a> {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        let identifier = <&str>::decode(reader, ())?;
+        Identifier::parse(identifier).map_err(|_| ReadMinecraftError::InvalidIdentifier)
+    }
+}
@@ -1,16 +1,36 @@
 #![no_std]
 
+mod bitset;
+mod compression;
 mod error;
 mod ext_async;
 mod ext_blocking;
+mod identifier;
 mod io_async;
 mod io_blocking;
+mod io_borrowed;
+pub mod nbt;
 pub mod options;
+mod peek;
+mod position;
 mod size;
+#[cfg(test)]
+mod test_util;
+mod window;
 
 use heapless::Vec;
 
-pub use self::{error::*, ext_async::*, ext_blocking::*, io_async::*, io_blocking::*, size::*};
+pub use self::{
+    bitset::*, compression::*, error::*, ext_async::*, ext_blocking::*, identifier::*, io_async::*,
+    io_blocking::*, io_borrowed::*, position::*, size::*, window::*,
+};
+
+/// Re-exports used by the code generated by the derive macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use defmt;
+    pub use embedded_byteorder;
+}
 
 pub const fn var_i32_size(value: i32) -> usize {
     static VAR_INT_LENGTHS: [usize; 33] = const {
@@ -31,8 +51,31 @@ pub const fn var_i32_size(value: i32) -> usize {
     VAR_INT_LENGTHS[value.leading_zeros() as usize]
 }
 
+pub const fn var_i64_size(value: i64) -> usize {
+    static VAR_LONG_LENGTHS: [usize; 65] = const {
+        let mut lengths = [0; 65];
+        let mut i: usize = 0;
+        while i < 64 {
+            lengths[i] = (64 - i).div_ceil(7);
+            i += 1;
+        }
+        lengths[64] = 1; // Special case for the number 0.
+        lengths
+    };
+
+    VAR_LONG_LENGTHS[value.leading_zeros() as usize]
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct RawPacket<const N: usize> {
     pub id: i32,
     pub data: Vec<u8, N>,
 }
+
+/// A packet whose body points into the buffer it was read from, such as a
+/// socket's receive buffer, and can be decoded with [`BorrowDecode`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BorrowedPacket<'a> {
+    pub id: i32,
+    pub data: &'a [u8],
+}
@@ -0,0 +1,116 @@
+//! Network NBT, the binary tag format used by registry data, chunk heightmaps,
+//! item components and text components.
+//!
+//! Since 1.20.2 the root tag sent over the network has no name: it is just a
+//! tag type followed by the tag's payload. Strings use Java's modified UTF-8.
+//!
+//! - [`Tag`] is a borrowed tree that can be built in a `const` and encoded
+//!   with [`AsyncEncode`](crate::AsyncEncode) or [`Encode`](crate::Encode).
+//! - [`AsyncCompoundWriter`] and [`AsyncListWriter`] stream NBT straight into
+//!   an [`AsyncWrite`](embedded_byteorder::AsyncWrite) without building a
+//!   tree, and [`CompoundWriter`] and [`ListWriter`] do the same for a
+//!   blocking [`Write`](embedded_byteorder::Write).
+//! - [`TagRef`] reads NBT from a byte slice without copying anything.
+
+use defmt::Format;
+use thiserror::Error;
+
+mod mutf8;
+mod reader;
+mod tag;
+mod writer;
+mod writer_async;
+mod writer_blocking;
+
+pub use self::{mutf8::*, reader::*, tag::*, writer_async::*, writer_blocking::*};
+
+/// The maximum nesting depth of compounds and lists that is read or written.
+///
+/// Vanilla allows up to 512 levels, but the encoder and the reader keep state
+/// per level, so the limit is kept low enough for microcontroller stacks.
+pub const MAX_DEPTH: usize = 32;
+
+/// The type of an NBT tag, as written before its payload.
+#[derive(Format, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+pub enum TagType {
+    End = 0,
+    Byte = 1,
+    Short = 2,
+    Int = 3,
+    Long = 4,
+    Float = 5,
+    Double = 6,
+    ByteArray = 7,
+    String = 8,
+    List = 9,
+    Compound = 10,
+    IntArray = 11,
+    LongArray = 12,
+}
+
+impl TryFrom<u8> for TagType {
+    type Error = NbtError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::End,
+            1 => Self::Byte,
+            2 => Self::Short,
+            3 => Self::Int,
+            4 => Self::Long,
+            5 => Self::Float,
+            6 => Self::Double,
+            7 => Self::ByteArray,
+            8 => Self::String,
+            9 => Self::List,
+            10 => Self::Compound,
+            11 => Self::IntArray,
+            12 => Self::LongArray,
+            _ => return Err(NbtError::InvalidTagType(value)),
+        })
+    }
+}
+
+/// Error type for reading NBT from a byte slice.
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NbtError {
+    #[error("unexpected EOF")]
+    UnexpectedEof,
+    #[error("invalid tag type {0}")]
+    InvalidTagType(u8),
+    #[error("negative length {0}")]
+    NegativeLength(i32),
+    #[error("invalid modified UTF-8")]
+    InvalidMutf8,
+    #[error("nesting deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+}
+
+/// Error type for writing NBT.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum NbtWriteError<E> {
+    #[error("string longer than 65535 bytes")]
+    StringTooLong,
+    #[error("nesting deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+    #[error("list element of type {found:?} written to a list of {expected:?}")]
+    ListType { expected: TagType, found: TagType },
+    #[error("list element count does not match its declared length")]
+    ListLength,
+    #[error("other error: {0}")]
+    Other(#[from] E),
+}
+
+impl<E> NbtWriteError<E> {
+    /// Maps the error of the underlying writer.
+    pub fn map_other<F>(self, f: impl FnOnce(E) -> F) -> NbtWriteError<F> {
+        match self {
+            Self::StringTooLong => NbtWriteError::StringTooLong,
+            Self::TooDeep => NbtWriteError::TooDeep,
+            Self::ListType { expected, found } => NbtWriteError::ListType { expected, found },
+            Self::ListLength => NbtWriteError::ListLength,
+            Self::Other(e) => NbtWriteError::Other(f(e)),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use core::fmt;
+
+use embedded_byteorder::{AsyncWrite, AsyncWriteBytesExt, BigEndian, Write, WriteBytesExt};
+
+use crate::nbt::{NbtError, NbtWriteError};
+
+/// Returns the number of bytes `value` takes up in Java's modified UTF-8,
+/// which encodes `'\0'` in two bytes and supplementary characters as two
+/// three-byte surrogates.
+pub fn mutf8_len(value: &str) -> usize {
+    pieces(value).map(|piece| piece.as_bytes().len()).sum()
+}
+
+/// Whether `c` is encoded differently in modified UTF-8 than in UTF-8.
+fn is_special(c: char) -> bool {
+    c == '\0' || c.len_utf8() == 4
+}
+
+/// A piece of a string in modified UTF-8: either a run of bytes that are
+/// valid modified UTF-8 as-is, or the encoding of a single character that
+/// differs from UTF-8.
+enum Piece<'a> {
+    Run(&'a [u8]),
+    Special([u8; 6], usize),
+}
+
+impl<'a> Piece<'a> {
+    fn special(c: char) -> Self {
+        if c == '\0' {
+            return Self::Special([0xC0, 0x80, 0, 0, 0, 0], 2);
+        }
+
+        let mut buf = [0; 6];
+        let mut units = [0u16; 2];
+        c.encode_utf16(&mut units);
+        for (unit, out) in units.iter().zip(buf.chunks_exact_mut(3)) {
+            out[0] = 0xE0 | (unit >> 12) as u8;
+            out[1] = 0x80 | ((unit >> 6) & 0x3F) as u8;
+            out[2] = 0x80 | (unit & 0x3F) as u8;
+        }
+        Self::Special(buf, 6)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Run(bytes) => bytes,
+            Self::Special(buf, len) => &buf[..*len],
+        }
+    }
+}
+
+/// Splits a string into the [`Piece`]s of its modified UTF-8 encoding.
+struct Pieces<'a> {
+    rest: &'a str,
+    pending: Option<char>,
+}
+
+fn pieces(value: &str) -> Pieces<'_> {
+    Pieces {
+        rest: value,
+        pending: None,
+    }
+}
+
+impl<'a> Iterator for Pieces<'a> {
+    type Item = Piece<'a>;
+
+    fn next(&mut self) -> Option<Piece<'a>> {
+        if let Some(c) = self.pending.take() {
+            return Some(Piece::special(c));
+        }
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let Some((i, c)) = self.rest.char_indices().find(|&(_, c)| is_special(c)) else {
+            let run = core::mem::take(&mut self.rest);
+            return Some(Piece::Run(run.as_bytes()));
+        };
+        let run = &self.rest[..i];
+        self.rest = &self.rest[i + c.len_utf8()..];
+        if run.is_empty() {
+            return Some(Piece::special(c));
+        }
+        self.pending = Some(c);
+        Some(Piece::Run(run.as_bytes()))
+    }
+}
+
+fn string_len<E>(value: &str) -> Result<u16, NbtWriteError<E>> {
+    u16::try_from(mutf8_len(value)).map_err(|_| NbtWriteError::StringTooLong)
+}
+
+/// Asynchronously writes an NBT string: a `u16` length followed by modified
+/// UTF-8.
+pub(crate) async fn write_mutf8<W: AsyncWrite + ?Sized>(
+    writer: &mut W,
+    value: &str,
+) -> Result<(), NbtWriteError<W::Error>> {
+    writer.write_u16::<BigEndian>(string_len(value)?).await?;
+    for piece in pieces(value) {
+        writer.write_all(piece.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Writes an NBT string: a `u16` length followed by modified UTF-8.
+pub(crate) fn write_mutf8_blocking<W: Write + ?Sized>(
+    writer: &mut W,
+    value: &str,
+) -> Result<(), NbtWriteError<W::Error>> {
+    writer.write_u16::<BigEndian>(string_len(value)?)?;
+    for piece in pieces(value) {
+        writer.write_all(piece.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// A borrowed string in Java's modified UTF-8, as found in NBT.
+///
+/// Most strings are plain ASCII or UTF-8 without `'\0'` and supplementary
+/// characters, in which case [`NbtStr::to_str`] returns them without copying.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NbtStr<'a>(&'a [u8]);
+
+impl<'a> NbtStr<'a> {
+    /// Checks that `bytes` is structurally valid modified UTF-8.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, NbtError> {
+        let mut rest = bytes;
+        while let [lead, tail @ ..] = rest {
+            let continuations = match lead {
+                0x01..=0x7F => 0,
+                0xC0..=0xDF => 1,
+                0xE0..=0xEF => 2,
+                _ => return Err(NbtError::InvalidMutf8),
+            };
+            let (cont, tail) = tail
+                .split_at_checked(continuations)
+                .ok_or(NbtError::InvalidMutf8)?;
+            if cont.iter().any(|b| b & 0xC0 != 0x80) {
+                return Err(NbtError::InvalidMutf8);
+            }
+            rest = tail;
+        }
+        Ok(Self(bytes))
+    }
+
+    /// The raw modified UTF-8 bytes of the string.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Returns the string as a `&str` if its modified UTF-8 encoding is also
+    /// valid UTF-8, which is the case unless it contains `'\0'` or
+    /// supplementary characters.
+    pub fn to_str(&self) -> Option<&'a str> {
+        core::str::from_utf8(self.0).ok()
+    }
+
+    /// Decodes the characters of the string, replacing unpaired surrogates
+    /// with [`char::REPLACEMENT_CHARACTER`].
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        char::decode_utf16(Units(self.0)).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+/// Decodes validated modified UTF-8 into UTF-16 code units.
+struct Units<'a>(&'a [u8]);
+
+impl Iterator for Units<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        let (unit, len) = match *self.0 {
+            [] => return None,
+            [a @ 0x00..=0x7F, ..] => (u16::from(a), 1),
+            [a @ 0xC0..=0xDF, b, ..] => ((u16::from(a & 0x1F) << 6) | u16::from(b & 0x3F), 2),
+            [a, b, c, ..] => (
+                (u16::from(a & 0x0F) << 12) | (u16::from(b & 0x3F) << 6) | u16::from(c & 0x3F),
+                3,
+            ),
+            _ => return None,
+        };
+        self.0 = &self.0[len..];
+        Some(unit)
+    }
+}
+
+impl PartialEq<str> for NbtStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        match self.to_str() {
+            Some(s) => s == other,
+            None => self.chars().eq(other.chars()),
+        }
+    }
+}
+
+impl fmt::Display for NbtStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_str() {
+            Some(s) => f.write_str(s),
+            None => self.chars().try_for_each(|c| fmt::Write::write_char(f, c)),
+        }
+    }
+}
+
+impl fmt::Debug for NbtStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        self.chars()
+            .flat_map(char::escape_debug)
+            .try_for_each(|c| fmt::Write::write_char(f, c))?;
+        f.write_str("\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{string::String, vec, vec::Vec};
+
+    use super::*;
+    use crate::test_util::block_on;
+
+    fn write(value: &str) -> Vec<u8> {
+        const MAX_LEN: usize = 64;
+
+        let mut blocking = vec![0; MAX_LEN];
+        let mut writer = &mut blocking[..];
+        write_mutf8_blocking(&mut writer, value).unwrap();
+        let len = MAX_LEN - writer.len();
+        blocking.truncate(len);
+
+        let mut buf = [0; MAX_LEN];
+        let mut writer = &mut buf[..];
+        block_on(write_mutf8(&mut writer, value)).unwrap();
+        assert_eq!(buf[..len], blocking);
+        blocking
+    }
+
+    #[test]
+    fn encodes_nul_in_two_bytes() {
+        assert_eq!(write("a\0b"), [0, 4, b'a', 0xC0, 0x80, b'b']);
+        assert_eq!(mutf8_len("\0"), 2);
+    }
+
+    #[test]
+    fn encodes_supplementary_characters_as_surrogates() {
+        // U+1F600 is the surrogate pair D83D DE00.
+        assert_eq!(
+            write("😀a😀"),
+            [
+                0, 13, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80, b'a', 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80
+            ],
+        );
+        assert_eq!(mutf8_len("😀"), 6);
+        // Other characters are the same as in UTF-8.
+        assert_eq!(write("é€"), [0, 5, 0xC3, 0xA9, 0xE2, 0x82, 0xAC]);
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let value = "\0x😀é\0";
+        let bytes = write(value);
+        let s = NbtStr::new(&bytes[2..]).unwrap();
+        assert_eq!(s.to_str(), None);
+        assert_eq!(s.chars().collect::<String>(), value);
+        assert!(s == *value);
+        assert_eq!(std::format!("{s}"), value);
+
+        let plain = NbtStr::new(b"plain").unwrap();
+        assert_eq!(plain.to_str(), Some("plain"));
+    }
+
+    #[test]
+    fn rejects_invalid_modified_utf8() {
+        // A raw nul, a four-byte UTF-8 sequence and a truncated sequence.
+        for bytes in [&[0][..], "😀".as_bytes(), &[0xE2, 0x82]] {
+            assert_eq!(NbtStr::new(bytes), Err(NbtError::InvalidMutf8), "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_strings_too_long() {
+        let value = "\0".repeat(usize::from(u16::MAX) / 2 + 1);
+        let mut buf = [0; 8];
+        assert_eq!(
+            write_mutf8_blocking(&mut &mut buf[..], &value),
+            Err(NbtWriteError::StringTooLong)
+        );
+    }
+}
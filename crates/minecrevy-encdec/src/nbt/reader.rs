@@ -0,0 +1,365 @@
+use crate::nbt::{MAX_DEPTH, NbtError, NbtStr, TagType};
+
+/// An NBT tag borrowed from a byte slice.
+///
+/// [`TagRef::parse`] validates the whole tree up front, so walking it
+/// afterwards cannot fail and never copies.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TagRef<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [u8]),
+    String(NbtStr<'a>),
+    List(ListRef<'a>),
+    Compound(CompoundRef<'a>),
+    IntArray(IntArrayRef<'a>),
+    LongArray(LongArrayRef<'a>),
+}
+
+impl<'a> TagRef<'a> {
+    /// Parses a nameless network NBT root tag from the start of `bytes`,
+    /// returning it along with the bytes that follow it.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), NbtError> {
+        let (ty, rest) = split_tag_type(bytes)?;
+        if ty == TagType::End {
+            return Err(NbtError::InvalidTagType(0));
+        }
+        split_payload(ty, rest, 0)
+    }
+
+    pub fn tag_type(&self) -> TagType {
+        match self {
+            Self::Byte(_) => TagType::Byte,
+            Self::Short(_) => TagType::Short,
+            Self::Int(_) => TagType::Int,
+            Self::Long(_) => TagType::Long,
+            Self::Float(_) => TagType::Float,
+            Self::Double(_) => TagType::Double,
+            Self::ByteArray(_) => TagType::ByteArray,
+            Self::String(_) => TagType::String,
+            Self::List(_) => TagType::List,
+            Self::Compound(_) => TagType::Compound,
+            Self::IntArray(_) => TagType::IntArray,
+            Self::LongArray(_) => TagType::LongArray,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<CompoundRef<'a>> {
+        match self {
+            Self::Compound(compound) => Some(*compound),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<ListRef<'a>> {
+        match self {
+            Self::List(list) => Some(*list),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<NbtStr<'a>> {
+        match self {
+            Self::String(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns any integer tag widened to an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Byte(value) => Some(value.into()),
+            Self::Short(value) => Some(value.into()),
+            Self::Int(value) => Some(value.into()),
+            Self::Long(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A compound borrowed from a byte slice.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CompoundRef<'a> {
+    /// The entries, excluding the trailing end tag.
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> CompoundRef<'a> {
+    /// Iterates over the entries in the order they were written.
+    pub fn iter(&self) -> CompoundIter<'a> {
+        CompoundIter {
+            bytes: self.bytes,
+            depth: self.depth,
+        }
+    }
+
+    /// Returns the value of the first entry called `name`.
+    pub fn get(&self, name: &str) -> Option<TagRef<'a>> {
+        self.iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, tag)| tag)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for CompoundRef<'a> {
+    type Item = (NbtStr<'a>, TagRef<'a>);
+    type IntoIter = CompoundIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [`CompoundRef`].
+pub struct CompoundIter<'a> {
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Iterator for CompoundIter<'a> {
+    type Item = (NbtStr<'a>, TagRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (name, tag, rest) = split_entry(self.bytes, self.depth).ok()??;
+        self.bytes = rest;
+        Some((name, tag))
+    }
+}
+
+/// A list borrowed from a byte slice.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ListRef<'a> {
+    ty: TagType,
+    len: usize,
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> ListRef<'a> {
+    /// The type of the elements, which is [`TagType::End`] for empty lists.
+    pub fn element_type(&self) -> TagType {
+        self.ty
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> ListIter<'a> {
+        ListIter { list: *self }
+    }
+}
+
+impl<'a> IntoIterator for ListRef<'a> {
+    type Item = TagRef<'a>;
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a [`ListRef`].
+pub struct ListIter<'a> {
+    list: ListRef<'a>,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = TagRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let list = &mut self.list;
+        let remaining = list.len.checked_sub(1)?;
+        let (tag, rest) = split_payload(list.ty, list.bytes, list.depth).ok()?;
+        list.len = remaining;
+        list.bytes = rest;
+        Some(tag)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+macro_rules! array_ref {
+    ($($(#[$attr:meta])* $name:ident($ty:ty, $size:literal);)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+            pub struct $name<'a>(&'a [u8]);
+
+            impl<'a> $name<'a> {
+                pub fn len(&self) -> usize {
+                    self.0.len() / $size
+                }
+
+                pub fn is_empty(&self) -> bool {
+                    self.0.is_empty()
+                }
+
+                pub fn get(&self, index: usize) -> Option<$ty> {
+                    let start = index.checked_mul($size)?;
+                    let bytes = self.0.get(start..start + $size)?;
+                    Some(<$ty>::from_be_bytes(bytes.try_into().ok()?))
+                }
+
+                pub fn iter(&self) -> impl ExactSizeIterator<Item = $ty> + 'a {
+                    self.0
+                        .chunks_exact($size)
+                        .map(|bytes| <$ty>::from_be_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+array_ref!(
+    /// An int array borrowed from a byte slice, decoded element by element.
+    IntArrayRef(i32, 4);
+    /// A long array borrowed from a byte slice, decoded element by element.
+    LongArrayRef(i64, 8);
+);
+
+fn split_tag_type(bytes: &[u8]) -> Result<(TagType, &[u8]), NbtError> {
+    let (&ty, rest) = bytes.split_first().ok_or(NbtError::UnexpectedEof)?;
+    Ok((TagType::try_from(ty)?, rest))
+}
+
+fn split_array<const N: usize>(bytes: &[u8]) -> Result<([u8; N], &[u8]), NbtError> {
+    let (head, rest) = bytes.split_first_chunk().ok_or(NbtError::UnexpectedEof)?;
+    Ok((*head, rest))
+}
+
+/// Splits off a length-prefixed run of `len * size` bytes.
+fn split_prefixed(bytes: &[u8], size: usize) -> Result<(usize, &[u8], &[u8]), NbtError> {
+    let (len, rest) = split_array(bytes)?;
+    let len = i32::from_be_bytes(len);
+    let len = usize::try_from(len).map_err(|_| NbtError::NegativeLength(len))?;
+    let (data, rest) = len
+        .checked_mul(size)
+        .and_then(|size| rest.split_at_checked(size))
+        .ok_or(NbtError::UnexpectedEof)?;
+    Ok((len, data, rest))
+}
+
+fn split_string(bytes: &[u8]) -> Result<(NbtStr<'_>, &[u8]), NbtError> {
+    let (len, rest) = split_array(bytes)?;
+    let (data, rest) = rest
+        .split_at_checked(u16::from_be_bytes(len).into())
+        .ok_or(NbtError::UnexpectedEof)?;
+    Ok((NbtStr::new(data)?, rest))
+}
+
+/// A compound entry's name and value, followed by the bytes after it.
+type Entry<'a> = (NbtStr<'a>, TagRef<'a>, &'a [u8]);
+
+/// Splits off a compound entry, or returns [`None`] at the end tag.
+fn split_entry(bytes: &[u8], depth: usize) -> Result<Option<Entry<'_>>, NbtError> {
+    let (ty, rest) = split_tag_type(bytes)?;
+    if ty == TagType::End {
+        return Ok(None);
+    }
+    let (name, rest) = split_string(rest)?;
+    let (tag, rest) = split_payload(ty, rest, depth)?;
+    Ok(Some((name, tag, rest)))
+}
+
+/// Validates and splits off the payload of a tag of type `ty` that is nested
+/// inside `depth` compounds and lists.
+fn split_payload(ty: TagType, bytes: &[u8], depth: usize) -> Result<(TagRef<'_>, &[u8]), NbtError> {
+    Ok(match ty {
+        TagType::End => return Err(NbtError::InvalidTagType(0)),
+        TagType::Byte => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Byte(i8::from_be_bytes(value)), rest)
+        }
+        TagType::Short => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Short(i16::from_be_bytes(value)), rest)
+        }
+        TagType::Int => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Int(i32::from_be_bytes(value)), rest)
+        }
+        TagType::Long => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Long(i64::from_be_bytes(value)), rest)
+        }
+        TagType::Float => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Float(f32::from_be_bytes(value)), rest)
+        }
+        TagType::Double => {
+            let (value, rest) = split_array(bytes)?;
+            (TagRef::Double(f64::from_be_bytes(value)), rest)
+        }
+        TagType::ByteArray => {
+            let (_, data, rest) = split_prefixed(bytes, 1)?;
+            (TagRef::ByteArray(data), rest)
+        }
+        TagType::String => {
+            let (value, rest) = split_string(bytes)?;
+            (TagRef::String(value), rest)
+        }
+        TagType::List => {
+            if depth >= MAX_DEPTH {
+                return Err(NbtError::TooDeep);
+            }
+            let (ty, rest) = split_tag_type(bytes)?;
+            let (len, rest) = split_array(rest)?;
+            let len = i32::from_be_bytes(len);
+            let len = usize::try_from(len).map_err(|_| NbtError::NegativeLength(len))?;
+            if ty == TagType::End && len > 0 {
+                return Err(NbtError::InvalidTagType(0));
+            }
+
+            let mut end = rest;
+            for _ in 0..len {
+                (_, end) = split_payload(ty, end, depth + 1)?;
+            }
+            let list = ListRef {
+                ty,
+                len,
+                bytes: &rest[..rest.len() - end.len()],
+                depth: depth + 1,
+            };
+            (TagRef::List(list), end)
+        }
+        TagType::Compound => {
+            if depth >= MAX_DEPTH {
+                return Err(NbtError::TooDeep);
+            }
+            let mut end = bytes;
+            while let Some((_, _, rest)) = split_entry(end, depth + 1)? {
+                end = rest;
+            }
+            let compound = CompoundRef {
+                bytes: &bytes[..bytes.len() - end.len()],
+                depth: depth + 1,
+            };
+            // Skip the end tag.
+            (TagRef::Compound(compound), &end[1..])
+        }
+        TagType::IntArray => {
+            let (_, data, rest) = split_prefixed(bytes, 4)?;
+            (TagRef::IntArray(IntArrayRef(data)), rest)
+        }
+        TagType::LongArray => {
+            let (_, data, rest) = split_prefixed(bytes, 8)?;
+            (TagRef::LongArray(LongArrayRef(data)), rest)
+        }
+    })
+}
@@ -0,0 +1,562 @@
+use embedded_byteorder::{AsyncWrite, AsyncWriteBytesExt, BigEndian, Write, WriteBytesExt};
+use heapless::Vec;
+
+use crate::{
+    AsyncEncode, Encode, WireSize,
+    nbt::{MAX_DEPTH, NbtWriteError, TagType, mutf8_len, write_mutf8, write_mutf8_blocking},
+};
+
+/// The named entries of a compound tag, in the order they are written.
+pub type Compound<'a> = &'a [(&'a str, Tag<'a>)];
+
+/// A borrowed NBT tag.
+///
+/// Everything is borrowed so that whole trees can be built in `const`s and
+/// `static`s and encoded straight from flash. Encoding a tag writes it as a
+/// nameless network NBT root: its type followed by its payload.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tag<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [u8]),
+    String(&'a str),
+    List(List<'a>),
+    Compound(Compound<'a>),
+    IntArray(&'a [i32]),
+    LongArray(&'a [i64]),
+}
+
+/// A borrowed NBT list, whose elements all have the same type.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum List<'a> {
+    Empty,
+    Byte(&'a [i8]),
+    Short(&'a [i16]),
+    Int(&'a [i32]),
+    Long(&'a [i64]),
+    Float(&'a [f32]),
+    Double(&'a [f64]),
+    ByteArray(&'a [&'a [u8]]),
+    String(&'a [&'a str]),
+    List(&'a [List<'a>]),
+    Compound(&'a [Compound<'a>]),
+    IntArray(&'a [&'a [i32]]),
+    LongArray(&'a [&'a [i64]]),
+}
+
+impl Tag<'_> {
+    pub fn tag_type(&self) -> TagType {
+        match self {
+            Self::Byte(_) => TagType::Byte,
+            Self::Short(_) => TagType::Short,
+            Self::Int(_) => TagType::Int,
+            Self::Long(_) => TagType::Long,
+            Self::Float(_) => TagType::Float,
+            Self::Double(_) => TagType::Double,
+            Self::ByteArray(_) => TagType::ByteArray,
+            Self::String(_) => TagType::String,
+            Self::List(_) => TagType::List,
+            Self::Compound(_) => TagType::Compound,
+            Self::IntArray(_) => TagType::IntArray,
+            Self::LongArray(_) => TagType::LongArray,
+        }
+    }
+
+    /// The number of bytes taken up by the payload, excluding the tag type.
+    pub fn payload_size(&self) -> usize {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Long(_) | Self::Double(_) => 8,
+            Self::ByteArray(values) => 4 + values.len(),
+            Self::String(value) => 2 + mutf8_len(value),
+            Self::List(list) => list.payload_size(),
+            Self::Compound(entries) => compound_size(entries),
+            Self::IntArray(values) => 4 + 4 * values.len(),
+            Self::LongArray(values) => 4 + 8 * values.len(),
+        }
+    }
+}
+
+impl List<'_> {
+    /// The type of the elements of the list.
+    pub fn element_type(&self) -> TagType {
+        match self {
+            Self::Empty => TagType::End,
+            Self::Byte(_) => TagType::Byte,
+            Self::Short(_) => TagType::Short,
+            Self::Int(_) => TagType::Int,
+            Self::Long(_) => TagType::Long,
+            Self::Float(_) => TagType::Float,
+            Self::Double(_) => TagType::Double,
+            Self::ByteArray(_) => TagType::ByteArray,
+            Self::String(_) => TagType::String,
+            Self::List(_) => TagType::List,
+            Self::Compound(_) => TagType::Compound,
+            Self::IntArray(_) => TagType::IntArray,
+            Self::LongArray(_) => TagType::LongArray,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::Byte(values) => values.len(),
+            Self::Short(values) => values.len(),
+            Self::Int(values) => values.len(),
+            Self::Long(values) => values.len(),
+            Self::Float(values) => values.len(),
+            Self::Double(values) => values.len(),
+            Self::ByteArray(values) => values.len(),
+            Self::String(values) => values.len(),
+            Self::List(values) => values.len(),
+            Self::Compound(values) => values.len(),
+            Self::IntArray(values) => values.len(),
+            Self::LongArray(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index` as a tag, unless it is out of bounds or
+    /// a nested list or compound.
+    fn flat_element(&self, index: usize) -> Option<Tag<'_>> {
+        match self {
+            Self::Empty | Self::List(_) | Self::Compound(_) => None,
+            Self::Byte(values) => values.get(index).copied().map(Tag::Byte),
+            Self::Short(values) => values.get(index).copied().map(Tag::Short),
+            Self::Int(values) => values.get(index).copied().map(Tag::Int),
+            Self::Long(values) => values.get(index).copied().map(Tag::Long),
+            Self::Float(values) => values.get(index).copied().map(Tag::Float),
+            Self::Double(values) => values.get(index).copied().map(Tag::Double),
+            Self::ByteArray(values) => values.get(index).copied().map(Tag::ByteArray),
+            Self::String(values) => values.get(index).copied().map(Tag::String),
+            Self::IntArray(values) => values.get(index).copied().map(Tag::IntArray),
+            Self::LongArray(values) => values.get(index).copied().map(Tag::LongArray),
+        }
+    }
+
+    /// The number of bytes taken up by the payload: the element type, the
+    /// length and the elements.
+    pub fn payload_size(&self) -> usize {
+        5 + match self {
+            Self::Empty => 0,
+            Self::Byte(values) => values.len(),
+            Self::Short(values) => 2 * values.len(),
+            Self::Int(values) => 4 * values.len(),
+            Self::Long(values) => 8 * values.len(),
+            Self::Float(values) => 4 * values.len(),
+            Self::Double(values) => 8 * values.len(),
+            Self::ByteArray(values) => values.iter().map(|v| 4 + v.len()).sum(),
+            Self::String(values) => values.iter().map(|v| 2 + mutf8_len(v)).sum(),
+            Self::List(values) => values.iter().map(List::payload_size).sum(),
+            Self::Compound(values) => values.iter().map(|v| compound_size(v)).sum(),
+            Self::IntArray(values) => values.iter().map(|v| 4 + 4 * v.len()).sum(),
+            Self::LongArray(values) => values.iter().map(|v| 4 + 8 * v.len()).sum(),
+        }
+    }
+}
+
+fn compound_size(entries: Compound<'_>) -> usize {
+    let entries: usize = entries
+        .iter()
+        .map(|(name, tag)| 1 + 2 + mutf8_len(name) + tag.payload_size())
+        .sum();
+    entries + 1
+}
+
+fn array_len(len: usize) -> i32 {
+    // Arrays this long could never fit in a packet anyway.
+    i32::try_from(len).unwrap()
+}
+
+impl WireSize for Tag<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        1 + self.payload_size()
+    }
+}
+
+impl Encode for Tag<'_> {
+    type Options = ();
+    type Error<E> = NbtWriteError<E>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_u8(self.tag_type() as u8)?;
+        write_payload_blocking(writer, self, 0)
+    }
+}
+
+pub(crate) fn write_payload_blocking<W: Write + ?Sized>(
+    writer: &mut W,
+    tag: &Tag<'_>,
+    depth: usize,
+) -> Result<(), NbtWriteError<W::Error>> {
+    match *tag {
+        Tag::Byte(value) => writer.write_i8(value)?,
+        Tag::Short(value) => writer.write_i16::<BigEndian>(value)?,
+        Tag::Int(value) => writer.write_i32::<BigEndian>(value)?,
+        Tag::Long(value) => writer.write_i64::<BigEndian>(value)?,
+        Tag::Float(value) => writer.write_f32::<BigEndian>(value)?,
+        Tag::Double(value) => writer.write_f64::<BigEndian>(value)?,
+        Tag::ByteArray(values) => {
+            writer.write_i32::<BigEndian>(array_len(values.len()))?;
+            writer.write_all(values)?;
+        }
+        Tag::String(value) => write_mutf8_blocking(writer, value)?,
+        Tag::List(list) => {
+            if depth >= MAX_DEPTH {
+                return Err(NbtWriteError::TooDeep);
+            }
+            writer.write_u8(list.element_type() as u8)?;
+            writer.write_i32::<BigEndian>(array_len(list.len()))?;
+            match list {
+                List::List(lists) => {
+                    for list in lists {
+                        write_payload_blocking(writer, &Tag::List(*list), depth + 1)?;
+                    }
+                }
+                List::Compound(compounds) => {
+                    for entries in compounds {
+                        write_payload_blocking(writer, &Tag::Compound(entries), depth + 1)?;
+                    }
+                }
+                list => {
+                    for index in 0..list.len() {
+                        if let Some(tag) = list.flat_element(index) {
+                            write_payload_blocking(writer, &tag, depth + 1)?;
+                        }
+                    }
+                }
+            }
+        }
+        Tag::Compound(entries) => {
+            if depth >= MAX_DEPTH {
+                return Err(NbtWriteError::TooDeep);
+            }
+            for (name, tag) in entries {
+                writer.write_u8(tag.tag_type() as u8)?;
+                write_mutf8_blocking(writer, name)?;
+                write_payload_blocking(writer, tag, depth + 1)?;
+            }
+            writer.write_u8(TagType::End as u8)?;
+        }
+        Tag::IntArray(values) => {
+            writer.write_i32::<BigEndian>(array_len(values.len()))?;
+            for &value in values {
+                writer.write_i32::<BigEndian>(value)?;
+            }
+        }
+        Tag::LongArray(values) => {
+            writer.write_i32::<BigEndian>(array_len(values.len()))?;
+            for &value in values {
+                writer.write_i64::<BigEndian>(value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl AsyncEncode for Tag<'_> {
+    type Options = ();
+    type Error<E> = NbtWriteError<E>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_u8(self.tag_type() as u8).await?;
+        write_payload(writer, self, 0).await
+    }
+}
+
+/// A compound or list whose elements are still being written.
+enum Frame<'a> {
+    Compound(core::slice::Iter<'a, (&'a str, Tag<'a>)>),
+    Lists(core::slice::Iter<'a, List<'a>>),
+    Compounds(core::slice::Iter<'a, Compound<'a>>),
+}
+
+/// Asynchronously writes the payload of `tag`.
+///
+/// Async functions cannot recurse without boxing, so nested compounds and
+/// lists are tracked on a fixed-size stack instead.
+pub(crate) async fn write_payload<'a, W: AsyncWrite + ?Sized>(
+    writer: &mut W,
+    tag: &Tag<'a>,
+    depth: usize,
+) -> Result<(), NbtWriteError<W::Error>> {
+    let mut stack = Vec::<Frame<'a>, MAX_DEPTH>::new();
+    if let Some(frame) = write_shallow(writer, *tag).await? {
+        push(&mut stack, frame, depth)?;
+    }
+
+    while let Some(frame) = stack.last_mut() {
+        let next = match frame {
+            Frame::Compound(entries) => match entries.next() {
+                Some((name, tag)) => {
+                    writer.write_u8(tag.tag_type() as u8).await?;
+                    write_mutf8(writer, name).await?;
+                    Some(*tag)
+                }
+                None => {
+                    writer.write_u8(TagType::End as u8).await?;
+                    None
+                }
+            },
+            Frame::Lists(lists) => lists.next().map(|list| Tag::List(*list)),
+            Frame::Compounds(compounds) => compounds.next().map(|entries| Tag::Compound(entries)),
+        };
+
+        match next {
+            Some(tag) => {
+                if let Some(frame) = write_shallow(writer, tag).await? {
+                    push(&mut stack, frame, depth)?;
+                }
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push<'a, E>(
+    stack: &mut Vec<Frame<'a>, MAX_DEPTH>,
+    frame: Frame<'a>,
+    depth: usize,
+) -> Result<(), NbtWriteError<E>> {
+    if depth + stack.len() >= MAX_DEPTH {
+        return Err(NbtWriteError::TooDeep);
+    }
+    stack.push(frame).map_err(|_| NbtWriteError::TooDeep)
+}
+
+/// Asynchronously writes the payload of `tag`, except for the elements of
+/// compounds and lists of lists or compounds, which are returned as a
+/// [`Frame`] to be written by the caller.
+async fn write_shallow<'a, W: AsyncWrite + ?Sized>(
+    writer: &mut W,
+    tag: Tag<'a>,
+) -> Result<Option<Frame<'a>>, NbtWriteError<W::Error>> {
+    match tag {
+        Tag::List(list) => {
+            writer.write_u8(list.element_type() as u8).await?;
+            writer.write_i32::<BigEndian>(array_len(list.len())).await?;
+            match list {
+                List::List(lists) => return Ok(Some(Frame::Lists(lists.iter()))),
+                List::Compound(compounds) => return Ok(Some(Frame::Compounds(compounds.iter()))),
+                list => {
+                    for index in 0..list.len() {
+                        if let Some(tag) = list.flat_element(index) {
+                            write_flat(writer, tag).await?;
+                        }
+                    }
+                }
+            }
+        }
+        Tag::Compound(entries) => return Ok(Some(Frame::Compound(entries.iter()))),
+        tag => write_flat(writer, tag).await?,
+    }
+    Ok(None)
+}
+
+/// Asynchronously writes the payload of a tag that is not a list or compound.
+async fn write_flat<W: AsyncWrite + ?Sized>(
+    writer: &mut W,
+    tag: Tag<'_>,
+) -> Result<(), NbtWriteError<W::Error>> {
+    match tag {
+        Tag::Byte(value) => writer.write_i8(value).await?,
+        Tag::Short(value) => writer.write_i16::<BigEndian>(value).await?,
+        Tag::Int(value) => writer.write_i32::<BigEndian>(value).await?,
+        Tag::Long(value) => writer.write_i64::<BigEndian>(value).await?,
+        Tag::Float(value) => writer.write_f32::<BigEndian>(value).await?,
+        Tag::Double(value) => writer.write_f64::<BigEndian>(value).await?,
+        Tag::ByteArray(values) => {
+            writer
+                .write_i32::<BigEndian>(array_len(values.len()))
+                .await?;
+            writer.write_all(values).await?;
+        }
+        Tag::String(value) => write_mutf8(writer, value).await?,
+        Tag::IntArray(values) => {
+            writer
+                .write_i32::<BigEndian>(array_len(values.len()))
+                .await?;
+            for &value in values {
+                writer.write_i32::<BigEndian>(value).await?;
+            }
+        }
+        Tag::LongArray(values) => {
+            writer
+                .write_i32::<BigEndian>(array_len(values.len()))
+                .await?;
+            for &value in values {
+                writer.write_i64::<BigEndian>(value).await?;
+            }
+        }
+        Tag::List(_) | Tag::Compound(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{boxed::Box, vec::Vec};
+
+    use super::*;
+    use crate::{
+        nbt::{NbtError, TagRef},
+        test_util::{block_on, encode},
+    };
+
+    #[test]
+    fn encodes_each_tag_type() {
+        let cases: &[(Tag<'_>, &[u8])] = &[
+            (Tag::Byte(-1), &[1, 0xFF]),
+            (Tag::Short(0x1234), &[2, 0x12, 0x34]),
+            (Tag::Int(-2), &[3, 0xFF, 0xFF, 0xFF, 0xFE]),
+            (Tag::Long(1), &[4, 0, 0, 0, 0, 0, 0, 0, 1]),
+            (Tag::Float(1.0), &[5, 0x3F, 0x80, 0, 0]),
+            (Tag::Double(-2.0), &[6, 0xC0, 0, 0, 0, 0, 0, 0, 0]),
+            (Tag::ByteArray(&[1, 2]), &[7, 0, 0, 0, 2, 1, 2]),
+            (Tag::String("hi"), &[8, 0, 2, b'h', b'i']),
+            (Tag::List(List::Empty), &[9, 0, 0, 0, 0, 0]),
+            (
+                Tag::List(List::Short(&[1, 2])),
+                &[9, 2, 0, 0, 0, 2, 0, 1, 0, 2],
+            ),
+            (
+                Tag::List(List::List(&[List::Empty])),
+                &[9, 9, 0, 0, 0, 1, 0, 0, 0, 0, 0],
+            ),
+            (
+                Tag::List(List::Compound(&[&[], &[("a", Tag::Byte(1))]])),
+                &[9, 10, 0, 0, 0, 2, 0, 1, 0, 1, b'a', 1, 0],
+            ),
+            (Tag::Compound(&[]), &[10, 0]),
+            (Tag::IntArray(&[1]), &[11, 0, 0, 0, 1, 0, 0, 0, 1]),
+            (
+                Tag::LongArray(&[-1]),
+                &[
+                    12, 0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                ],
+            ),
+        ];
+        for (tag, expected) in cases {
+            assert_eq!(encode(tag), *expected, "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn root_compound_is_nameless() {
+        let tag = Tag::Compound(&[
+            ("a", Tag::Byte(1)),
+            ("list", Tag::List(List::String(&["x"]))),
+        ]);
+        #[rustfmt::skip]
+        let expected = [
+            // Root: the tag type and no name.
+            10,
+            1, 0, 1, b'a', 1,
+            9, 0, 4, b'l', b'i', b's', b't', 8, 0, 0, 0, 1, 0, 1, b'x',
+            0,
+        ];
+        assert_eq!(encode(&tag), expected);
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        let tag = Tag::Compound(&[
+            ("byte", Tag::Byte(-3)),
+            ("long", Tag::Long(i64::MIN)),
+            ("double", Tag::Double(0.5)),
+            ("bytes", Tag::ByteArray(&[1, 2, 3])),
+            ("name", Tag::String("a\0😀")),
+            ("ints", Tag::IntArray(&[1, -1])),
+            ("longs", Tag::LongArray(&[i64::MAX])),
+            (
+                "lists",
+                Tag::List(List::List(&[List::Int(&[7]), List::Empty])),
+            ),
+            ("nested", Tag::Compound(&[("x", Tag::Float(2.0))])),
+        ]);
+        let bytes = encode(&tag);
+        let (parsed, rest) = TagRef::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+
+        let compound = parsed.as_compound().unwrap();
+        assert_eq!(compound.iter().count(), 9);
+        assert_eq!(compound.get("byte"), Some(TagRef::Byte(-3)));
+        assert_eq!(compound.get("long").unwrap().as_i64(), Some(i64::MIN));
+        assert_eq!(compound.get("double"), Some(TagRef::Double(0.5)));
+        assert_eq!(compound.get("bytes"), Some(TagRef::ByteArray(&[1, 2, 3])));
+        assert!(compound.get("name").unwrap().as_str().unwrap() == *"a\0😀");
+        let Some(TagRef::IntArray(ints)) = compound.get("ints") else {
+            panic!("not an int array");
+        };
+        assert_eq!(ints.iter().collect::<Vec<_>>(), [1, -1]);
+        let Some(TagRef::LongArray(longs)) = compound.get("longs") else {
+            panic!("not a long array");
+        };
+        assert_eq!(longs.get(0), Some(i64::MAX));
+
+        let lists = compound.get("lists").unwrap().as_list().unwrap();
+        assert_eq!(lists.element_type(), TagType::List);
+        let lists: Vec<_> = lists.iter().map(|tag| tag.as_list().unwrap()).collect();
+        assert_eq!(lists[0].iter().collect::<Vec<_>>(), [TagRef::Int(7)]);
+        assert_eq!(lists[1].element_type(), TagType::End);
+        assert!(lists[1].is_empty());
+
+        let nested = compound.get("nested").unwrap().as_compound().unwrap();
+        assert_eq!(nested.get("x"), Some(TagRef::Float(2.0)));
+    }
+
+    /// Builds `levels` compounds, each nested inside the one before.
+    fn nested_compounds(levels: usize) -> Tag<'static> {
+        let mut tag = Tag::Int(0);
+        for _ in 0..levels {
+            tag = Tag::Compound(Box::leak(Box::new([("", tag)])));
+        }
+        tag
+    }
+
+    #[test]
+    fn rejects_nesting_past_max_depth() {
+        let deepest = nested_compounds(MAX_DEPTH);
+        let bytes = encode(&deepest);
+        assert!(TagRef::parse(&bytes).is_ok());
+
+        let too_deep = nested_compounds(MAX_DEPTH + 1);
+        let mut buf = [0; 512];
+        assert_eq!(
+            Encode::encode(&too_deep, &mut &mut buf[..], ()),
+            Err(NbtWriteError::TooDeep)
+        );
+        assert_eq!(
+            block_on(AsyncEncode::encode(&too_deep, &mut &mut buf[..], ())),
+            Err(NbtWriteError::TooDeep)
+        );
+
+        // Wrap the accepted tree once more by hand to check the reader.
+        let bytes = [&[10, 10, 0, 0][..], &bytes[1..], &[0]].concat();
+        assert_eq!(TagRef::parse(&bytes), Err(NbtError::TooDeep));
+    }
+}
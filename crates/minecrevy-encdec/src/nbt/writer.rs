@@ -0,0 +1,353 @@
+use crate::nbt::{MAX_DEPTH, NbtWriteError};
+
+/// Defines a compound writer and a list writer for one flavour of I/O.
+///
+/// The async and blocking writers only differ in `async` and `.await`, so
+/// both are generated from this one definition.
+macro_rules! writers {
+    (
+        $Compound:ident, $List:ident: $Write:ident,
+        $write_mutf8:ident, $write_payload:ident,
+        into: $into:literal,
+        async: [$($async:tt)*],
+        await: [$($await:tt)*]
+    ) => {
+        #[doc = concat!("Streams the entries of an NBT compound into ", $into, ".")]
+        ///
+        /// Nested compounds and lists borrow their parent writer, so they must be
+        /// finished with `end` before the parent can be written to again. Dropping a
+        /// writer without calling `end` leaves the NBT unterminated.
+        pub struct $Compound<'w, W: $Write + ?Sized> {
+            writer: &'w mut W,
+            depth: usize,
+        }
+
+        impl<'w, W: $Write + ?Sized> $Compound<'w, W> {
+            /// Starts a nameless network NBT root compound.
+            pub $($async)* fn root(writer: &'w mut W) -> Result<Self, NbtWriteError<W::Error>> {
+                writer.write_u8(TagType::Compound as u8) $($await)* ?;
+                Ok(Self { writer, depth: 1 })
+            }
+
+            $($async)* fn entry(
+                &mut self,
+                ty: TagType,
+                name: &str,
+            ) -> Result<&mut W, NbtWriteError<W::Error>> {
+                self.writer.write_u8(ty as u8) $($await)* ?;
+                $write_mutf8(self.writer, name) $($await)* ?;
+                Ok(self.writer)
+            }
+
+            pub $($async)* fn byte(&mut self, name: &str, value: i8) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Byte(value)) $($await)*
+            }
+
+            pub $($async)* fn short(&mut self, name: &str, value: i16) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Short(value)) $($await)*
+            }
+
+            pub $($async)* fn int(&mut self, name: &str, value: i32) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Int(value)) $($await)*
+            }
+
+            pub $($async)* fn long(&mut self, name: &str, value: i64) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Long(value)) $($await)*
+            }
+
+            pub $($async)* fn float(&mut self, name: &str, value: f32) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Float(value)) $($await)*
+            }
+
+            pub $($async)* fn double(&mut self, name: &str, value: f64) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::Double(value)) $($await)*
+            }
+
+            pub $($async)* fn string(&mut self, name: &str, value: &str) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(name, &Tag::String(value)) $($await)*
+            }
+
+            /// Writes a boolean as a byte, the way vanilla stores them.
+            pub $($async)* fn bool(&mut self, name: &str, value: bool) -> Result<(), NbtWriteError<W::Error>> {
+                self.byte(name, i8::from(value)) $($await)*
+            }
+
+            /// Writes a whole tag, which may itself be a compound or list.
+            pub $($async)* fn tag(&mut self, name: &str, tag: &Tag<'_>) -> Result<(), NbtWriteError<W::Error>> {
+                let depth = self.depth;
+                let writer = self.entry(tag.tag_type(), name) $($await)* ?;
+                $write_payload(writer, tag, depth) $($await)*
+            }
+
+            /// Starts a nested compound.
+            pub $($async)* fn compound(
+                &mut self,
+                name: &str,
+            ) -> Result<$Compound<'_, W>, NbtWriteError<W::Error>> {
+                let depth = nested(self.depth)?;
+                let writer = self.entry(TagType::Compound, name) $($await)* ?;
+                Ok($Compound { writer, depth })
+            }
+
+            /// Starts a nested list of `len` elements of type `ty`.
+            pub $($async)* fn list(
+                &mut self,
+                name: &str,
+                ty: TagType,
+                len: usize,
+            ) -> Result<$List<'_, W>, NbtWriteError<W::Error>> {
+                let depth = nested(self.depth)?;
+                let writer = self.entry(TagType::List, name) $($await)* ?;
+                $List::start(writer, ty, len, depth) $($await)*
+            }
+
+            /// Finishes the compound.
+            pub $($async)* fn end(self) -> Result<(), NbtWriteError<W::Error>> {
+                self.writer.write_u8(TagType::End as u8) $($await)* ?;
+                Ok(())
+            }
+        }
+
+        #[doc = concat!("Streams the elements of an NBT list into ", $into, ".")]
+        ///
+        /// Every element must have the type the list was started with, and exactly as
+        /// many elements as declared must be written before calling
+        #[doc = concat!("[`", stringify!($List), "::end`].")]
+        pub struct $List<'w, W: $Write + ?Sized> {
+            writer: &'w mut W,
+            ty: TagType,
+            remaining: usize,
+            depth: usize,
+        }
+
+        impl<'w, W: $Write + ?Sized> $List<'w, W> {
+            $($async)* fn start(
+                writer: &'w mut W,
+                ty: TagType,
+                len: usize,
+                depth: usize,
+            ) -> Result<Self, NbtWriteError<W::Error>> {
+                let ty = if len == 0 { TagType::End } else { ty };
+                writer.write_u8(ty as u8) $($await)* ?;
+                writer.write_i32::<BigEndian>(i32::try_from(len).unwrap()) $($await)* ?;
+                Ok(Self {
+                    writer,
+                    ty,
+                    remaining: len,
+                    depth,
+                })
+            }
+
+            fn element(&mut self, ty: TagType) -> Result<(), NbtWriteError<W::Error>> {
+                if ty != self.ty {
+                    return Err(NbtWriteError::ListType {
+                        expected: self.ty,
+                        found: ty,
+                    });
+                }
+                self.remaining = self
+                    .remaining
+                    .checked_sub(1)
+                    .ok_or(NbtWriteError::ListLength)?;
+                Ok(())
+            }
+
+            pub $($async)* fn byte(&mut self, value: i8) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Byte(value)) $($await)*
+            }
+
+            pub $($async)* fn short(&mut self, value: i16) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Short(value)) $($await)*
+            }
+
+            pub $($async)* fn int(&mut self, value: i32) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Int(value)) $($await)*
+            }
+
+            pub $($async)* fn long(&mut self, value: i64) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Long(value)) $($await)*
+            }
+
+            pub $($async)* fn float(&mut self, value: f32) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Float(value)) $($await)*
+            }
+
+            pub $($async)* fn double(&mut self, value: f64) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::Double(value)) $($await)*
+            }
+
+            pub $($async)* fn string(&mut self, value: &str) -> Result<(), NbtWriteError<W::Error>> {
+                self.tag(&Tag::String(value)) $($await)*
+            }
+
+            /// Writes a whole tag, which may itself be a compound or list.
+            pub $($async)* fn tag(&mut self, tag: &Tag<'_>) -> Result<(), NbtWriteError<W::Error>> {
+                self.element(tag.tag_type())?;
+                $write_payload(self.writer, tag, self.depth) $($await)*
+            }
+
+            /// Starts a compound element.
+            pub $($async)* fn compound(&mut self) -> Result<$Compound<'_, W>, NbtWriteError<W::Error>> {
+                let depth = nested(self.depth)?;
+                self.element(TagType::Compound)?;
+                Ok($Compound {
+                    writer: self.writer,
+                    depth,
+                })
+            }
+
+            /// Starts a list element of `len` elements of type `ty`.
+            pub $($async)* fn list(
+                &mut self,
+                ty: TagType,
+                len: usize,
+            ) -> Result<$List<'_, W>, NbtWriteError<W::Error>> {
+                let depth = nested(self.depth)?;
+                self.element(TagType::List)?;
+                $List::start(self.writer, ty, len, depth) $($await)*
+            }
+
+            /// Finishes the list, checking that every element was written.
+            pub fn end(self) -> Result<(), NbtWriteError<W::Error>> {
+                match self.remaining {
+                    0 => Ok(()),
+                    _ => Err(NbtWriteError::ListLength),
+                }
+            }
+        }
+    };
+}
+
+pub(super) use writers;
+
+pub(super) fn nested<E>(depth: usize) -> Result<usize, NbtWriteError<E>> {
+    match depth {
+        MAX_DEPTH.. => Err(NbtWriteError::TooDeep),
+        depth => Ok(depth + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Encode,
+        nbt::{AsyncCompoundWriter, CompoundWriter, List, MAX_DEPTH, NbtWriteError, Tag, TagType},
+        test_util::block_on,
+    };
+
+    const TAG: Tag<'static> = Tag::Compound(&[
+        ("flag", Tag::Byte(1)),
+        ("name", Tag::String("a\0b")),
+        ("ints", Tag::List(List::Int(&[1, 2]))),
+        ("empty", Tag::List(List::Empty)),
+        ("nested", Tag::Compound(&[("x", Tag::Double(0.5))])),
+        (
+            "compounds",
+            Tag::List(List::Compound(&[&[("y", Tag::Short(3))]])),
+        ),
+    ]);
+
+    #[test]
+    fn streams_the_same_bytes_as_a_tag() {
+        let mut expected = [0; 128];
+        let len = {
+            let mut writer = &mut expected[..];
+            TAG.encode(&mut writer, ()).unwrap();
+            128 - writer.len()
+        };
+
+        let mut blocking = [0; 128];
+        let mut writer = &mut blocking[..];
+        let mut nbt = CompoundWriter::root(&mut writer).unwrap();
+        nbt.bool("flag", true).unwrap();
+        nbt.string("name", "a\0b").unwrap();
+        let mut ints = nbt.list("ints", TagType::Int, 2).unwrap();
+        ints.int(1).unwrap();
+        ints.int(2).unwrap();
+        ints.end().unwrap();
+        nbt.list("empty", TagType::String, 0)
+            .unwrap()
+            .end()
+            .unwrap();
+        let mut nested = nbt.compound("nested").unwrap();
+        nested.double("x", 0.5).unwrap();
+        nested.end().unwrap();
+        let mut compounds = nbt.list("compounds", TagType::Compound, 1).unwrap();
+        let mut element = compounds.compound().unwrap();
+        element.short("y", 3).unwrap();
+        element.end().unwrap();
+        compounds.end().unwrap();
+        nbt.end().unwrap();
+        assert_eq!(128 - writer.len(), len);
+        assert_eq!(blocking[..len], expected[..len]);
+
+        let mut streamed = [0; 128];
+        let mut writer = &mut streamed[..];
+        block_on(async {
+            let mut nbt = AsyncCompoundWriter::root(&mut writer).await?;
+            nbt.bool("flag", true).await?;
+            nbt.string("name", "a\0b").await?;
+            let mut ints = nbt.list("ints", TagType::Int, 2).await?;
+            ints.int(1).await?;
+            ints.int(2).await?;
+            ints.end()?;
+            nbt.list("empty", TagType::String, 0).await?.end()?;
+            let mut nested = nbt.compound("nested").await?;
+            nested.double("x", 0.5).await?;
+            nested.end().await?;
+            let mut compounds = nbt.list("compounds", TagType::Compound, 1).await?;
+            let mut element = compounds.compound().await?;
+            element.short("y", 3).await?;
+            element.end().await?;
+            compounds.end()?;
+            nbt.end().await
+        })
+        .unwrap();
+        assert_eq!(128 - writer.len(), len);
+        assert_eq!(streamed[..len], expected[..len]);
+    }
+
+    #[test]
+    fn checks_list_elements() {
+        let mut buf = [0; 64];
+        let mut writer = &mut buf[..];
+        let mut nbt = CompoundWriter::root(&mut writer).unwrap();
+
+        let mut list = nbt.list("list", TagType::Int, 1).unwrap();
+        assert_eq!(
+            list.long(1),
+            Err(NbtWriteError::ListType {
+                expected: TagType::Int,
+                found: TagType::Long,
+            })
+        );
+        list.int(1).unwrap();
+        assert_eq!(list.int(2), Err(NbtWriteError::ListLength));
+        list.end().unwrap();
+
+        let list = nbt.list("short", TagType::Int, 2).unwrap();
+        assert_eq!(list.end(), Err(NbtWriteError::ListLength));
+    }
+
+    #[test]
+    fn rejects_nesting_past_max_depth() {
+        fn nest<W: embedded_byteorder::Write>(
+            nbt: &mut CompoundWriter<'_, W>,
+            levels: usize,
+        ) -> Result<(), NbtWriteError<W::Error>> {
+            if levels == 0 {
+                return Ok(());
+            }
+            let mut nested = nbt.compound("")?;
+            nest(&mut nested, levels - 1)?;
+            nested.end()
+        }
+
+        let mut buf = [0; 256];
+        let mut writer = &mut buf[..];
+        let mut nbt = CompoundWriter::root(&mut writer).unwrap();
+        // The root is the first level.
+        nest(&mut nbt, MAX_DEPTH - 1).unwrap();
+        assert_eq!(nest(&mut nbt, MAX_DEPTH), Err(NbtWriteError::TooDeep));
+    }
+}
@@ -0,0 +1,14 @@
+use embedded_byteorder::{AsyncWrite, AsyncWriteBytesExt, BigEndian};
+
+use crate::nbt::{
+    NbtWriteError, Tag, TagType, write_mutf8, write_payload,
+    writer::{nested, writers},
+};
+
+writers! {
+    AsyncCompoundWriter, AsyncListWriter: AsyncWrite,
+    write_mutf8, write_payload,
+    into: "an [`AsyncWrite`]",
+    async: [async],
+    await: [.await]
+}
@@ -0,0 +1,14 @@
+use embedded_byteorder::{BigEndian, Write, WriteBytesExt};
+
+use crate::nbt::{
+    NbtWriteError, Tag, TagType, write_mutf8_blocking, write_payload_blocking,
+    writer::{nested, writers},
+};
+
+writers! {
+    CompoundWriter, ListWriter: Write,
+    write_mutf8_blocking, write_payload_blocking,
+    into: "a [`Write`]",
+    async: [],
+    await: []
+}
@@ -1,4 +1,67 @@
+/// Options for `i32` and `i64`, the integers that the protocol also sends
+/// variable-length. Every other integer is always sent at its fixed width.
 #[derive(Clone, Default)]
 pub struct IntOptions {
+    /// Encodes an `i32` as a VarInt and an `i64` as a VarLong.
     pub varint: bool,
 }
+
+/// Options for borrowed byte arrays, `&[u8]`.
+#[derive(Clone, Default)]
+pub struct BytesOptions {
+    /// Runs to the end of the reader, which is usually the end of the packet,
+    /// instead of being prefixed with its length as a VarInt.
+    pub remaining: bool,
+}
+
+/// How an array tells where it ends.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Framing {
+    /// Prefixed with the number of elements as a VarInt.
+    #[default]
+    VarInt,
+    /// Not prefixed, because the number of elements is known. A `[T; N]` or
+    /// `heapless::Vec<T, N>` has exactly `N` of them, and encoding a `Vec`
+    /// that isn't full fails. A slice is written as is, so the reader has to
+    /// know its length some other way.
+    ///
+    /// The default for `[T; N]`, see [`FixedArrayOptions`].
+    Fixed,
+    /// Not prefixed, and runs to the end of the reader, which is usually the
+    /// end of the packet. The elements must not be empty.
+    Remaining,
+}
+
+/// Options for arrays, such as slices and `heapless::Vec<T, N>`.
+#[derive(Clone, Default)]
+pub struct ArrayOptions<T> {
+    pub framing: Framing,
+    /// The options each element is encoded with.
+    pub element: T,
+}
+
+/// Options for fixed-size arrays, `[T; N]`.
+///
+/// The same as [`ArrayOptions`], except that the framing defaults to
+/// [`Framing::Fixed`], since the length is part of the type.
+#[derive(Clone)]
+pub struct FixedArrayOptions<T> {
+    pub framing: Framing,
+    /// The options each element is encoded with.
+    pub element: T,
+}
+
+impl<T: Default> Default for FixedArrayOptions<T> {
+    fn default() -> Self {
+        Self {
+            framing: Framing::Fixed,
+            element: T::default(),
+        }
+    }
+}
+
+impl<T> From<FixedArrayOptions<T>> for ArrayOptions<T> {
+    fn from(FixedArrayOptions { framing, element }: FixedArrayOptions<T>) -> Self {
+        Self { framing, element }
+    }
+}
@@ -0,0 +1,50 @@
+use embedded_byteorder::{AsyncRead, ErrorType, Read};
+
+/// A reader that yields a byte that was read ahead of `inner` before the rest
+/// of `inner`.
+///
+/// Used to decode arrays that run to the end of the reader, whose end is only
+/// found by trying to read another element.
+pub(crate) struct Peeked<'r, R> {
+    byte: Option<u8>,
+    inner: &'r mut R,
+}
+
+impl<'r, R> Peeked<'r, R> {
+    pub(crate) fn new(byte: u8, inner: &'r mut R) -> Self {
+        Self {
+            byte: Some(byte),
+            inner,
+        }
+    }
+}
+
+impl<R: ErrorType> ErrorType for Peeked<'_, R> {
+    type Error = R::Error;
+}
+
+impl<R: Read> Read for Peeked<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match (self.byte, buf.first_mut()) {
+            (Some(byte), Some(first)) => {
+                *first = byte;
+                self.byte = None;
+                Ok(1)
+            }
+            _ => self.inner.read(buf),
+        }
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Peeked<'_, R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match (self.byte, buf.first_mut()) {
+            (Some(byte), Some(first)) => {
+                *first = byte;
+                self.byte = None;
+                Ok(1)
+            }
+            _ => self.inner.read(buf).await,
+        }
+    }
+}
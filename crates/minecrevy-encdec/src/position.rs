@@ -0,0 +1,142 @@
+use defmt::Format;
+
+/// A block position, sent as a long with 26 bits for `x`, 26 bits for `z`
+/// and 12 bits for `y`, from the most significant bit down.
+#[derive(Format, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Position {
+    /// The smallest `x` and `z` that fit in 26 bits.
+    pub const MIN_XZ: i32 = -(1 << 25);
+    /// The largest `x` and `z` that fit in 26 bits.
+    pub const MAX_XZ: i32 = (1 << 25) - 1;
+    /// The smallest `y` that fits in 12 bits.
+    pub const MIN_Y: i32 = -(1 << 11);
+    /// The largest `y` that fits in 12 bits.
+    pub const MAX_Y: i32 = (1 << 11) - 1;
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Whether the coordinates fit in their bits, so that [`Position::pack`]
+    /// keeps them.
+    pub const fn is_valid(self) -> bool {
+        Self::MIN_XZ <= self.x
+            && self.x <= Self::MAX_XZ
+            && Self::MIN_Y <= self.y
+            && self.y <= Self::MAX_Y
+            && Self::MIN_XZ <= self.z
+            && self.z <= Self::MAX_XZ
+    }
+
+    /// Packs the position into a long. Coordinates that do not fit are
+    /// truncated to their low bits.
+    pub const fn pack(self) -> i64 {
+        ((self.x as i64 & 0x3FF_FFFF) << 38)
+            | ((self.z as i64 & 0x3FF_FFFF) << 12)
+            | (self.y as i64 & 0xFFF)
+    }
+
+    /// Unpacks a long made by [`Position::pack`], sign-extending each
+    /// coordinate.
+    pub const fn unpack(packed: i64) -> Self {
+        Self {
+            x: (packed >> 38) as i32,
+            y: (packed << 52 >> 52) as i32,
+            z: (packed << 26 >> 38) as i32,
+        }
+    }
+
+    /// The X coordinate of the chunk the position is in.
+    pub const fn chunk_x(self) -> i32 {
+        self.x >> 4
+    }
+
+    /// The Z coordinate of the chunk the position is in.
+    pub const fn chunk_z(self) -> i32 {
+        self.z >> 4
+    }
+
+    /// The Y coordinate of the section the position is in, which is
+    /// `min_y / 16` for the lowest section of a dimension.
+    pub const fn section_y(self) -> i32 {
+        self.y >> 4
+    }
+
+    /// The position within its section, each from 0 to 15.
+    pub const fn local(self) -> (u8, u8, u8) {
+        (
+            (self.x & 15) as u8,
+            (self.y & 15) as u8,
+            (self.z & 15) as u8,
+        )
+    }
+
+    /// The index of the block in its section's block states, which are
+    /// ordered by Y, then Z, then X.
+    pub const fn section_index(self) -> usize {
+        let (x, y, z) = self.local();
+        ((y as usize) << 8) | ((z as usize) << 4) | x as usize
+    }
+
+    /// The block at `index` of the section at the given chunk and section
+    /// coordinates, which is the inverse of [`Position::section_index`].
+    pub const fn from_section_index(
+        chunk_x: i32,
+        section_y: i32,
+        chunk_z: i32,
+        index: usize,
+    ) -> Self {
+        Self {
+            x: (chunk_x << 4) | (index & 15) as i32,
+            y: (section_y << 4) | (index >> 8 & 15) as i32,
+            z: (chunk_z << 4) | (index >> 4 & 15) as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDGES_XZ: [i32; 5] = [Position::MIN_XZ, -1, 0, 1, Position::MAX_XZ];
+    const EDGES_Y: [i32; 5] = [Position::MIN_Y, -1, 0, 1, Position::MAX_Y];
+
+    #[test]
+    fn pack_round_trips_full_range() {
+        for x in EDGES_XZ {
+            for y in EDGES_Y {
+                for z in EDGES_XZ {
+                    let position = Position::new(x, y, z);
+                    assert!(position.is_valid());
+                    assert_eq!(Position::unpack(position.pack()), position);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pack_matches_wiki_example() {
+        let position = Position::new(18357644, 831, -20882616);
+        assert_eq!(position.pack(), 0x4607_632C_15B4_833F);
+        assert_eq!(Position::unpack(0x4607_632C_15B4_833F), position);
+    }
+
+    #[test]
+    fn pack_minus_one_sets_every_bit() {
+        assert_eq!(Position::new(-1, -1, -1).pack(), -1);
+        assert_eq!(Position::unpack(-1), Position::new(-1, -1, -1));
+    }
+
+    #[test]
+    fn is_valid_rejects_out_of_range() {
+        assert!(!Position::new(Position::MAX_XZ + 1, 0, 0).is_valid());
+        assert!(!Position::new(0, Position::MIN_Y - 1, 0).is_valid());
+        assert!(!Position::new(0, 0, Position::MIN_XZ - 1).is_valid());
+    }
+}
@@ -1,5 +1,9 @@
+mod bitset;
 mod core;
 mod heapless;
+mod identifier;
+mod position;
+mod uuid;
 
 pub use minecrevy_encdec_macros::WireSize;
 
@@ -0,0 +1,18 @@
+use crate::{BitSet, FixedBitSet, WireSize, var_i32_size};
+
+impl<const N: usize> WireSize for BitSet<N> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        let len = self.trimmed().len();
+        var_i32_size(len as i32) + 8 * len
+    }
+}
+
+impl<const N: usize> WireSize for FixedBitSet<N> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        N
+    }
+}
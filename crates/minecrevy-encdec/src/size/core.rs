@@ -1,4 +1,8 @@
-use crate::{WireSize, options::IntOptions, var_i32_size};
+use crate::{
+    WireSize,
+    options::{ArrayOptions, FixedArrayOptions, Framing, IntOptions},
+    var_i32_size, var_i64_size,
+};
 
 macro_rules! impl_primitive {
     ($($ty:ty),*) => {
@@ -15,7 +19,7 @@ macro_rules! impl_primitive {
     };
 }
 
-impl_primitive!(u8, i8, u16, i16, u32, u64, i64, f32, f64);
+impl_primitive!(bool, u8, i8, u16, i16, u32, u64, f32, f64);
 
 impl WireSize for i32 {
     type Options = IntOptions;
@@ -29,3 +33,68 @@ impl WireSize for i32 {
         }
     }
 }
+
+impl WireSize for i64 {
+    type Options = IntOptions;
+
+    #[inline]
+    fn wire_size(&self, IntOptions { varint }: Self::Options) -> usize {
+        if varint {
+            var_i64_size(*self)
+        } else {
+            core::mem::size_of::<Self>()
+        }
+    }
+}
+
+impl WireSize for str {
+    type Options = ();
+
+    #[inline]
+    fn wire_size(&self, (): Self::Options) -> usize {
+        var_i32_size(i32::try_from(self.len()).unwrap()) + self.len()
+    }
+}
+
+impl<T: WireSize + ?Sized> WireSize for &T {
+    type Options = T::Options;
+
+    #[inline]
+    fn wire_size(&self, options: Self::Options) -> usize {
+        T::wire_size(self, options)
+    }
+}
+
+impl<T: WireSize> WireSize for Option<T> {
+    type Options = T::Options;
+
+    #[inline]
+    fn wire_size(&self, options: Self::Options) -> usize {
+        1 + self.as_ref().map_or(0, |value| value.wire_size(options))
+    }
+}
+
+impl<T: WireSize> WireSize for [T] {
+    type Options = ArrayOptions<T::Options>;
+
+    fn wire_size(&self, ArrayOptions { framing, element }: Self::Options) -> usize {
+        let prefix = match framing {
+            Framing::VarInt => var_i32_size(i32::try_from(self.len()).unwrap()),
+            Framing::Fixed | Framing::Remaining => 0,
+        };
+        prefix
+            + self
+                .iter()
+                .map(|item| item.wire_size(element.clone()))
+                .sum::<usize>()
+    }
+}
+
+impl<T: WireSize, const N: usize> WireSize for [T; N] {
+    type Options = FixedArrayOptions<T::Options>;
+
+    #[inline]
+    fn wire_size(&self, options: Self::Options) -> usize {
+        self.as_slice().wire_size(options.into())
+    }
+}
@@ -1,6 +1,6 @@
-use heapless::String;
+use heapless::{String, Vec};
 
-use crate::{WireSize, var_i32_size};
+use crate::{WireSize, options::ArrayOptions, var_i32_size};
 
 impl<const N: usize> WireSize for String<N> {
     type Options = ();
@@ -9,3 +9,12 @@ impl<const N: usize> WireSize for String<N> {
         var_i32_size(i32::try_from(self.len()).unwrap()) + self.len()
     }
 }
+
+impl<T: WireSize, const N: usize> WireSize for Vec<T, N> {
+    type Options = ArrayOptions<T::Options>;
+
+    #[inline]
+    fn wire_size(&self, options: Self::Options) -> usize {
+        self.as_slice().wire_size(options)
+    }
+}
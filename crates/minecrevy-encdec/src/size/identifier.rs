@@ -0,0 +1,18 @@
+use crate::{Identifier, OwnedIdentifier, WireSize, var_i32_size};
+
+impl WireSize for Identifier<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        let len = self.wire_len();
+        var_i32_size(len) + len as usize
+    }
+}
+
+impl<const N: usize> WireSize for OwnedIdentifier<N> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        self.as_identifier().wire_size(())
+    }
+}
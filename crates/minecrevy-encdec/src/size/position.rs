@@ -0,0 +1,9 @@
+use crate::{Position, WireSize};
+
+impl WireSize for Position {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        8
+    }
+}
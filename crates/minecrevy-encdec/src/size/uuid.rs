@@ -0,0 +1,11 @@
+use uuid::Uuid;
+
+use crate::WireSize;
+
+impl WireSize for Uuid {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        16
+    }
+}
@@ -0,0 +1,86 @@
+//! Helpers shared by the tests.
+
+extern crate std;
+
+use core::{
+    fmt::Debug,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+use std::{vec, vec::Vec};
+
+use embedded_byteorder::SliceWriteError;
+
+use crate::{AsyncEncode, Encode, WireSize};
+
+/// Runs a future that never waits on anything outside of memory.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Encodes `value` with both the blocking and the async encoder, checking
+/// that they write the same bytes and as many as its wire size says.
+pub fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: Encode<Options = ()> + AsyncEncode<Options = ()> + WireSize<Options = ()> + ?Sized,
+    <T as Encode>::Error<SliceWriteError>: Debug,
+    <T as AsyncEncode>::Error<SliceWriteError>: Debug,
+{
+    encode_with(value, ())
+}
+
+/// Like [`encode`], but with the given options.
+pub fn encode_with<T, O: Clone>(value: &T, options: O) -> Vec<u8>
+where
+    T: Encode<Options = O> + AsyncEncode<Options = O> + WireSize<Options = O> + ?Sized,
+    <T as Encode>::Error<SliceWriteError>: Debug,
+    <T as AsyncEncode>::Error<SliceWriteError>: Debug,
+{
+    const MAX_LEN: usize = 1 << 16;
+
+    let mut blocking = vec![0; MAX_LEN];
+    let mut writer = &mut blocking[..];
+    Encode::encode(value, &mut writer, options.clone()).unwrap();
+    let len = MAX_LEN - writer.len();
+    blocking.truncate(len);
+
+    let mut buf = vec![0; MAX_LEN];
+    let mut writer = &mut buf[..];
+    block_on(AsyncEncode::encode(value, &mut writer, options.clone())).unwrap();
+    assert_eq!(MAX_LEN - writer.len(), len);
+    assert_eq!(buf[..len], blocking);
+
+    assert_eq!(value.wire_size(options), len);
+    blocking
+}
+
+/// VarLongs and their encodings, covering each length and the extremes.
+pub const VAR_LONGS: [(i64, &[u8]); 9] = [
+    (0, &[0x00]),
+    (1, &[0x01]),
+    (127, &[0x7F]),
+    (128, &[0x80, 0x01]),
+    (i32::MAX as i64, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+    (
+        i64::MAX,
+        &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F],
+    ),
+    (
+        -1,
+        &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+    ),
+    (
+        i32::MIN as i64,
+        &[0x80, 0x80, 0x80, 0x80, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+    ),
+    (
+        i64::MIN,
+        &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+    ),
+];
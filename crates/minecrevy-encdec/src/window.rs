@@ -0,0 +1,171 @@
+use core::convert::Infallible;
+
+use defmt::Format;
+use embedded_byteorder::{AsyncWrite, Error, ErrorKind, ErrorType, Write};
+use thiserror::Error;
+
+/// The number of bytes [`write_windowed`] buffers at a time.
+pub const WINDOW_SIZE: usize = 64;
+
+/// A blocking [`Write`] that only counts the bytes written to it.
+///
+/// Useful for implementing [`WireSize`](crate::WireSize) for types whose size
+/// is easiest to find by encoding them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SizeCounter(usize);
+
+impl SizeCounter {
+    pub fn size(&self) -> usize {
+        self.0
+    }
+}
+
+impl ErrorType for SizeCounter {
+    type Error = Infallible;
+}
+
+impl Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Returned by a [`Window`] once it is full.
+#[derive(Format, Error, Clone, Copy, PartialEq, Eq, Debug)]
+#[error("window full")]
+pub struct WindowFull;
+
+impl Error for WindowFull {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::WriteZero
+    }
+}
+
+/// A blocking [`Write`] that skips the bytes already sent by a previous pass
+/// and keeps the next [`WINDOW_SIZE`] bytes.
+pub struct Window<'b> {
+    skip: usize,
+    buf: &'b mut [u8],
+    len: usize,
+    full: bool,
+}
+
+impl ErrorType for Window<'_> {
+    type Error = WindowFull;
+}
+
+impl Write for Window<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, Self::Error> {
+        let skipped = self.skip.min(data.len());
+        self.skip -= skipped;
+        let kept = (data.len() - skipped).min(self.buf.len() - self.len);
+        if skipped + kept == 0 && !data.is_empty() {
+            self.full = true;
+            return Err(WindowFull);
+        }
+        self.buf[self.len..][..kept].copy_from_slice(&data[skipped..][..kept]);
+        self.len += kept;
+        Ok(skipped + kept)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Error type for [`write_windowed`].
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum WindowedError<WE, EE> {
+    #[error("failed to write: {0}")]
+    Write(WE),
+    #[error("failed to encode: {0}")]
+    Encode(EE),
+}
+
+/// Asynchronously writes the output of a blocking encoder without buffering
+/// all of it.
+///
+/// `encode` is called repeatedly with a [`Window`] that keeps the next
+/// [`WINDOW_SIZE`] bytes, which are then written to `writer`. The encoder
+/// must produce the same bytes every time it is called.
+///
+/// Every pass encodes from the start again, up to the end of its window, so
+/// the work grows quadratically with the size of the output. This trades CPU
+/// time for memory, which only suits small payloads such as JSON and text
+/// components that are awkward to encode asynchronously because they are
+/// recursive. Large parts, such as the status favicon, should be written to
+/// `writer` directly instead.
+pub async fn write_windowed<W, E>(
+    writer: &mut W,
+    mut encode: impl FnMut(&mut Window<'_>) -> Result<(), E>,
+) -> Result<(), WindowedError<W::Error, E>>
+where
+    W: AsyncWrite + ?Sized,
+{
+    let mut buf = [0; WINDOW_SIZE];
+    let mut written = 0;
+    loop {
+        let mut window = Window {
+            skip: written,
+            buf: &mut buf,
+            len: 0,
+            full: false,
+        };
+        let result = encode(&mut window);
+        let (len, full) = (window.len, window.full);
+        match result {
+            Err(e) if !full => return Err(WindowedError::Encode(e)),
+            _ => {}
+        }
+        writer
+            .write_all(&buf[..len])
+            .await
+            .map_err(WindowedError::Write)?;
+        written += len;
+        if !full {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{vec, vec::Vec};
+
+    use super::*;
+    use crate::test_util::block_on;
+
+    #[test]
+    fn write_windowed_bounds_the_passes() {
+        const LEN: usize = 1024;
+
+        let data: Vec<u8> = (0..LEN).map(|i| i as u8).collect();
+        let mut passes = 0;
+        let mut offered = 0;
+        let mut buf = vec![0; LEN];
+        let mut writer = &mut buf[..];
+        let result = block_on(write_windowed(&mut writer, |window| {
+            passes += 1;
+            // One byte at a time, so every byte the encoder produces counts.
+            for byte in &data {
+                offered += 1;
+                window.write_all(core::slice::from_ref(byte))?;
+            }
+            Ok::<_, WindowFull>(())
+        }));
+        result.unwrap();
+        assert!(writer.is_empty());
+        assert_eq!(buf, data);
+
+        // Each pass stops at the end of its window.
+        assert_eq!(passes, LEN / WINDOW_SIZE);
+        assert!(offered <= LEN * LEN / (2 * WINDOW_SIZE) + LEN);
+    }
+}
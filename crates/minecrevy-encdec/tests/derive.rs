@@ -0,0 +1,314 @@
+//! Tests for the code generated by the derive macros.
+
+use core::{
+    convert::Infallible,
+    fmt::Debug,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use embedded_byteorder::SliceWriteError;
+use minecrevy_encdec::{AsyncDecode, AsyncEncode, Decode, Encode, ReadMinecraftError, WireSize};
+
+/// Runs a future that never waits on anything outside of memory.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Encodes `value` with both the blocking and the async encoder, checking
+/// that they write the same bytes and as many as its wire size says.
+fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: Encode<Options = ()> + AsyncEncode<Options = ()> + WireSize<Options = ()>,
+    <T as Encode>::Error<SliceWriteError>: Debug,
+    <T as AsyncEncode>::Error<SliceWriteError>: Debug,
+{
+    let mut blocking = [0; 256];
+    let mut writer = &mut blocking[..];
+    Encode::encode(value, &mut writer, ()).unwrap();
+    let len = 256 - writer.len();
+
+    let mut buf = [0; 256];
+    let mut writer = &mut buf[..];
+    block_on(AsyncEncode::encode(value, &mut writer, ())).unwrap();
+    assert_eq!(256 - writer.len(), len);
+    assert_eq!(buf[..len], blocking[..len]);
+
+    assert_eq!(value.wire_size(()), len);
+    blocking[..len].to_vec()
+}
+
+/// Decodes `bytes` with both the blocking and the async decoder, checking
+/// that they agree and consume every byte.
+fn decode<T>(bytes: &[u8]) -> T
+where
+    T: Decode<Options = ()> + AsyncDecode<Options = ()> + PartialEq + Debug,
+    <T as Decode>::Error<Infallible>: Debug,
+    <T as AsyncDecode>::Error<Infallible>: Debug,
+{
+    let mut reader = bytes;
+    let blocking = <T as Decode>::decode(&mut reader, ()).unwrap();
+    assert!(reader.is_empty());
+
+    let mut reader = bytes;
+    let decoded = block_on(<T as AsyncDecode>::decode(&mut reader, ())).unwrap();
+    assert!(reader.is_empty());
+
+    assert_eq!(decoded, blocking);
+    decoded
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+struct Named {
+    flag: bool,
+    #[options(.varint = true)]
+    id: i32,
+    raw: i32,
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+struct Tuple(u8, #[options(.varint = true)] i64);
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+struct Unit;
+
+#[test]
+fn round_trips_named_struct() {
+    let value = Named {
+        flag: true,
+        id: 300,
+        raw: 300,
+    };
+    let bytes = encode(&value);
+    // The VarInt field takes two bytes and the plain one four.
+    assert_eq!(bytes, [1, 0xAC, 0x02, 0, 0, 0x01, 0x2C]);
+    assert_eq!(decode::<Named>(&bytes), value);
+}
+
+#[test]
+fn round_trips_tuple_struct() {
+    let value = Tuple(7, -1);
+    let bytes = encode(&value);
+    assert_eq!(
+        bytes,
+        [
+            7, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01
+        ]
+    );
+    assert_eq!(decode::<Tuple>(&bytes), value);
+}
+
+#[test]
+fn round_trips_unit_struct() {
+    assert!(encode(&Unit).is_empty());
+    assert_eq!(decode::<Unit>(&[]), Unit);
+}
+
+#[test]
+fn reports_the_field_that_failed() {
+    // Ends in the middle of the VarInt `id`.
+    let mut reader = &[1, 0x80][..];
+    let error = <Named as Decode>::decode(&mut reader, ()).unwrap_err();
+    assert!(matches!(
+        error,
+        DecodeNamedError::Id(ReadMinecraftError::UnexpectedEof)
+    ));
+    assert_eq!(
+        error.to_string(),
+        "failed to decode field `id` of `Named`: unexpected EOF"
+    );
+
+    let mut reader = &[0, 1, 0, 0][..];
+    let error = block_on(<Named as AsyncDecode>::decode(&mut reader, ())).unwrap_err();
+    assert!(matches!(
+        error,
+        AsyncDecodeNamedError::Raw(ReadMinecraftError::UnexpectedEof)
+    ));
+
+    // Only the first two fields fit.
+    let mut buf = [0; 3];
+    let error = Encode::encode(
+        &Named {
+            flag: false,
+            id: 1,
+            raw: 2,
+        },
+        &mut &mut buf[..],
+        (),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error,
+        EncodeNamedError::Raw(SliceWriteError::Full)
+    ));
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+enum Implicit {
+    Zero,
+    One,
+    Two,
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+enum Explicit {
+    First = 1,
+    // Follows on from the previous discriminant, like the compiler does.
+    Second,
+    Large = 200,
+    AfterLarge,
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+#[tag(u8)]
+enum Byte {
+    A = 3,
+    B,
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+#[tag(i32, .varint = false)]
+enum Int {
+    A = 1,
+}
+
+#[derive(Encode, Decode, AsyncEncode, AsyncDecode, WireSize, PartialEq, Debug)]
+enum Data {
+    Empty,
+    Tuple(u8, bool),
+    Named {
+        #[options(.varint = true)]
+        id: i32,
+    },
+}
+
+#[test]
+fn encodes_implicit_discriminants() {
+    for (value, tag) in [(Implicit::Zero, 0), (Implicit::One, 1), (Implicit::Two, 2)] {
+        assert_eq!(encode(&value), [tag]);
+        assert_eq!(decode::<Implicit>(&[tag]), value);
+    }
+}
+
+#[test]
+fn encodes_explicit_discriminants_as_varints() {
+    let cases: [(Explicit, &[u8]); 4] = [
+        (Explicit::First, &[1]),
+        (Explicit::Second, &[2]),
+        (Explicit::Large, &[0xC8, 0x01]),
+        (Explicit::AfterLarge, &[0xC9, 0x01]),
+    ];
+    for (value, bytes) in cases {
+        assert_eq!(encode(&value), bytes);
+        assert_eq!(decode::<Explicit>(bytes), value);
+    }
+}
+
+#[test]
+fn encodes_tag_with_the_given_type() {
+    assert_eq!(encode(&Byte::A), [3]);
+    assert_eq!(encode(&Byte::B), [4]);
+    assert_eq!(decode::<Byte>(&[4]), Byte::B);
+
+    assert_eq!(encode(&Int::A), [0, 0, 0, 1]);
+    assert_eq!(decode::<Int>(&[0, 0, 0, 1]), Int::A);
+}
+
+#[test]
+fn round_trips_data_carrying_variants() {
+    let cases: [(Data, &[u8]); 3] = [
+        (Data::Empty, &[0]),
+        (Data::Tuple(9, true), &[1, 9, 1]),
+        (Data::Named { id: 128 }, &[2, 0x80, 0x01]),
+    ];
+    for (value, bytes) in cases {
+        assert_eq!(encode(&value), bytes);
+        assert_eq!(decode::<Data>(bytes), value);
+    }
+}
+
+#[test]
+fn rejects_unknown_discriminants() {
+    let mut reader = &[3][..];
+    let error = <Implicit as Decode>::decode(&mut reader, ()).unwrap_err();
+    assert!(matches!(error, DecodeImplicitError::InvalidTag(3)));
+    assert_eq!(error.to_string(), "invalid discriminant for `Implicit`: 3");
+
+    let mut reader = &[5][..];
+    let error = block_on(<Byte as AsyncDecode>::decode(&mut reader, ())).unwrap_err();
+    assert!(matches!(error, AsyncDecodeByteError::InvalidTag(5)));
+
+    // The error of a variant's field is named after the variant and field.
+    let mut reader = &[2, 0x80][..];
+    let error = <Data as Decode>::decode(&mut reader, ()).unwrap_err();
+    assert!(matches!(
+        error,
+        DecodeDataError::NamedId(ReadMinecraftError::UnexpectedEof)
+    ));
+    assert_eq!(
+        error.to_string(),
+        "failed to decode field `id` of `Data::Named`: unexpected EOF"
+    );
+}
+
+#[derive(Encode, AsyncEncode, WireSize, Debug)]
+struct Borrowed<'a, T> {
+    name: &'a str,
+    value: T,
+}
+
+/// Compiles only if the derives bound `T` by nothing more than the trait
+/// being derived.
+fn assert_bounds<T>()
+where
+    T: Encode + AsyncEncode + WireSize,
+{
+    fn implements<X: Encode + AsyncEncode + WireSize>() {}
+    implements::<Borrowed<'static, T>>();
+}
+
+#[test]
+fn encodes_borrowed_generic_struct() {
+    assert_bounds::<u8>();
+    assert_bounds::<Named>();
+
+    let value = Borrowed {
+        name: "hi",
+        value: Tuple(1, 2),
+    };
+    assert_eq!(encode(&value), [2, b'h', b'i', 1, 2]);
+
+    let nested = Borrowed {
+        name: "",
+        value: Borrowed {
+            name: "a",
+            value: true,
+        },
+    };
+    assert_eq!(encode(&nested), [0, 1, b'a', 1]);
+}
+
+#[test]
+fn error_does_not_borrow_the_value() {
+    let error = {
+        let name = String::from("long enough");
+        let value = Borrowed {
+            name: name.as_str(),
+            value: 0u8,
+        };
+        let mut buf = [0; 4];
+        Encode::encode(&value, &mut &mut buf[..], ()).unwrap_err()
+    };
+    // The error type has no lifetime, so it outlives the string.
+    let error: EncodeBorrowedError<u8, SliceWriteError> = error;
+    assert!(matches!(
+        error,
+        EncodeBorrowedError::Name(SliceWriteError::Full)
+    ));
+}
@@ -1,28 +1,80 @@
 #![no_std]
 
+use core::convert::Infallible;
+
 use embassy_executor::Spawner;
-use embassy_net::{Stack, tcp::TcpSocket};
-use embassy_time::Duration;
-use embedded_byteorder::ReadExactError;
+use embassy_net::{
+    Stack,
+    dns::{DnsQueryType, Error as DnsError},
+    tcp::{ConnectError, Error as TcpError, TcpReader, TcpSocket, TcpWriter},
+};
+use embassy_time::{Duration, Instant};
+use embedded_byteorder::{AsyncRead, AsyncWrite};
+use minecrevy_crypto::{
+    Cfb8, CipherReader, CipherWriter, RsaError, RsaPrivateKey, ServerHash, Sha1,
+};
 use minecrevy_encdec::{
-    AsyncDecode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, WritePacketError,
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, AsyncWriteMinecraftExt, Compression,
+    Identifier, Position, ReadMinecraftError, ReadPacketError, WritePacketError,
 };
-use minecrevy_log::{assert_eq, info};
-use minecrevy_protocol::r770::{
-    Handshake, NextState, StatusPing, StatusRequest, StatusResponseSimple,
+use minecrevy_log::info;
+use minecrevy_protocol::{
+    json::{Json, JsonError},
+    legacy::{LEGACY_PING, LegacyPingError, LegacyPingResponse},
+    r770::{
+        AsyncDecodeHandshakeError, AsyncDecodeLoginStartError, AsyncDecodeStatusPingError,
+        AsyncEncodeEncryptionRequestError, AsyncEncodeLoginSuccessError,
+        AsyncEncodeStatusPingError, EncryptionRequest, EncryptionResponse, Handshake,
+        LoginAcknowledged, LoginDisconnect, LoginStart, LoginSuccess, MAX_ENCRYPTED_SIZE,
+        MAX_IDENTIFIER_LEN, NextState, StatusPing, StatusRequest, StatusResponse,
+        StatusResponsePlayers, Version,
+        configuration::{
+            AcknowledgeFinishConfiguration, AsyncDecodeClientInformationError,
+            AsyncDecodeServerboundKnownPacksError, AsyncEncodeClientboundKeepAliveError,
+            AsyncEncodeClientboundKnownPacksError, AsyncEncodeFeatureFlagsError,
+            AsyncEncodeRegistryDataError, ClientInformation, ClientboundKnownPacks, FeatureFlags,
+            FinishConfiguration, MAX_KNOWN_PACKS, ServerboundKnownPacks,
+        },
+        offline_uuid,
+        packet_ids::{configuration, handshake, login, play, status},
+        play::{
+            AsyncDecodeConfirmTeleportationError, AsyncEncodeChunkDataAndUpdateLightError,
+            AsyncEncodeGameEventError, AsyncEncodeLoginError, AsyncEncodeSetCenterChunkError,
+            AsyncEncodeSetDefaultSpawnPositionError, AsyncEncodeSynchronizePlayerPositionError,
+            ChunkDataAndUpdateLight, ChunkSection, ChunkSections, ClientboundKeepAlive,
+            ConfirmTeleportation, GameEvent, GameEventKind, GameMode, LightData, Login,
+            PlayerLoaded, PreviousGameMode, SetCenterChunk, SetDefaultSpawnPosition,
+            SynchronizePlayerPosition, TeleportFlags,
+        },
+        registries::{CORE_PACK, REGISTRIES},
+    },
+    text::Text,
 };
+use minecrevy_session::{HasJoinedError, SessionServer, Transport};
 use thiserror::Error;
 
 const MAX_CONNECTIONS: usize = 10;
 
-pub fn spawn_connection_tasks(spawner: Spawner, stack: Stack<'static>) {
+/// Spawns the tasks that accept connections. Players are authenticated if
+/// `online_mode` is given, and let in under any name otherwise.
+pub fn spawn_connection_tasks(
+    spawner: Spawner,
+    stack: Stack<'static>,
+    online_mode: Option<&'static OnlineMode>,
+) {
     for id in 0..MAX_CONNECTIONS {
-        spawner.spawn(connection_task(stack, id)).unwrap();
+        spawner
+            .spawn(connection_task(stack, id, online_mode))
+            .unwrap();
     }
 }
 
 #[embassy_executor::task(pool_size = MAX_CONNECTIONS)]
-async fn connection_task(stack: Stack<'static>, id: usize) {
+async fn connection_task(
+    stack: Stack<'static>,
+    id: usize,
+    online_mode: Option<&'static OnlineMode>,
+) {
     let mut rx_buffer = [0; 4096];
     let mut tx_buffer = [0; 4096];
 
@@ -41,13 +93,25 @@ async fn connection_task(stack: Stack<'static>, id: usize) {
             socket.remote_endpoint()
         );
 
-        let handshake: Handshake = match socket
-            .read_packet(async move |id, mut reader| {
-                assert_eq!(id, 0x00, "Handshake packet ID mismatch");
-                Handshake::decode(&mut reader, ()).await
-            })
-            .await
-        {
+        match read_legacy_ping(&mut socket).await {
+            Ok(true) => {
+                info!("Socket {} received legacy server list ping", id);
+                if let Err(e) = answer_legacy_ping(socket, &STATUS_RESPONSE).await {
+                    info!(
+                        "Socket {} failed to write legacy ping response: {:?}",
+                        id, e
+                    );
+                }
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                info!("Socket {} failed to read handshake: {:?}", id, e);
+                continue;
+            }
+        }
+
+        let handshake = match read_handshake(&mut socket).await {
             Ok(handshake) => handshake,
             Err(e) => {
                 info!("Socket {} failed to read handshake: {:?}", id, e);
@@ -67,6 +131,7 @@ async fn connection_task(stack: Stack<'static>, id: usize) {
 
         match handshake.next_state {
             NextState::Status => handle_status_packets(socket, id).await,
+            NextState::Login => handle_login(socket, stack, online_mode, id).await,
             _ => {
                 info!(
                     "Socket {} received unsupported next state: {:?}",
@@ -78,38 +143,103 @@ async fn connection_task(stack: Stack<'static>, id: usize) {
     }
 }
 
-const STATUS_RESPONSE_SIMPLE: StatusResponseSimple<'static> = StatusResponseSimple(
-    r#"{"version":{"name":"1.21.5","protocol":770},"players":{"max":0,"online":0},"description":"Hello, world!","enforcesSecureChat":false}"#,
-);
+const STATUS_RESPONSE: StatusResponse<'static> = StatusResponse {
+    version: Version::V1_21_5,
+    players: StatusResponsePlayers {
+        max: 0,
+        online: 0,
+        sample: &[],
+    },
+    description: Text::literal("Hello, world!"),
+    favicon: None,
+    enforces_secure_chat: false,
+    prevents_chat_reports: false,
+};
+
+/// Peeks at the first byte to tell a legacy server list ping apart from a
+/// handshake, discarding whatever has arrived of the ping if it is one.
+pub async fn read_legacy_ping(socket: &mut TcpSocket<'_>) -> Result<bool, TcpError> {
+    socket
+        .read_with(|buf| match buf.first() {
+            Some(&LEGACY_PING) => (buf.len(), true),
+            _ => (0, false),
+        })
+        .await
+}
+
+/// Reads the handshake that every connection other than a legacy server list
+/// ping starts with.
+pub async fn read_handshake<R: AsyncRead>(
+    reader: &mut R,
+) -> Result<Handshake, ReadPacketError<R::Error, HandshakePacketError<R::Error>>> {
+    reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            if packet_id != handshake::serverbound::INTENTION {
+                return Err(HandshakePacketError::UnexpectedPacketId(packet_id));
+            }
+            Handshake::decode(&mut reader, ())
+                .await
+                .map_err(HandshakePacketError::Handshake)
+        })
+        .await
+}
+
+#[derive(Error, Debug)]
+pub enum HandshakePacketError<E> {
+    #[error("failed to read handshake: {0}")]
+    Handshake(AsyncDecodeHandshakeError<E>),
+    #[error("unexpected packet ID: 0x{0:02X}")]
+    UnexpectedPacketId(i32),
+}
+
+/// Answers a legacy server list ping with `status` and closes the connection.
+pub async fn answer_legacy_ping(
+    mut socket: TcpSocket<'_>,
+    status: &StatusResponse<'_>,
+) -> Result<(), LegacyPingError<TcpError>> {
+    LegacyPingResponse::from(status)
+        .encode(&mut socket, ())
+        .await?;
+    socket.close();
+    // Wait for the response and the FIN to be sent before dropping the socket.
+    let _ = socket.flush().await;
+    Ok(())
+}
 
 async fn handle_status_packets(mut socket: TcpSocket<'_>, id: usize) {
     loop {
         let (mut reader, mut writer) = socket.split();
 
         let result = reader
-            .read_packet(async move |id, mut reader| {
+            .read_packet(Compression::Disabled, async move |id, mut reader| {
                 match id {
-                    0x00 => {
+                    status::serverbound::STATUS_REQUEST => {
                         let Ok(request) = StatusRequest::decode(&mut reader, ()).await;
                         info!("Received status request: {:?}", request);
                         writer
-                            .write_packet(0x00, STATUS_RESPONSE_SIMPLE, ())
+                            .write_packet(
+                                Compression::Disabled,
+                                status::clientbound::STATUS_RESPONSE,
+                                STATUS_RESPONSE,
+                                (),
+                            )
                             .await
                             .map_err(StatusPacketError::Response)?;
-                        // writer
-                        //     .write_packet(0x00, STATUS_RESPONSE, Default::default())
-                        //     .await
-                        //     .expect("failed to write response");
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
                         Ok(())
                     }
-                    0x01 => {
+                    status::serverbound::PING_REQUEST => {
                         let ping = StatusPing::decode(&mut reader, ())
                             .await
                             .map_err(StatusPacketError::Ping)?;
                         info!("Received status ping: {:?}", ping);
                         writer
-                            .write_packet(0x01, ping, ())
+                            .write_packet(
+                                Compression::Disabled,
+                                status::clientbound::PONG_RESPONSE,
+                                ping,
+                                (),
+                            )
                             .await
                             .map_err(StatusPacketError::Pong)?;
                         writer.flush().await.map_err(StatusPacketError::Flush)?;
@@ -130,13 +260,717 @@ async fn handle_status_packets(mut socket: TcpSocket<'_>, id: usize) {
 #[derive(Error, Debug)]
 pub enum StatusPacketError<E> {
     #[error("failed to read status ping: {0}")]
-    Ping(ReadExactError<E>),
+    Ping(AsyncDecodeStatusPingError<E>),
     #[error("failed to write status pong: {0}")]
-    Pong(WritePacketError<E, E>),
+    Pong(WritePacketError<E, AsyncEncodeStatusPingError<E>>),
     #[error("failed to write status response: {0}")]
-    Response(WritePacketError<E, E>),
+    Response(WritePacketError<E, JsonError<E>>),
     #[error("failed to flush write buffer: {0}")]
     Flush(E),
     #[error("unknown packet ID: 0x{0:02X}")]
     UnknownPacketId(i32),
 }
+
+/// Settings for authenticating players, which also encrypts their
+/// connections.
+pub struct OnlineMode {
+    /// The key each connection's shared secret is encrypted with. Generating
+    /// one is slow, so it should be made once at startup.
+    pub key: RsaPrivateKey,
+    pub session_server: SessionServer<'static>,
+}
+
+/// The size of the buffer the session server's response is read into.
+const SESSION_RESPONSE_SIZE: usize = 4096;
+
+/// Logs the player in, authenticating them first if the server is in online
+/// mode, and carries on in the configuration state.
+async fn handle_login(
+    mut socket: TcpSocket<'_>,
+    stack: Stack<'static>,
+    online_mode: Option<&OnlineMode>,
+    id: usize,
+) {
+    let (reader, writer) = socket.split();
+    let result = match online_mode {
+        Some(online_mode) => log_in_online(reader, writer, stack, online_mode, id).await,
+        None => log_in_offline(reader, writer, id).await,
+    };
+
+    if let Err(e) = result {
+        info!("Socket {} failed to log in: {:?}", id, e);
+        socket.close();
+        // Wait for any disconnect message and the FIN to be sent.
+        let _ = socket.flush().await;
+    }
+}
+
+/// Logs the player in without authenticating them.
+async fn log_in_offline(
+    mut reader: TcpReader<'_>,
+    mut writer: TcpWriter<'_>,
+    id: usize,
+) -> Result<(), LoginError<TcpError>> {
+    let start = read_login_start(&mut reader).await?;
+    let success = LoginSuccess {
+        uuid: offline_uuid(&start.name),
+        username: &start.name,
+        properties: &[],
+    };
+    finish_login(&mut reader, &mut writer, success).await?;
+    info!("Socket {} logged in as {}", id, start.name.as_str());
+
+    handle_configuration(reader, writer, id).await;
+    Ok(())
+}
+
+/// Encrypts the connection and authenticates the player with the session
+/// server, which also provides their UUID and skin.
+async fn log_in_online(
+    mut reader: TcpReader<'_>,
+    mut writer: TcpWriter<'_>,
+    stack: Stack<'static>,
+    online_mode: &OnlineMode,
+    id: usize,
+) -> Result<(), LoginError<TcpError>> {
+    let start = read_login_start(&mut reader).await?;
+
+    let public_key = online_mode.key.public_key_der();
+    let verify_token = verify_token(id);
+    let request = EncryptionRequest {
+        server_id: "",
+        public_key: &public_key,
+        verify_token: &verify_token,
+        should_authenticate: true,
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            login::clientbound::HELLO,
+            request,
+            (),
+        )
+        .await
+        .map_err(LoginError::EncryptionRequest)?;
+    writer.flush().await.map_err(LoginError::Flush)?;
+
+    let shared_secret = reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            if packet_id != login::serverbound::KEY {
+                return Err(LoginPacketError::UnexpectedPacketId(packet_id));
+            }
+            let response = EncryptionResponse::decode(&mut reader, ())
+                .await
+                .map_err(LoginPacketError::EncryptionResponse)?;
+
+            let mut buf = [0; MAX_ENCRYPTED_SIZE];
+            let token = online_mode
+                .key
+                .decrypt(&response.verify_token, &mut buf)
+                .map_err(LoginPacketError::Decrypt)?;
+            if token != verify_token {
+                return Err(LoginPacketError::VerifyTokenMismatch);
+            }
+            let secret = online_mode
+                .key
+                .decrypt(&response.shared_secret, &mut buf)
+                .map_err(LoginPacketError::Decrypt)?;
+            <[u8; 16]>::try_from(secret).map_err(|_| LoginPacketError::InvalidSharedSecret)
+        })
+        .await?;
+
+    // Everything after the Encryption Response is encrypted.
+    let mut reader = CipherReader::new(reader, Cfb8::from_shared_secret(&shared_secret));
+    let mut writer = CipherWriter::new(writer, Cfb8::from_shared_secret(&shared_secret));
+
+    let mut rx_buffer = [0; 1024];
+    let mut tx_buffer = [0; 512];
+    let mut response = [0; SESSION_RESPONSE_SIZE];
+    let mut transport = TcpTransport::new(stack, &mut rx_buffer, &mut tx_buffer);
+    let server_hash = ServerHash::new("", &shared_secret, &public_key);
+    let profile = match online_mode
+        .session_server
+        .has_joined(&mut transport, &start.name, &server_hash, &mut response)
+        .await
+    {
+        Ok(profile) => profile,
+        Err(e) => {
+            let reason = match e {
+                HasJoinedError::NotJoined => "multiplayer.disconnect.unverified_username",
+                _ => "multiplayer.disconnect.authservers_down",
+            };
+            disconnect(&mut writer, Text::translatable(reason, &[])).await;
+            return Err(LoginError::Authenticate(e));
+        }
+    };
+
+    let success = LoginSuccess {
+        uuid: profile.id,
+        username: profile.name,
+        properties: &profile.properties,
+    };
+    finish_login(&mut reader, &mut writer, success).await?;
+    info!("Socket {} logged in as {}", id, profile.name);
+
+    handle_configuration(reader, writer, id).await;
+    Ok(())
+}
+
+async fn read_login_start<R: AsyncRead>(
+    reader: &mut R,
+) -> Result<LoginStart, ReadPacketError<R::Error, LoginPacketError<R::Error>>> {
+    let start = reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            if packet_id != login::serverbound::HELLO {
+                return Err(LoginPacketError::UnexpectedPacketId(packet_id));
+            }
+            LoginStart::decode(&mut reader, ())
+                .await
+                .map_err(LoginPacketError::Start)
+        })
+        .await?;
+    info!("Received login start: {}", start.name.as_str());
+    Ok(start)
+}
+
+/// Sends `success` and waits for the client to acknowledge it, which leaves
+/// the connection in the configuration state.
+async fn finish_login<R: AsyncRead, W: AsyncWrite<Error = R::Error>>(
+    reader: &mut R,
+    writer: &mut W,
+    success: LoginSuccess<'_>,
+) -> Result<(), LoginError<R::Error>> {
+    writer
+        .write_packet(
+            Compression::Disabled,
+            login::clientbound::LOGIN_FINISHED,
+            success,
+            (),
+        )
+        .await
+        .map_err(LoginError::Success)?;
+    writer.flush().await.map_err(LoginError::Flush)?;
+
+    reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            if packet_id != login::serverbound::LOGIN_ACKNOWLEDGED {
+                return Err(LoginPacketError::UnexpectedPacketId(packet_id));
+            }
+            let Ok(LoginAcknowledged) = LoginAcknowledged::decode(&mut reader, ()).await;
+            Ok::<_, LoginPacketError<R::Error>>(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Kicks the player during login. Errors are ignored, since the connection
+/// is closed either way.
+async fn disconnect<W: AsyncWrite>(writer: &mut W, reason: Text<'_>) {
+    let disconnect = LoginDisconnect {
+        reason: Json(reason),
+    };
+    if writer
+        .write_packet(
+            Compression::Disabled,
+            login::clientbound::LOGIN_DISCONNECT,
+            disconnect,
+            (),
+        )
+        .await
+        .is_ok()
+    {
+        let _ = writer.flush().await;
+    }
+}
+
+/// Makes the verify token for a connection.
+///
+/// It only shows that the client encrypted with the right key, so like
+/// vanilla we don't need a secure random number for it.
+fn verify_token(id: usize) -> [u8; 4] {
+    let mut sha1 = Sha1::new();
+    sha1.update(&Instant::now().as_ticks().to_le_bytes());
+    sha1.update(&id.to_le_bytes());
+    let digest = sha1.finalize();
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+#[derive(Error, Debug)]
+pub enum LoginError<E> {
+    #[error("failed to read packet: {0}")]
+    Read(#[from] ReadPacketError<E, LoginPacketError<E>>),
+    #[error("failed to write encryption request: {0}")]
+    EncryptionRequest(WritePacketError<E, AsyncEncodeEncryptionRequestError<E>>),
+    #[error("failed to write login success: {0}")]
+    Success(WritePacketError<E, AsyncEncodeLoginSuccessError<E>>),
+    #[error("failed to flush write buffer: {0}")]
+    Flush(E),
+    #[error("failed to authenticate: {0}")]
+    Authenticate(HasJoinedError<TcpConnectError, E>),
+}
+
+#[derive(Error, Debug)]
+pub enum LoginPacketError<E> {
+    #[error("failed to read login start: {0}")]
+    Start(AsyncDecodeLoginStartError<E>),
+    #[error("failed to read encryption response: {0}")]
+    EncryptionResponse(ReadMinecraftError<E>),
+    #[error("failed to decrypt encryption response: {0}")]
+    Decrypt(RsaError),
+    #[error("shared secret is not 16 bytes long")]
+    InvalidSharedSecret,
+    #[error("verify token mismatch")]
+    VerifyTokenMismatch,
+    #[error("unexpected packet ID: 0x{0:02X}")]
+    UnexpectedPacketId(i32),
+}
+
+/// Connects to the session server over plain TCP, looking its host up with
+/// DNS.
+pub struct TcpTransport<'a> {
+    stack: Stack<'static>,
+    rx_buffer: &'a mut [u8],
+    tx_buffer: &'a mut [u8],
+}
+
+impl<'a> TcpTransport<'a> {
+    pub fn new(stack: Stack<'static>, rx_buffer: &'a mut [u8], tx_buffer: &'a mut [u8]) -> Self {
+        Self {
+            stack,
+            rx_buffer,
+            tx_buffer,
+        }
+    }
+}
+
+impl Transport for TcpTransport<'_> {
+    type ConnectError = TcpConnectError;
+    type Error = TcpError;
+    type Connection<'t>
+        = TcpSocket<'t>
+    where
+        Self: 't;
+
+    async fn connect(&mut self, host: &str, port: u16) -> Result<TcpSocket<'_>, TcpConnectError> {
+        let addresses = self
+            .stack
+            .dns_query(host, DnsQueryType::A)
+            .await
+            .map_err(TcpConnectError::Dns)?;
+        let address = *addresses.first().ok_or(TcpConnectError::NoAddress)?;
+
+        let mut socket = TcpSocket::new(self.stack, self.rx_buffer, self.tx_buffer);
+        socket.set_timeout(Some(Duration::from_secs(10)));
+        socket
+            .connect((address, port))
+            .await
+            .map_err(TcpConnectError::Connect)?;
+        Ok(socket)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TcpConnectError {
+    #[error("failed to look up host: {0:?}")]
+    Dns(DnsError),
+    #[error("host has no address")]
+    NoAddress,
+    #[error("failed to connect: {0:?}")]
+    Connect(ConnectError),
+}
+
+/// Configures the client, then spawns the player and carries on in the play
+/// state.
+async fn handle_configuration<R: AsyncRead, W: AsyncWrite<Error = R::Error>>(
+    mut reader: R,
+    mut writer: W,
+    id: usize,
+) {
+    if let Err(e) = configure(&mut reader, &mut writer).await {
+        info!("Socket {} failed to configure: {:?}", id, e);
+        return;
+    }
+    info!("Socket {} finished configuration", id);
+
+    if let Err(e) = handle_play(&mut reader, &mut writer, id).await {
+        info!("Socket {} failed to play: {:?}", id, e);
+    }
+}
+
+/// Sends the registries and finishes the configuration once the client has
+/// said which known packs it has, so that the registries' data can be left
+/// out if it has the core pack.
+async fn configure<R: AsyncRead, W: AsyncWrite<Error = R::Error>>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<(), ConfigurationError<R::Error>> {
+    let flags = FeatureFlags {
+        flags: &[Identifier::minecraft("vanilla")],
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            configuration::clientbound::UPDATE_ENABLED_FEATURES,
+            flags,
+            (),
+        )
+        .await
+        .map_err(ConfigurationError::FeatureFlags)?;
+    let known_packs = ClientboundKnownPacks {
+        packs: &[CORE_PACK],
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            configuration::clientbound::SELECT_KNOWN_PACKS,
+            known_packs,
+            (),
+        )
+        .await
+        .map_err(ConfigurationError::KnownPacks)?;
+    writer.flush().await.map_err(ConfigurationError::Flush)?;
+
+    let has_core_pack = loop {
+        if let ConfigurationPacket::KnownPacks { has_core_pack } =
+            read_configuration_packet(reader).await?
+        {
+            break has_core_pack;
+        }
+    };
+    info!("Client has the core pack: {}", has_core_pack);
+
+    for registry in REGISTRIES {
+        writer
+            .write_packet(
+                Compression::Disabled,
+                configuration::clientbound::REGISTRY_DATA,
+                registry.registry_data(has_core_pack),
+                (),
+            )
+            .await
+            .map_err(ConfigurationError::RegistryData)?;
+    }
+    writer
+        .write_packet(
+            Compression::Disabled,
+            configuration::clientbound::FINISH_CONFIGURATION,
+            FinishConfiguration,
+            (),
+        )
+        .await
+        .map_err(ConfigurationError::Finish)?;
+    writer.flush().await.map_err(ConfigurationError::Flush)?;
+
+    while !matches!(
+        read_configuration_packet(reader).await?,
+        ConfigurationPacket::FinishAcknowledged
+    ) {}
+    Ok(())
+}
+
+/// The configuration packets that [`configure`] waits for.
+enum ConfigurationPacket {
+    /// The client's known packs, of which only the core pack matters.
+    KnownPacks {
+        has_core_pack: bool,
+    },
+    FinishAcknowledged,
+    /// Any other packet, which has been logged or skipped.
+    Other,
+}
+
+async fn read_configuration_packet<R: AsyncRead>(
+    reader: &mut R,
+) -> Result<ConfigurationPacket, ReadPacketError<R::Error, ConfigurationPacketError<R::Error>>> {
+    reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            match packet_id {
+                configuration::serverbound::CLIENT_INFORMATION => {
+                    let information = ClientInformation::decode(&mut reader, ())
+                        .await
+                        .map_err(ConfigurationPacketError::ClientInformation)?;
+                    info!("Received client information: {:?}", information);
+                    Ok(ConfigurationPacket::Other)
+                }
+                configuration::serverbound::CUSTOM_PAYLOAD => {
+                    // The rest of the message is skipped.
+                    let channel = reader
+                        .read_identifier::<MAX_IDENTIFIER_LEN>()
+                        .await
+                        .map_err(ConfigurationPacketError::PluginMessage)?;
+                    info!("Received plugin message on {}", channel);
+                    Ok(ConfigurationPacket::Other)
+                }
+                configuration::serverbound::FINISH_CONFIGURATION => {
+                    let Ok(AcknowledgeFinishConfiguration) =
+                        AcknowledgeFinishConfiguration::decode(&mut reader, ()).await;
+                    Ok(ConfigurationPacket::FinishAcknowledged)
+                }
+                configuration::serverbound::SELECT_KNOWN_PACKS => {
+                    let packs = <ServerboundKnownPacks>::decode(&mut reader, ())
+                        .await
+                        .map_err(ConfigurationPacketError::KnownPacks)?;
+                    Ok(ConfigurationPacket::KnownPacks {
+                        has_core_pack: packs.contains(&CORE_PACK),
+                    })
+                }
+                _ => {
+                    info!("Skipping configuration packet {}", packet_id);
+                    Ok(ConfigurationPacket::Other)
+                }
+            }
+        })
+        .await
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigurationError<E> {
+    #[error("failed to read packet: {0}")]
+    Read(#[from] ReadPacketError<E, ConfigurationPacketError<E>>),
+    #[error("failed to write feature flags: {0}")]
+    FeatureFlags(WritePacketError<E, AsyncEncodeFeatureFlagsError<E>>),
+    #[error("failed to write known packs: {0}")]
+    KnownPacks(WritePacketError<E, AsyncEncodeClientboundKnownPacksError<E>>),
+    #[error("failed to write registry data: {0}")]
+    RegistryData(WritePacketError<E, AsyncEncodeRegistryDataError<E>>),
+    #[error("failed to write finish configuration: {0}")]
+    Finish(WritePacketError<E, Infallible>),
+    #[error("failed to flush write buffer: {0}")]
+    Flush(E),
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigurationPacketError<E> {
+    #[error("failed to read client information: {0}")]
+    ClientInformation(AsyncDecodeClientInformationError<E>),
+    #[error("failed to read plugin message: {0}")]
+    PluginMessage(ReadMinecraftError<E>),
+    #[error("failed to read known packs: {0}")]
+    KnownPacks(AsyncDecodeServerboundKnownPacksError<MAX_KNOWN_PACKS, E>),
+}
+
+/// Spawns the player into an empty world, then answers its packets and keeps
+/// it connected.
+async fn handle_play<R: AsyncRead, W: AsyncWrite<Error = R::Error>>(
+    reader: &mut R,
+    writer: &mut W,
+    id: usize,
+) -> Result<(), PlayError<R::Error>> {
+    spawn(writer, id).await?;
+    info!("Socket {} spawned", id);
+
+    let mut last_keep_alive = Instant::now();
+    loop {
+        read_play_packet(reader, id).await?;
+
+        // The client sends movement packets every second, so there is one
+        // to piggyback on well before it would time out.
+        if last_keep_alive.elapsed() >= KEEP_ALIVE_INTERVAL {
+            last_keep_alive = Instant::now();
+            let keep_alive = ClientboundKeepAlive(last_keep_alive.as_millis() as i64);
+            writer
+                .write_packet(
+                    Compression::Disabled,
+                    play::clientbound::KEEP_ALIVE,
+                    keep_alive,
+                    (),
+                )
+                .await
+                .map_err(PlayError::KeepAlive)?;
+            writer.flush().await.map_err(PlayError::Flush)?;
+        }
+    }
+}
+
+/// How often the client is sent a keep alive. It disconnects if it has not
+/// heard from the server for 30 seconds.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The only dimension, which players spawn in.
+const OVERWORLD: Identifier<'static> = Identifier::minecraft("overworld");
+
+/// The distance, in chunks, to which chunks are sent around the spawn.
+const VIEW_DISTANCE: i32 = 2;
+
+/// The overworld in
+/// [`DIMENSION_TYPES`](minecrevy_protocol::r770::registries::DIMENSION_TYPES)
+/// reaches from Y -64 to 320.
+const SECTION_COUNT: usize = 384 / 16;
+
+/// The ID of `minecraft:plains`, the only biome in
+/// [`BIOMES`](minecrevy_protocol::r770::registries::BIOMES).
+const PLAINS: i32 = 0;
+
+/// The sections of every chunk of the empty world.
+static EMPTY_SECTIONS: [ChunkSection<'static>; SECTION_COUNT] =
+    [ChunkSection::empty(PLAINS); SECTION_COUNT];
+
+/// Sends the player into the overworld as a spectator, with nothing but air
+/// around the spawn.
+async fn spawn<W: AsyncWrite>(writer: &mut W, id: usize) -> Result<(), PlayError<W::Error>> {
+    let login = Login {
+        entity_id: id as i32,
+        is_hardcore: false,
+        dimension_names: &[OVERWORLD],
+        max_players: MAX_CONNECTIONS as i32,
+        view_distance: VIEW_DISTANCE,
+        simulation_distance: VIEW_DISTANCE,
+        reduced_debug_info: false,
+        enable_respawn_screen: true,
+        do_limited_crafting: false,
+        dimension_type: 0,
+        dimension_name: OVERWORLD,
+        hashed_seed: 0,
+        game_mode: GameMode::Spectator,
+        previous_game_mode: PreviousGameMode(None),
+        is_debug: false,
+        is_flat: false,
+        death_location: None,
+        portal_cooldown: 0,
+        sea_level: 63,
+        enforces_secure_chat: false,
+    };
+    writer
+        .write_packet(Compression::Disabled, play::clientbound::LOGIN, login, ())
+        .await
+        .map_err(PlayError::Login)?;
+    let spawn_position = SetDefaultSpawnPosition {
+        location: Position::new(0, 64, 0),
+        angle: 0.0,
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            play::clientbound::SET_DEFAULT_SPAWN_POSITION,
+            spawn_position,
+            (),
+        )
+        .await
+        .map_err(PlayError::SpawnPosition)?;
+    let game_event = GameEvent {
+        event: GameEventKind::StartWaitingForLevelChunks,
+        value: 0.0,
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            play::clientbound::GAME_EVENT,
+            game_event,
+            (),
+        )
+        .await
+        .map_err(PlayError::GameEvent)?;
+    let center = SetCenterChunk {
+        chunk_x: 0,
+        chunk_z: 0,
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            play::clientbound::SET_CHUNK_CACHE_CENTER,
+            center,
+            (),
+        )
+        .await
+        .map_err(PlayError::CenterChunk)?;
+    for chunk_x in -VIEW_DISTANCE..=VIEW_DISTANCE {
+        for chunk_z in -VIEW_DISTANCE..=VIEW_DISTANCE {
+            let chunk = ChunkDataAndUpdateLight {
+                chunk_x,
+                chunk_z,
+                heightmaps: &[],
+                sections: ChunkSections(&EMPTY_SECTIONS),
+                block_entities: &[],
+                light: LightData::EMPTY,
+            };
+            writer
+                .write_packet(
+                    Compression::Disabled,
+                    play::clientbound::LEVEL_CHUNK_WITH_LIGHT,
+                    chunk,
+                    (),
+                )
+                .await
+                .map_err(PlayError::Chunk)?;
+        }
+    }
+    let position = SynchronizePlayerPosition {
+        teleport_id: 0,
+        x: 0.5,
+        y: 64.0,
+        z: 0.5,
+        velocity_x: 0.0,
+        velocity_y: 0.0,
+        velocity_z: 0.0,
+        yaw: 0.0,
+        pitch: 0.0,
+        flags: TeleportFlags::ABSOLUTE,
+    };
+    writer
+        .write_packet(
+            Compression::Disabled,
+            play::clientbound::PLAYER_POSITION,
+            position,
+            (),
+        )
+        .await
+        .map_err(PlayError::Position)?;
+    writer.flush().await.map_err(PlayError::Flush)
+}
+
+/// Reads a packet in the play state, of which only the ones that finish
+/// spawning are handled for now.
+async fn read_play_packet<R: AsyncRead>(
+    reader: &mut R,
+    id: usize,
+) -> Result<(), ReadPacketError<R::Error, PlayPacketError<R::Error>>> {
+    reader
+        .read_packet(Compression::Disabled, async |packet_id, mut reader| {
+            match packet_id {
+                play::serverbound::ACCEPT_TELEPORTATION => {
+                    let confirm = ConfirmTeleportation::decode(&mut reader, ())
+                        .await
+                        .map_err(PlayPacketError::ConfirmTeleportation)?;
+                    info!("Socket {} confirmed teleport {}", id, confirm.teleport_id);
+                }
+                play::serverbound::PLAYER_LOADED => {
+                    let Ok(PlayerLoaded) = PlayerLoaded::decode(&mut reader, ()).await;
+                    info!("Socket {} finished loading", id);
+                }
+                // Answers to keep alives, which are only needed to stay
+                // connected.
+                play::serverbound::KEEP_ALIVE => {}
+                _ => info!("Skipping play packet {}", packet_id),
+            }
+            Ok(())
+        })
+        .await
+}
+
+#[derive(Error, Debug)]
+pub enum PlayError<E> {
+    #[error("failed to read packet: {0}")]
+    Read(#[from] ReadPacketError<E, PlayPacketError<E>>),
+    #[error("failed to write login: {0}")]
+    Login(WritePacketError<E, AsyncEncodeLoginError<E>>),
+    #[error("failed to write spawn position: {0}")]
+    SpawnPosition(WritePacketError<E, AsyncEncodeSetDefaultSpawnPositionError<E>>),
+    #[error("failed to write game event: {0}")]
+    GameEvent(WritePacketError<E, AsyncEncodeGameEventError<E>>),
+    #[error("failed to write center chunk: {0}")]
+    CenterChunk(WritePacketError<E, AsyncEncodeSetCenterChunkError<E>>),
+    #[error("failed to write chunk: {0}")]
+    Chunk(WritePacketError<E, AsyncEncodeChunkDataAndUpdateLightError<E>>),
+    #[error("failed to write player position: {0}")]
+    Position(WritePacketError<E, AsyncEncodeSynchronizePlayerPositionError<E>>),
+    #[error("failed to write keep alive: {0}")]
+    KeepAlive(WritePacketError<E, AsyncEncodeClientboundKeepAliveError<E>>),
+    #[error("failed to flush write buffer: {0}")]
+    Flush(E),
+}
+
+#[derive(Error, Debug)]
+pub enum PlayPacketError<E> {
+    #[error("failed to read teleport confirmation: {0}")]
+    ConfirmTeleportation(AsyncDecodeConfirmTeleportationError<E>),
+}
@@ -0,0 +1,303 @@
+//! Generates block, registry and packet ID tables from the vanilla data
+//! reports in `reports/<protocol version>`.
+//!
+//! The reports are made by running a vanilla server with
+//! `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`
+//! and copying `blocks.json`, `registries.json` and `packets.json` from
+//! `generated/reports`, which `reports/fetch.sh` does for a given release.
+//! The tables for a version are written to `$OUT_DIR/r<version>`, where its
+//! module includes them.
+//!
+//! The block state IDs and registry IDs are only right if the reports are
+//! complete, so a version whose reports look trimmed gets a warning.
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+/// The registries that get an enum, and the name of the enum.
+const REGISTRY_ENUMS: &[(&str, &str)] = &[
+    ("minecraft:entity_type", "EntityType"),
+    ("minecraft:item", "Item"),
+];
+
+/// The protocol states in `packets.json`, in the order they are written.
+const STATES: &[&str] = &["handshake", "status", "login", "configuration", "play"];
+
+/// Fewer blocks than any release the reports could come from has, which
+/// means the reports were trimmed.
+const MIN_VANILLA_BLOCKS: usize = 1000;
+
+fn main() {
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=reports");
+
+    for version in fs::read_dir("reports").unwrap() {
+        let reports = version.unwrap().path();
+        if !reports.is_dir() {
+            continue;
+        }
+        let version = reports.file_name().unwrap().to_str().unwrap();
+        let out = out_dir.join(format!("r{version}"));
+        fs::create_dir_all(&out).unwrap();
+
+        let block_report = read(&reports, "blocks.json");
+        if block_report.len() < MIN_VANILLA_BLOCKS {
+            println!(
+                "cargo:warning=reports/{version} has only {} blocks, so its state and registry \
+                 IDs won't match vanilla; regenerate it with reports/fetch.sh",
+                block_report.len(),
+            );
+        }
+        fs::write(out.join("blocks.rs"), blocks(&block_report)).unwrap();
+        fs::write(
+            out.join("registries.rs"),
+            registries(&read(&reports, "registries.json")),
+        )
+        .unwrap();
+        fs::write(
+            out.join("packet_ids.rs"),
+            packet_ids(&read(&reports, "packets.json")),
+        )
+        .unwrap();
+    }
+}
+
+fn read(reports: &Path, name: &str) -> Map<String, Value> {
+    let path = reports.join(name);
+    let json = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+    match serde_json::from_str(&json) {
+        Ok(Value::Object(report)) => report,
+        Ok(_) => panic!("{} is not an object", path.display()),
+        Err(e) => panic!("failed to parse {}: {e}", path.display()),
+    }
+}
+
+/// Writes the `Block` enum and its state IDs.
+///
+/// The blocks are ordered by their state IDs, which vanilla hands out in the
+/// order of the block registry, so that the enum's discriminants are the
+/// blocks' registry IDs too.
+fn blocks(report: &Map<String, Value>) -> String {
+    // (name, first state ID, default state ID)
+    let mut blocks: Vec<(&str, u64, u64)> = report
+        .iter()
+        .map(|(name, block)| {
+            let states = block["states"].as_array().unwrap();
+            let ids = states.iter().map(|state| state["id"].as_u64().unwrap());
+            let first = ids.clone().min().unwrap();
+            let default = states
+                .iter()
+                .find(|state| state["default"].as_bool() == Some(true))
+                .map_or(first, |state| state["id"].as_u64().unwrap());
+            (name.as_str(), first, default)
+        })
+        .collect();
+    blocks.sort_by_key(|&(_, first, _)| first);
+    let state_count: u64 = report
+        .values()
+        .map(|block| block["states"].as_array().unwrap().len() as u64)
+        .sum();
+
+    let names: Vec<&str> = blocks.iter().map(|&(name, _, _)| name).collect();
+    let mut out = String::new();
+    write_enum(
+        &mut out,
+        "Block",
+        "A block, whose discriminant is its registry ID.",
+        &names,
+    );
+
+    let first_states = join(blocks.iter().map(|&(_, first, _)| first));
+    let default_states = join(blocks.iter().map(|&(_, _, default)| default));
+    let count = blocks.len();
+    write!(
+        out,
+        "
+/// The number of block states.
+pub const STATE_COUNT: i32 = {state_count};
+
+/// The first state ID of each block.
+static FIRST_STATES: [u16; {count}] = [{first_states}];
+
+/// The default state ID of each block.
+static DEFAULT_STATES: [u16; {count}] = [{default_states}];
+
+impl Block {{
+    /// The state the block is placed in unless something else is chosen.
+    pub const fn default_state_id(self) -> i32 {{
+        DEFAULT_STATES[self as usize] as i32
+    }}
+
+    /// The first of the block's states, which have consecutive IDs.
+    pub const fn min_state_id(self) -> i32 {{
+        FIRST_STATES[self as usize] as i32
+    }}
+
+    /// The last of the block's states, which have consecutive IDs.
+    pub const fn max_state_id(self) -> i32 {{
+        match Self::from_id(self.id() + 1) {{
+            Some(next) => next.min_state_id() - 1,
+            None => STATE_COUNT - 1,
+        }}
+    }}
+
+    /// The block that a state belongs to.
+    pub fn from_state_id(id: i32) -> Option<Self> {{
+        if !(0..STATE_COUNT).contains(&id) {{
+            return None;
+        }}
+        let index = FIRST_STATES.partition_point(|&first| i32::from(first) <= id);
+        Self::from_id(index as i32 - 1)
+    }}
+}}
+"
+    )
+    .unwrap();
+    out
+}
+
+/// Writes an enum for each of [`REGISTRY_ENUMS`].
+fn registries(report: &Map<String, Value>) -> String {
+    let mut out = String::new();
+    for &(registry, name) in REGISTRY_ENUMS {
+        let entries = report
+            .get(registry)
+            .unwrap_or_else(|| panic!("registries.json has no {registry}"))["entries"]
+            .as_object()
+            .unwrap();
+        let names = by_protocol_id(entries);
+        let doc = format!("An entry of `{registry}`, whose discriminant is its registry ID.");
+        write_enum(&mut out, name, &doc, &names);
+    }
+    out
+}
+
+/// Writes a module of packet ID constants for each state and direction.
+fn packet_ids(report: &Map<String, Value>) -> String {
+    let mut out = String::new();
+    for &state in STATES {
+        let Some(directions) = report.get(state) else {
+            continue;
+        };
+        writeln!(out, "/// The packets of the {state} state.").unwrap();
+        writeln!(out, "pub mod {state} {{").unwrap();
+        for direction in ["clientbound", "serverbound"] {
+            let Some(packets) = directions.get(direction) else {
+                continue;
+            };
+            writeln!(out, "    pub mod {direction} {{").unwrap();
+            for name in by_protocol_id(packets.as_object().unwrap()) {
+                let id = packets[name]["protocol_id"].as_u64().unwrap();
+                let constant = path(name).to_uppercase();
+                writeln!(out, "        /// `{name}`").unwrap();
+                writeln!(out, "        pub const {constant}: i32 = 0x{id:02X};").unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+        writeln!(out, "}}\n").unwrap();
+    }
+    out
+}
+
+/// Sorts the entries of a registry or packet list by their protocol IDs,
+/// checking that they are consecutive from 0.
+fn by_protocol_id(entries: &Map<String, Value>) -> Vec<&str> {
+    let mut names: Vec<(u64, &str)> = entries
+        .iter()
+        .map(|(name, entry)| (entry["protocol_id"].as_u64().unwrap(), name.as_str()))
+        .collect();
+    names.sort();
+    for (index, &(id, name)) in names.iter().enumerate() {
+        assert_eq!(id, index as u64, "{name} has an unexpected protocol ID");
+    }
+    names.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Writes an enum whose variants are the given identifiers, with their
+/// positions as discriminants.
+fn write_enum(out: &mut String, name: &str, doc: &str, identifiers: &[&str]) {
+    let count = identifiers.len();
+    let variants: Vec<String> = identifiers.iter().map(|id| variant(id)).collect();
+
+    writeln!(out, "/// {doc}").unwrap();
+    writeln!(out, "#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]").unwrap();
+    writeln!(out, "pub enum {name} {{").unwrap();
+    for (id, variant) in identifiers.iter().zip(&variants) {
+        writeln!(out, "    /// `{id}`").unwrap();
+        writeln!(out, "    {variant},").unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    let all = join(variants.iter().map(|variant| format!("Self::{variant}")));
+    let names = join(identifiers.iter().map(|id| format!("{id:?}")));
+    write!(
+        out,
+        "impl {name} {{
+    /// Every variant, in the order of their registry IDs.
+    pub const ALL: [Self; {count}] = [{all}];
+
+    const NAMES: [&str; {count}] = [{names}];
+
+    pub const fn id(self) -> i32 {{
+        self as i32
+    }}
+
+    pub const fn from_id(id: i32) -> Option<Self> {{
+        if id >= 0 && (id as usize) < Self::ALL.len() {{
+            Some(Self::ALL[id as usize])
+        }} else {{
+            None
+        }}
+    }}
+
+    /// The identifier, such as `minecraft:stone`.
+    pub const fn name(self) -> &'static str {{
+        Self::NAMES[self as usize]
+    }}
+
+    pub fn from_name(name: &str) -> Option<Self> {{
+        let index = Self::NAMES.iter().position(|&n| n == name)?;
+        Some(Self::ALL[index])
+    }}
+}}
+"
+    )
+    .unwrap();
+}
+
+/// The path of an identifier, without its namespace.
+fn path(identifier: &str) -> &str {
+    identifier
+        .split_once(':')
+        .map_or(identifier, |(_, path)| path)
+}
+
+/// Turns `minecraft:polished_granite` into `PolishedGranite`.
+fn variant(identifier: &str) -> String {
+    path(identifier)
+        .split(['_', '/', '.'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            let mut part = String::from(first);
+            part.extend(chars);
+            part
+        })
+        .collect()
+}
+
+fn join<T: ToString>(items: impl Iterator<Item = T>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
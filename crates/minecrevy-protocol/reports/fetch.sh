@@ -0,0 +1,37 @@
+#!/bin/sh
+# Regenerates the data reports for a protocol version from the vanilla server
+# of the matching release, e.g. `./fetch.sh 1.21.5 770`.
+#
+# The server jar is looked up in Mojang's version manifest and checked against
+# the SHA-1 listed there before it is run. Needs curl, jq, sha1sum and Java 21.
+#
+# To use a server jar that is already on disk, for example on a machine without
+# network access, set SERVER_JAR to its path. It is run as is, so check that it
+# is the right release first. Then only Java 21 is needed.
+set -eu
+
+release=$1
+protocol=$2
+reports=$(cd "$(dirname "$0")" && pwd)/$protocol
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+if [ -n "${SERVER_JAR:-}" ]; then
+    cp "$SERVER_JAR" "$work/server.jar"
+else
+    version_url=$(curl -fsSL https://piston-meta.mojang.com/mc/game/version_manifest_v2.json |
+        jq -er --arg release "$release" '.versions[] | select(.id == $release) | .url')
+    curl -fsSL "$version_url" -o "$work/version.json"
+    server_url=$(jq -er '.downloads.server.url' "$work/version.json")
+    server_sha1=$(jq -er '.downloads.server.sha1' "$work/version.json")
+
+    curl -fsSL "$server_url" -o "$work/server.jar"
+    echo "$server_sha1  $work/server.jar" | sha1sum -c -
+fi
+
+(cd "$work" && java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports)
+
+mkdir -p "$reports"
+for report in blocks.json packets.json registries.json; do
+    cp "$work/generated/reports/$report" "$reports/$report"
+done
@@ -0,0 +1,805 @@
+//! A minimal JSON serializer that writes straight into a blocking [`Write`],
+//! for the parts of the protocol that are JSON strings.
+//!
+//! Unlike `serde_json_core::to_slice` it needs no buffer, so it can be paired
+//! with [`SizeCounter`] to find the length prefix and with
+//! [`write_windowed`](minecrevy_encdec::write_windowed) to stream into an
+//! [`AsyncWrite`](embedded_byteorder::AsyncWrite).
+
+use core::fmt::{self, Display};
+
+use defmt::Format;
+use embedded_byteorder::{AsyncWrite, Write};
+use minecrevy_encdec::{
+    AsyncEncode, AsyncWriteMinecraftExt, Encode, SizeCounter, WindowedError, WireSize,
+    WriteMinecraftExt, var_i32_size, write_windowed,
+};
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+use thiserror::Error;
+
+/// Error type for serializing JSON.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum JsonError<E> {
+    #[error("map keys must be strings")]
+    KeyMustBeString,
+    #[error("failed to serialize")]
+    Custom,
+    #[error("length exceeded")]
+    LengthExceeded,
+    #[error("other error: {0:?}")]
+    Other(#[from] E),
+}
+
+impl<E> JsonError<E> {
+    /// Maps the error of the underlying writer.
+    pub fn map_other<F>(self, f: impl FnOnce(E) -> F) -> JsonError<F> {
+        match self {
+            Self::KeyMustBeString => JsonError::KeyMustBeString,
+            Self::Custom => JsonError::Custom,
+            Self::LengthExceeded => JsonError::LengthExceeded,
+            Self::Other(e) => JsonError::Other(f(e)),
+        }
+    }
+}
+
+impl<E: fmt::Debug> ser::Error for JsonError<E> {
+    fn custom<T: Display>(_msg: T) -> Self {
+        Self::Custom
+    }
+}
+
+/// Serializes `value` as compact JSON into `writer`.
+pub fn to_writer<W, T>(writer: &mut W, value: &T) -> Result<(), JsonError<W::Error>>
+where
+    W: Write + ?Sized,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut Serializer { writer })
+}
+
+/// Returns the number of bytes `value` takes up as compact JSON.
+pub fn size<T: Serialize + ?Sized>(value: &T) -> usize {
+    let mut counter = SizeCounter::default();
+    // Values that fail to serialize are never sent, so their size doesn't matter.
+    let _ = to_writer(&mut counter, value);
+    counter.size()
+}
+
+/// A value sent as a JSON string, i.e. prefixed with its length in bytes.
+///
+/// The JSON is streamed in windows, so it is never buffered as a whole.
+/// Instead, the async encoder serializes the value again for every
+/// [`WINDOW_SIZE`](minecrevy_encdec::WINDOW_SIZE) bytes, each pass stopping
+/// at the end of its window. A value of `n` bytes therefore costs about
+/// `n² / (2 * WINDOW_SIZE)` bytes of serialization, which is 8 KiB for a
+/// 1 KiB value but 8 MiB for a 32 KiB one. Keep it to values of a few KiB,
+/// such as text components, and write anything larger to the writer
+/// directly, as [`StatusResponse`](crate::r770::status::StatusResponse) does
+/// with its favicon.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> AsyncEncode for Json<T> {
+    type Options = ();
+    type Error<E> = JsonError<E>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let len = i32::try_from(size(&self.0)).map_err(|_| JsonError::LengthExceeded)?;
+        writer.write_var_i32(len).await?;
+        write_windowed(writer, |window| to_writer(window, &self.0))
+            .await
+            .map_err(|e| match e {
+                WindowedError::Write(e) => JsonError::Other(e),
+                // A full window is handled by `write_windowed` and never surfaces here.
+                WindowedError::Encode(e) => e.map_other(|_| unreachable!()),
+            })
+    }
+}
+
+impl<T: Serialize> Encode for Json<T> {
+    type Options = ();
+    type Error<E> = JsonError<E>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let len = i32::try_from(size(&self.0)).map_err(|_| JsonError::LengthExceeded)?;
+        writer.write_var_i32(len)?;
+        to_writer(writer, &self.0)
+    }
+}
+
+impl<T: Serialize> WireSize for Json<T> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        let len = size(&self.0);
+        var_i32_size(len as i32) + len
+    }
+}
+
+struct Serializer<'w, W: ?Sized> {
+    writer: &'w mut W,
+}
+
+impl<W: Write + ?Sized> Serializer<'_, W> {
+    fn raw(&mut self, bytes: &[u8]) -> Result<(), JsonError<W::Error>> {
+        Ok(self.writer.write_all(bytes)?)
+    }
+
+    fn display(&mut self, value: impl Display) -> Result<(), JsonError<W::Error>> {
+        self.format(value, false)
+    }
+
+    /// Writes the output of `value`'s [`Display`] impl, escaping it for use
+    /// inside a string if `escape` is set.
+    fn format(&mut self, value: impl Display, escape: bool) -> Result<(), JsonError<W::Error>> {
+        struct Adapter<'a, 'w, W: Write + ?Sized> {
+            ser: &'a mut Serializer<'w, W>,
+            escape: bool,
+            error: Option<JsonError<W::Error>>,
+        }
+
+        impl<W: Write + ?Sized> fmt::Write for Adapter<'_, '_, W> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let result = match self.escape {
+                    true => self.ser.escaped(s),
+                    false => self.ser.raw(s.as_bytes()),
+                };
+                result.map_err(|e| {
+                    self.error = Some(e);
+                    fmt::Error
+                })
+            }
+        }
+
+        let mut adapter = Adapter {
+            ser: self,
+            escape,
+            error: None,
+        };
+        match fmt::write(&mut adapter, format_args!("{value}")) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(adapter.error.unwrap_or(JsonError::Custom)),
+        }
+    }
+
+    fn string(&mut self, value: &str) -> Result<(), JsonError<W::Error>> {
+        self.raw(b"\"")?;
+        self.escaped(value)?;
+        self.raw(b"\"")
+    }
+
+    /// Writes the contents of a string, without the quotes.
+    fn escaped(&mut self, value: &str) -> Result<(), JsonError<W::Error>> {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+
+        let mut start = 0;
+        for (i, byte) in value.bytes().enumerate() {
+            let escaped: &[u8] = match byte {
+                b'"' => b"\\\"",
+                b'\\' => b"\\\\",
+                b'\n' => b"\\n",
+                b'\r' => b"\\r",
+                b'\t' => b"\\t",
+                0x00..=0x1F => &[
+                    b'\\',
+                    b'u',
+                    b'0',
+                    b'0',
+                    HEX[usize::from(byte >> 4)],
+                    HEX[usize::from(byte & 0xF)],
+                ],
+                _ => continue,
+            };
+            self.raw(&value.as_bytes()[start..i])?;
+            self.raw(escaped)?;
+            start = i + 1;
+        }
+        self.raw(&value.as_bytes()[start..])
+    }
+
+    fn float(&mut self, value: f64) -> Result<(), JsonError<W::Error>> {
+        match value.is_finite() {
+            true => self.display(value),
+            false => self.raw(b"null"),
+        }
+    }
+}
+
+/// Serializes the elements of a sequence or the entries of a map, writing a
+/// comma before every one but the first.
+struct Compound<'a, 'w, W: ?Sized> {
+    ser: &'a mut Serializer<'w, W>,
+    first: bool,
+    /// The closing bracket, followed by a `}` for variants.
+    close: &'static [u8],
+}
+
+impl<'a, 'w, W: Write + ?Sized> Compound<'a, 'w, W> {
+    fn open(
+        ser: &'a mut Serializer<'w, W>,
+        open: &[u8],
+        close: &'static [u8],
+    ) -> Result<Self, JsonError<W::Error>> {
+        ser.raw(open)?;
+        Ok(Self {
+            ser,
+            first: true,
+            close,
+        })
+    }
+
+    fn separator(&mut self) -> Result<(), JsonError<W::Error>> {
+        if !core::mem::take(&mut self.first) {
+            self.ser.raw(b",")?;
+        }
+        Ok(())
+    }
+
+    fn field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), JsonError<W::Error>> {
+        self.separator()?;
+        self.ser.string(key)?;
+        self.ser.raw(b":")?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError<W::Error>> {
+        self.separator()?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn close(self) -> Result<(), JsonError<W::Error>> {
+        self.ser.raw(self.close)
+    }
+}
+
+impl<'a, 'w, W: Write + ?Sized> ser::Serializer for &'a mut Serializer<'w, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+    type SerializeSeq = Compound<'a, 'w, W>;
+    type SerializeTuple = Compound<'a, 'w, W>;
+    type SerializeTupleStruct = Compound<'a, 'w, W>;
+    type SerializeTupleVariant = Compound<'a, 'w, W>;
+    type SerializeMap = Compound<'a, 'w, W>;
+    type SerializeStruct = Compound<'a, 'w, W>;
+    type SerializeStructVariant = Compound<'a, 'w, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Self::Error> {
+        self.raw(if v { b"true" } else { b"false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Self::Error> {
+        self.display(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
+        self.float(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Self::Error> {
+        self.float(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Self::Error> {
+        self.string(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Self::Error> {
+        self.string(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Self::Error> {
+        let mut seq = Compound::open(self, b"[", b"]")?;
+        for byte in v {
+            ser::SerializeSeq::serialize_element(&mut seq, byte)?;
+        }
+        seq.close()
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        self.raw(b"null")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Self::Error> {
+        self.string(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let mut map = Compound::open(self, b"{", b"}")?;
+        map.field(variant, value)?;
+        map.close()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Compound::open(self, b"[", b"]")
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Compound::open(self, b"[", b"]")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Compound::open(self, b"[", b"]")
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.raw(b"{")?;
+        self.string(variant)?;
+        Compound::open(self, b":[", b"]}")
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Compound::open(self, b"{", b"}")
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Compound::open(self, b"{", b"}")
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.raw(b"{")?;
+        self.string(variant)?;
+        Compound::open(self, b":{", b"}}")
+    }
+
+    fn collect_str<T: Display + ?Sized>(self, value: &T) -> Result<(), Self::Error> {
+        self.raw(b"\"")?;
+        self.format(value, true)?;
+        self.raw(b"\"")
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeSeq for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeTuple for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeTupleStruct for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeTupleVariant for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeMap for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.separator()?;
+        key.serialize(KeySerializer {
+            ser: &mut *self.ser,
+        })?;
+        self.ser.raw(b":")
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeStruct for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+impl<W: Write + ?Sized> ser::SerializeStructVariant for Compound<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close()
+    }
+}
+
+/// Serializes map keys, which JSON requires to be strings.
+struct KeySerializer<'a, 'w, W: ?Sized> {
+    ser: &'a mut Serializer<'w, W>,
+}
+
+macro_rules! reject_keys {
+    ($($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<(), Self::Error> {
+                Err(JsonError::KeyMustBeString)
+            }
+        )*
+    };
+}
+
+impl<W: Write + ?Sized> ser::Serializer for KeySerializer<'_, '_, W> {
+    type Ok = ();
+    type Error = JsonError<W::Error>;
+    type SerializeSeq = Impossible<(), Self::Error>;
+    type SerializeTuple = Impossible<(), Self::Error>;
+    type SerializeTupleStruct = Impossible<(), Self::Error>;
+    type SerializeTupleVariant = Impossible<(), Self::Error>;
+    type SerializeMap = Impossible<(), Self::Error>;
+    type SerializeStruct = Impossible<(), Self::Error>;
+    type SerializeStructVariant = Impossible<(), Self::Error>;
+
+    fn serialize_str(self, v: &str) -> Result<(), Self::Error> {
+        self.ser.string(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Self::Error> {
+        self.ser.string(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Self::Error> {
+        self.ser.string(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn collect_str<T: Display + ?Sized>(self, value: &T) -> Result<(), Self::Error> {
+        ser::Serializer::collect_str(self.ser, value)
+    }
+
+    reject_keys! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(JsonError::KeyMustBeString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{string::String, vec};
+
+    use embedded_byteorder::SliceWriteError;
+
+    use super::*;
+    use crate::test_util::encode;
+
+    /// A map with borrowed entries, since serde only has maps with `alloc`.
+    struct Map<'a, K, V>(&'a [(K, V)]);
+
+    impl<K: Serialize, V: Serialize> Serialize for Map<'_, K, V> {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+        }
+    }
+
+    /// Serializes `value` with [`to_writer`], checking that [`size`] counts
+    /// as many bytes as it writes.
+    fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonError<SliceWriteError>> {
+        const MAX_LEN: usize = 4096;
+
+        let mut buf = vec![0; MAX_LEN];
+        let mut writer = &mut buf[..];
+        to_writer(&mut writer, value)?;
+        let len = MAX_LEN - writer.len();
+        assert_eq!(size(value), len);
+
+        buf.truncate(len);
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(
+            to_string("say \"hi\" \\ bye").unwrap(),
+            r#""say \"hi\" \\ bye""#
+        );
+        assert_eq!(
+            to_string("\n\r\t\u{0}\u{1f}\u{7f}").unwrap(),
+            "\"\\n\\r\\t\\u0000\\u001f\u{7f}\""
+        );
+        // Anything else, including non-ASCII, is written as is.
+        assert_eq!(to_string("é€😀").unwrap(), "\"é€😀\"");
+        assert_eq!(to_string(&'"').unwrap(), r#""\"""#);
+    }
+
+    #[test]
+    fn writes_non_finite_floats_as_null() {
+        let floats = [1.5, -0.25, f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        assert_eq!(to_string(&floats).unwrap(), "[1.5,-0.25,null,null,null]");
+        assert_eq!(to_string(&f32::NAN).unwrap(), "null");
+    }
+
+    #[test]
+    fn writes_nested_maps_and_sequences() {
+        let inner: [(&str, &[i32]); 2] = [("b", &[1, -2]), ("c", &[])];
+        let outer = [("a", Map(&inner))];
+        let value = (Map(&outer), [[true], [false]], None::<u8>, Some("x"));
+        assert_eq!(
+            to_string(&value).unwrap(),
+            r#"[{"a":{"b":[1,-2],"c":[]}},[[true],[false]],null,"x"]"#
+        );
+    }
+
+    #[test]
+    fn writes_enum_variants() {
+        #[derive(Serialize)]
+        enum Shape {
+            Point,
+            Circle(f32),
+            Line(i8, i8),
+            Rect { w: u8, h: u8 },
+        }
+
+        let shapes = [
+            Shape::Point,
+            Shape::Circle(0.5),
+            Shape::Line(1, -1),
+            Shape::Rect { w: 2, h: 3 },
+        ];
+        assert_eq!(
+            to_string(&shapes).unwrap(),
+            r#"["Point",{"Circle":0.5},{"Line":[1,-1]},{"Rect":{"w":2,"h":3}}]"#
+        );
+    }
+
+    #[test]
+    fn rejects_keys_that_are_not_strings() {
+        assert_eq!(to_string(&Map(&[(1, 2)])), Err(JsonError::KeyMustBeString));
+        assert_eq!(
+            to_string(&Map(&[(Some("a"), 2)])),
+            Err(JsonError::KeyMustBeString)
+        );
+        // Characters are strings too.
+        assert_eq!(to_string(&Map(&[('k', 2)])).unwrap(), r#"{"k":2}"#);
+    }
+
+    #[test]
+    fn encode_json_string() {
+        assert_eq!(encode(&Json("hi")), b"\x04\"hi\"");
+    }
+
+    #[test]
+    fn encode_json_spanning_many_windows() {
+        // Longer than a VarInt's first byte can count, and escaped throughout.
+        let value: &[&str] = &["a\"b"; 100];
+        let json = to_string(value).unwrap();
+        assert_eq!(json.len(), 2 + 100 * 6 + 99);
+
+        let bytes = encode(&Json(value));
+        assert_eq!(bytes[..2], [0xBD, 0x05]);
+        assert_eq!(bytes[2..], *json.as_bytes());
+    }
+}
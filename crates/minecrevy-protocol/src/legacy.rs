@@ -0,0 +1,224 @@
+//! The server list ping from before 1.7, which old clients and many server
+//! list scanners still send.
+//!
+//! It starts with a [`LEGACY_PING`] byte where a packet length would be, so it
+//! can be told apart from a handshake by its first byte. The server replies
+//! with a [`LegacyPingResponse`] and closes the connection.
+
+use core::fmt::{self, Write as _};
+
+use defmt::Format;
+use embedded_byteorder::{AsyncWrite, BigEndian, Write, WriteBytesExt};
+use minecrevy_encdec::{AsyncEncode, Encode, WindowedError, WireSize, write_windowed};
+use thiserror::Error;
+
+use crate::{r770::StatusResponse, text::Text};
+
+/// The first byte of a legacy server list ping.
+pub const LEGACY_PING: u8 = 0xFE;
+
+/// The packet ID of the kick packet that carries a [`LegacyPingResponse`].
+pub const LEGACY_KICK: u8 = 0xFF;
+
+/// The reply to a legacy server list ping, sent as a kick packet whose reason
+/// is a UTF-16BE string of NUL-separated fields.
+///
+/// Clients from 1.4 to 1.6 read every field, older ones only show the MOTD
+/// and player counts.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LegacyPingResponse<'a> {
+    pub protocol: i32,
+    pub version: &'a str,
+    /// Written as plain text, since legacy clients don't know text components.
+    pub motd: Text<'a>,
+    pub online: i32,
+    pub max: i32,
+}
+
+impl<'a> From<&StatusResponse<'a>> for LegacyPingResponse<'a> {
+    fn from(status: &StatusResponse<'a>) -> Self {
+        Self {
+            protocol: status.version.protocol(),
+            version: status.version.name(),
+            motd: status.description,
+            online: status.players.online,
+            max: status.players.max,
+        }
+    }
+}
+
+impl LegacyPingResponse<'_> {
+    /// The length of the kick reason in UTF-16 code units.
+    fn reason_len(&self) -> usize {
+        let mut counter = Utf16Counter(0);
+        // Formatting into a counter never fails.
+        let _ = write!(counter, "{self}");
+        counter.0
+    }
+}
+
+/// Writes the kick reason.
+impl fmt::Display for LegacyPingResponse<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            self.protocol, self.version, self.motd, self.online, self.max
+        )
+    }
+}
+
+/// Error type for encoding a [`LegacyPingResponse`].
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum LegacyPingError<E> {
+    #[error("kick reason longer than 65535 UTF-16 code units")]
+    ReasonTooLong,
+    #[error("other error: {0:?}")]
+    Other(#[from] E),
+}
+
+impl Encode for LegacyPingResponse<'_> {
+    type Options = ();
+    type Error<E> = LegacyPingError<E>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let len = u16::try_from(self.reason_len()).map_err(|_| LegacyPingError::ReasonTooLong)?;
+        writer.write_u8(LEGACY_KICK)?;
+        writer.write_u16::<BigEndian>(len)?;
+
+        let mut utf16 = Utf16Writer {
+            writer,
+            error: None,
+        };
+        match write!(utf16, "{self}") {
+            Ok(()) => Ok(()),
+            // Only the writer can fail, since formatting the fields can't.
+            Err(fmt::Error) => Err(LegacyPingError::Other(utf16.error.unwrap())),
+        }
+    }
+}
+
+impl AsyncEncode for LegacyPingResponse<'_> {
+    type Options = ();
+    type Error<E> = LegacyPingError<E>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        write_windowed(writer, |window| Encode::encode(self, window, ()))
+            .await
+            .map_err(|e| match e {
+                WindowedError::Write(e) => LegacyPingError::Other(e),
+                WindowedError::Encode(LegacyPingError::ReasonTooLong) => {
+                    LegacyPingError::ReasonTooLong
+                }
+                // A full window is handled by `write_windowed` and never surfaces here.
+                WindowedError::Encode(LegacyPingError::Other(_)) => unreachable!(),
+            })
+    }
+}
+
+impl WireSize for LegacyPingResponse<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        1 + 2 + 2 * self.reason_len()
+    }
+}
+
+/// Counts the UTF-16 code units of everything written to it.
+struct Utf16Counter(usize);
+
+impl fmt::Write for Utf16Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.encode_utf16().count();
+        Ok(())
+    }
+}
+
+/// Writes everything written to it as UTF-16BE, keeping the first error.
+struct Utf16Writer<'w, W: Write> {
+    writer: &'w mut W,
+    error: Option<W::Error>,
+}
+
+impl<W: Write> fmt::Write for Utf16Writer<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for unit in s.encode_utf16() {
+            if let Err(e) = self.writer.write_u16::<BigEndian>(unit) {
+                self.error = Some(e);
+                return Err(fmt::Error);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::test_util::encode;
+
+    #[test]
+    fn encode_response() {
+        let response = LegacyPingResponse {
+            protocol: 770,
+            version: "1.21.5",
+            motd: Text::literal("A Minecraft Server"),
+            online: 3,
+            max: 20,
+        };
+        let buf = encode(&response);
+
+        let reason = "§1\x00770\x001.21.5\x00A Minecraft Server\x003\x0020";
+        let len = reason.encode_utf16().count();
+        assert_eq!(len, 37);
+        assert_eq!(buf[..3], [LEGACY_KICK, 0x00, 37]);
+        assert_eq!(buf[3..9], [0x00, 0xa7, 0x00, b'1', 0x00, 0x00]);
+        assert!(
+            buf[3..]
+                .chunks(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .eq(reason.encode_utf16())
+        );
+        assert_eq!(buf.len(), 3 + 2 * len);
+    }
+
+    #[test]
+    fn counts_surrogate_pairs() {
+        let response = LegacyPingResponse {
+            protocol: 770,
+            version: "1.21.5",
+            motd: Text::literal("\u{1F600}"),
+            online: 0,
+            max: 0,
+        };
+        let buf = encode(&response);
+        // The emoji is one character but two UTF-16 code units.
+        assert_eq!(buf[1..3], [0x00, 20]);
+        assert_eq!(buf[31..35], [0xd8, 0x3d, 0xde, 0x00]);
+    }
+
+    #[test]
+    fn rejects_reason_too_long() {
+        let motd = std::string::String::from("a").repeat(65536);
+        let response = LegacyPingResponse {
+            protocol: 770,
+            version: "1.21.5",
+            motd: Text::literal(&motd),
+            online: 0,
+            max: 0,
+        };
+        let mut buf = [0; 16];
+        let result = Encode::encode(&response, &mut &mut buf[..], ());
+        assert!(matches!(result, Err(LegacyPingError::ReasonTooLong)));
+    }
+}
@@ -1,4 +1,10 @@
 #![no_std]
 
+pub mod json;
+pub mod legacy;
+pub mod md5;
 /// Release version 1.21.5
 pub mod r770;
+#[cfg(test)]
+mod test_util;
+pub mod text;
@@ -0,0 +1,170 @@
+//! MD5, which vanilla uses to derive the UUIDs of offline players.
+//!
+//! MD5 is not collision resistant, so don't use it for anything that needs to
+//! be secure.
+
+/// The per-round left rotations.
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, //
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, //
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, //
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// The per-round constants, `floor(abs(sin(i + 1)) * 2^32)`.
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// An incremental MD5 hasher.
+#[derive(Clone, Debug)]
+pub struct Md5 {
+    state: [u32; 4],
+    block: [u8; 64],
+    /// The number of bytes hashed so far.
+    len: u64,
+}
+
+impl Md5 {
+    pub const fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            block: [0; 64],
+            len: 0,
+        }
+    }
+
+    /// Hashes `data` in one go.
+    pub fn digest(data: &[u8]) -> [u8; 16] {
+        let mut md5 = Self::new();
+        md5.update(data);
+        md5.finalize()
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let offset = (self.len % 64) as usize;
+            let n = data.len().min(64 - offset);
+            self.block[offset..][..n].copy_from_slice(&data[..n]);
+            self.len += n as u64;
+            data = &data[n..];
+            if offset + n == 64 {
+                compress(&mut self.state, &self.block);
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 16] {
+        let bits = self.len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.len % 64 != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_le_bytes());
+
+        let mut digest = [0; 16];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn compress(state: &mut [u32; 4], block: &[u8; 64]) {
+    let mut words = [0; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a
+            .wrapping_add(f)
+            .wrapping_add(K[i])
+            .wrapping_add(words[g])
+            .rotate_left(SHIFTS[i]);
+        (a, b, c, d) = (d, b.wrapping_add(rotated), b, c);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(data: &[u8]) -> u128 {
+        u128::from_be_bytes(Md5::digest(data))
+    }
+
+    #[test]
+    fn rfc_1321_test_suite() {
+        let cases: [(&[u8], u128); 7] = [
+            (b"", 0xd41d8cd98f00b204e9800998ecf8427e),
+            (b"a", 0x0cc175b9c0f1b6a831c399e269772661),
+            (b"abc", 0x900150983cd24fb0d6963f7d28e17f72),
+            (b"message digest", 0xf96b697d7cb7938d525a2f31aaf161d0),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                0xc3fcd3d76192e4007dfb496cca67e13b,
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                0xd174ab98d277d9f5a5611c2c9f419d9f,
+            ),
+            // Two blocks.
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                0x57edf4a22be3c955ac49da2e2107b67a,
+            ),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(digest(data), expected, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn padding_at_the_block_boundary() {
+        // 55 bytes leave just enough room for the padding in the same block,
+        // 56 don't, and 64 fill the block exactly.
+        assert_eq!(digest(&[b'a'; 55]), 0xef1772b6dff9a122358552954ad0df65);
+        assert_eq!(digest(&[b'a'; 56]), 0x3b0c8ac703f828b04c6c197006d17218);
+        assert_eq!(digest(&[b'a'; 64]), 0x014842d480b571495a4a0363793f7367);
+    }
+
+    #[test]
+    fn update_in_pieces() {
+        let data: [u8; 200] = core::array::from_fn(|i| i as u8);
+        assert_eq!(digest(&data), 0xfb7001d34b8e82c9b579be5005d5b0a5);
+
+        // Splits that cross the block boundaries in different places.
+        for split in [1, 63, 64, 65, 127, 199] {
+            let mut md5 = Md5::new();
+            md5.update(&data[..split]);
+            md5.update(&[]);
+            md5.update(&data[split..]);
+            assert_eq!(md5.finalize(), Md5::digest(&data), "{split}");
+        }
+    }
+}
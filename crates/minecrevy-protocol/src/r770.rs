@@ -1,5 +1,12 @@
+pub mod blocks;
+pub mod configuration;
 mod handshake;
+mod login;
+pub mod packet_ids;
+pub mod play;
+pub mod registries;
 mod status;
 
 pub use handshake::*;
+pub use login::*;
 pub use status::*;
@@ -0,0 +1,3 @@
+//! Blocks and their states, generated from the vanilla `blocks.json` report.
+
+include!(concat!(env!("OUT_DIR"), "/r770/blocks.rs"));
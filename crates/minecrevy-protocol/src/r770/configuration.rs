@@ -0,0 +1,513 @@
+//! The configuration state, which the client enters once it has sent
+//! [`LoginAcknowledged`](super::LoginAcknowledged).
+//!
+//! The server sends the registries, feature flags and tags the client needs
+//! before it can join, and may exchange resource packs, cookies and plugin
+//! messages. It then sends [`FinishConfiguration`], and once the client has
+//! answered with [`AcknowledgeFinishConfiguration`] both sides switch to the
+//! play state.
+//!
+//! Cookies use the same [`CookieRequest`] and [`CookieResponse`] as login,
+//! under clientbound `0x00` and serverbound `0x01`.
+
+use core::convert::Infallible;
+
+use heapless::{String, Vec};
+use minecrevy_encdec::{
+    AsyncDecode, AsyncEncode, BorrowDecode, Decode, Encode, Identifier, OwnedIdentifier,
+    ReadMinecraftError, WireSize,
+    nbt::Tag,
+    options::{BytesOptions, Framing},
+};
+use uuid::Uuid;
+
+use super::MAX_IDENTIFIER_LEN;
+pub use super::{CookieRequest, CookieResponse};
+use crate::text::Text;
+
+/// The maximum number of known packs that is read.
+pub const MAX_KNOWN_PACKS: usize = 8;
+
+/// The maximum length of the namespace, ID and version of a known pack that is
+/// read.
+pub const MAX_KNOWN_PACK_LEN: usize = 64;
+
+/// Clientbound `0x01`: a message on a custom channel, such as
+/// `minecraft:brand`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClientboundPluginMessage<'a> {
+    pub channel: Identifier<'a>,
+    /// Sent as the rest of the packet, without a length prefix.
+    #[options(.framing = Framing::Remaining)]
+    pub data: &'a [u8],
+}
+
+/// Clientbound `0x02`: kicks the player during configuration.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct Disconnect<'a> {
+    pub reason: Text<'a>,
+}
+
+/// Clientbound `0x03`: ends the configuration, which the client answers with
+/// [`AcknowledgeFinishConfiguration`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FinishConfiguration;
+
+/// Clientbound `0x04`: checks that the client is still there. It must answer
+/// with a [`ServerboundKeepAlive`] holding the same ID.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClientboundKeepAlive(pub i64);
+
+/// Clientbound `0x05`: answered with a [`Pong`] holding the same ID.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ping(pub i32);
+
+/// Clientbound `0x06`: clears the client's chat history and signing state.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ResetChat;
+
+/// Clientbound `0x07`: the entries of one registry, such as
+/// `minecraft:dimension_type`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct RegistryData<'a> {
+    pub registry: Identifier<'a>,
+    pub entries: &'a [RegistryEntry<'a>],
+}
+
+/// An entry of a [`RegistryData`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct RegistryEntry<'a> {
+    pub id: Identifier<'a>,
+    /// The entry itself, which can be left out if the client has it in one of
+    /// its known packs.
+    pub data: Option<Tag<'a>>,
+}
+
+/// Clientbound `0x08`: removes one resource pack, or all of them if `uuid` is
+/// [`None`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RemoveResourcePack {
+    pub uuid: Option<Uuid>,
+}
+
+/// Clientbound `0x09`: asks the client to download and apply a resource
+/// pack. It answers with [`ResourcePackResponse`]s as the download goes on.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct AddResourcePack<'a> {
+    pub uuid: Uuid,
+    pub url: &'a str,
+    /// The SHA-1 of the pack in lowercase hex, or empty to skip the check.
+    pub hash: &'a str,
+    /// Whether the client is disconnected if it declines the pack.
+    pub forced: bool,
+    /// Shown in the prompt that asks the player to accept the pack.
+    pub prompt: Option<Text<'a>>,
+}
+
+/// Clientbound `0x0A`: stores a cookie on the client, which it keeps across
+/// [`Transfer`]s.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StoreCookie<'a> {
+    pub key: Identifier<'a>,
+    /// At most [`MAX_COOKIE_SIZE`](super::MAX_COOKIE_SIZE) bytes.
+    pub payload: &'a [u8],
+}
+
+/// Clientbound `0x0B`: sends the client to another server, which it joins
+/// with [`NextState::Transfer`](super::NextState::Transfer).
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Transfer<'a> {
+    pub host: &'a str,
+    #[options(.varint = true)]
+    pub port: i32,
+}
+
+/// Clientbound `0x0C`: the feature flags to enable, such as
+/// `minecraft:vanilla`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FeatureFlags<'a> {
+    pub flags: &'a [Identifier<'a>],
+}
+
+/// Clientbound `0x0D`: the tags of each registry, such as which blocks are
+/// `minecraft:logs`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UpdateTags<'a> {
+    pub registries: &'a [RegistryTags<'a>],
+}
+
+/// The tags of one registry in [`UpdateTags`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RegistryTags<'a> {
+    pub registry: Identifier<'a>,
+    pub tags: &'a [TagEntries<'a>],
+}
+
+/// A tag and the numeric IDs of the registry entries in it.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TagEntries<'a> {
+    pub name: Identifier<'a>,
+    #[options(.element.varint = true)]
+    pub entries: &'a [i32],
+}
+
+/// Clientbound `0x0E`: the data packs the server uses, so that the client can
+/// say which of them it already has in a [`ServerboundKnownPacks`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClientboundKnownPacks<'a> {
+    pub packs: &'a [KnownPack<'a>],
+}
+
+/// A data pack, such as vanilla's `minecraft:core` at version `1.21.5`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KnownPack<'a> {
+    pub namespace: &'a str,
+    pub id: &'a str,
+    pub version: &'a str,
+}
+
+/// Clientbound `0x0F`: extra details that the client adds to crash reports
+/// and disconnection reports.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CustomReportDetails<'a> {
+    /// At most 32 details.
+    pub details: &'a [ReportDetail<'a>],
+}
+
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReportDetail<'a> {
+    /// At most 128 characters.
+    pub title: &'a str,
+    /// At most 4096 characters.
+    pub description: &'a str,
+}
+
+/// Clientbound `0x10`: links shown in the pause menu.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct ServerLinks<'a> {
+    pub links: &'a [ServerLink<'a>],
+}
+
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct ServerLink<'a> {
+    pub label: ServerLinkLabel<'a>,
+    pub url: &'a str,
+}
+
+/// What a [`ServerLink`] is labelled with, prefixed with whether it is built
+/// in.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+#[tag(u8)]
+#[repr(u8)]
+pub enum ServerLinkLabel<'a> {
+    /// One of the labels the client translates itself.
+    BuiltIn(BuiltInLinkLabel) = 1,
+    Custom(Text<'a>) = 0,
+}
+
+#[derive(AsyncEncode, Encode, WireSize, defmt::Format, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BuiltInLinkLabel {
+    BugReport = 0,
+    CommunityGuidelines = 1,
+    Support = 2,
+    Status = 3,
+    Feedback = 4,
+    Community = 5,
+    Website = 6,
+    Forums = 7,
+    News = 8,
+    Announcements = 9,
+}
+
+/// Serverbound `0x00`: the client's settings. It is sent again during play
+/// whenever they change.
+#[derive(AsyncDecode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ClientInformation {
+    /// Such as `en_us`.
+    pub locale: String<16>,
+    /// The render distance, in chunks.
+    pub view_distance: i8,
+    pub chat_mode: ChatMode,
+    pub chat_colors: bool,
+    /// A bit mask of the skin layers to show: cape (`0x01`), jacket, left
+    /// sleeve, right sleeve, left pants leg, right pants leg and hat (`0x40`).
+    pub displayed_skin_parts: u8,
+    pub main_hand: MainHand,
+    pub enable_text_filtering: bool,
+    /// Whether the player may be listed in the server list sample.
+    pub allow_server_listings: bool,
+    pub particle_status: ParticleStatus,
+}
+
+#[derive(AsyncDecode, Decode, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChatMode {
+    Enabled = 0,
+    CommandsOnly = 1,
+    Hidden = 2,
+}
+
+#[derive(AsyncDecode, Decode, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MainHand {
+    Left = 0,
+    Right = 1,
+}
+
+#[derive(AsyncDecode, Decode, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleStatus {
+    All = 0,
+    Decreased = 1,
+    Minimal = 2,
+}
+
+/// Serverbound `0x02`: a message on a custom channel, holding up to `N` bytes
+/// of data.
+#[derive(AsyncDecode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ServerboundPluginMessage<const N: usize> {
+    pub channel: OwnedIdentifier<MAX_IDENTIFIER_LEN>,
+    #[options(.framing = Framing::Remaining)]
+    pub data: Vec<u8, N>,
+}
+
+/// Serverbound `0x02`: a [`ServerboundPluginMessage`] that points into the
+/// packet, so that large messages are not copied.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ServerboundPluginMessageRef<'a> {
+    pub channel: Identifier<'a>,
+    pub data: &'a [u8],
+}
+
+impl<'a> BorrowDecode<'a> for ServerboundPluginMessageRef<'a> {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        Ok(Self {
+            channel: Identifier::decode(reader, ())?,
+            data: <&[u8]>::decode(reader, BytesOptions { remaining: true })?,
+        })
+    }
+}
+
+/// Serverbound `0x03`: the client has received [`FinishConfiguration`] and
+/// switched to the play state.
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AcknowledgeFinishConfiguration;
+
+/// Serverbound `0x04`: the answer to a [`ClientboundKeepAlive`].
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ServerboundKeepAlive(pub i64);
+
+/// Serverbound `0x05`: the answer to a [`Ping`].
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pong(pub i32);
+
+/// Serverbound `0x06`: how an [`AddResourcePack`] is going.
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ResourcePackResponse {
+    pub uuid: Uuid,
+    pub result: ResourcePackResult,
+}
+
+#[derive(AsyncDecode, Decode, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResourcePackResult {
+    SuccessfullyDownloaded = 0,
+    Declined = 1,
+    FailedToDownload = 2,
+    Accepted = 3,
+    Downloaded = 4,
+    InvalidUrl = 5,
+    FailedToReload = 6,
+    Discarded = 7,
+}
+
+/// Serverbound `0x07`: the packs from [`ClientboundKnownPacks`] that the
+/// client has, holding up to `N` of them.
+#[derive(AsyncDecode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ServerboundKnownPacks<const N: usize = MAX_KNOWN_PACKS> {
+    pub packs: Vec<OwnedKnownPack, N>,
+}
+
+impl<const N: usize> ServerboundKnownPacks<N> {
+    /// Whether the client has `pack`.
+    pub fn contains(&self, pack: &KnownPack<'_>) -> bool {
+        self.packs
+            .iter()
+            .any(|known| known.as_known_pack() == *pack)
+    }
+}
+
+/// A [`KnownPack`] that has been read.
+#[derive(AsyncDecode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct OwnedKnownPack {
+    pub namespace: String<MAX_KNOWN_PACK_LEN>,
+    pub id: String<MAX_KNOWN_PACK_LEN>,
+    pub version: String<MAX_KNOWN_PACK_LEN>,
+}
+
+impl OwnedKnownPack {
+    pub fn as_known_pack(&self) -> KnownPack<'_> {
+        KnownPack {
+            namespace: &self.namespace,
+            id: &self.id,
+            version: &self.version,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use minecrevy_encdec::ReadArrayError;
+
+    use super::*;
+    use crate::test_util::{block_on, encode};
+
+    const TEXT_X: &[u8] = &[10, 8, 0, 4, b't', b'e', b'x', b't', 0, 1, b'x', 0];
+
+    #[test]
+    fn encode_plugin_message_without_length() {
+        let message = ClientboundPluginMessage {
+            channel: Identifier::minecraft("brand"),
+            data: b"\x07vanilla",
+        };
+        assert_eq!(encode(&message), b"\x0fminecraft:brand\x07vanilla");
+    }
+
+    #[test]
+    fn encode_registry_data() {
+        let with_data = RegistryData {
+            registry: Identifier::minecraft("dimension_type"),
+            entries: &[RegistryEntry {
+                id: Identifier::minecraft("overworld"),
+                data: Some(Tag::Compound(&[("a", Tag::Byte(1))])),
+            }],
+        };
+        let expected = [
+            b"\x18minecraft:dimension_type\x01\x13minecraft:overworld".as_slice(),
+            // Has data, followed by the nameless NBT compound.
+            &[1, 10, 1, 0, 1, b'a', 1, 0],
+        ]
+        .concat();
+        assert_eq!(encode(&with_data), expected);
+
+        let without_data = RegistryData {
+            registry: Identifier::minecraft("dimension_type"),
+            entries: &[RegistryEntry {
+                id: Identifier::minecraft("overworld"),
+                data: None,
+            }],
+        };
+        assert_eq!(
+            encode(&without_data),
+            b"\x18minecraft:dimension_type\x01\x13minecraft:overworld\x00"
+        );
+    }
+
+    #[test]
+    fn encode_update_tags() {
+        let tags = UpdateTags {
+            registries: &[RegistryTags {
+                registry: Identifier::minecraft("block"),
+                tags: &[TagEntries {
+                    name: Identifier::minecraft("logs"),
+                    entries: &[1, 300],
+                }],
+            }],
+        };
+        // The entries are VarInts.
+        assert_eq!(
+            encode(&tags),
+            b"\x01\x0fminecraft:block\x01\x0eminecraft:logs\x02\x01\xac\x02"
+        );
+    }
+
+    #[test]
+    fn encode_server_links() {
+        let links = ServerLinks {
+            links: &[
+                ServerLink {
+                    label: ServerLinkLabel::BuiltIn(BuiltInLinkLabel::Support),
+                    url: "a",
+                },
+                ServerLink {
+                    label: ServerLinkLabel::Custom(Text::literal("x")),
+                    url: "b",
+                },
+            ],
+        };
+        let expected = [
+            // Two links, the first built in as a VarInt.
+            &[2, 1, 2, 1, b'a'][..],
+            // The second one custom, as a text component.
+            &[0],
+            TEXT_X,
+            &[1, b'b'],
+        ]
+        .concat();
+        assert_eq!(encode(&links), expected);
+    }
+
+    #[test]
+    fn encode_add_resource_pack() {
+        let mut pack = AddResourcePack {
+            uuid: Uuid::from_u128(1),
+            url: "u",
+            hash: "",
+            forced: true,
+            prompt: None,
+        };
+        let uuid = [0; 15].into_iter().chain([1]);
+        let expected: Vec<u8> = uuid.clone().chain([1, b'u', 0, 1, 0]).collect();
+        assert_eq!(encode(&pack), expected);
+
+        pack.prompt = Some(Text::literal("x"));
+        let expected: Vec<u8> = uuid
+            .chain([1, b'u', 0, 1, 1])
+            .chain(TEXT_X.iter().copied())
+            .collect();
+        assert_eq!(encode(&pack), expected);
+    }
+
+    #[test]
+    fn decode_serverbound_known_packs() {
+        let bytes = b"\x01\x09minecraft\x04core\x061.21.5";
+        let core = KnownPack {
+            namespace: "minecraft",
+            id: "core",
+            version: "1.21.5",
+        };
+
+        let mut reader = &bytes[..];
+        let packs = <ServerboundKnownPacks as Decode>::decode(&mut reader, ()).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(packs.packs.len(), 1);
+        assert!(packs.contains(&core));
+
+        let mut reader = &bytes[..];
+        let packs = block_on(<ServerboundKnownPacks as AsyncDecode>::decode(
+            &mut reader,
+            (),
+        ))
+        .unwrap();
+        assert!(packs.contains(&core));
+
+        let mut reader = &b"\x02\x01a\x01b\x01c\x01d\x01e\x01f"[..];
+        let error = <ServerboundKnownPacks<1> as Decode>::decode(&mut reader, ()).unwrap_err();
+        assert!(matches!(
+            error,
+            DecodeServerboundKnownPacksError::Packs(ReadArrayError::LengthExceeded)
+        ));
+    }
+
+    #[test]
+    fn decode_serverbound_plugin_message_ref() {
+        let mut reader = &b"\x0fminecraft:brand\x07vanilla"[..];
+        let message = ServerboundPluginMessageRef::decode(&mut reader, ()).unwrap();
+        assert_eq!(message.channel, Identifier::minecraft("brand"));
+        assert_eq!(message.data, b"\x07vanilla");
+        assert!(reader.is_empty());
+    }
+}
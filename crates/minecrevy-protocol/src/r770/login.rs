@@ -0,0 +1,381 @@
+use core::convert::Infallible;
+
+use embedded_byteorder::{AsyncRead, AsyncReadBytesExt, Read, ReadBytesExt};
+use heapless::{String, Vec};
+use minecrevy_encdec::{
+    AsyncDecode, AsyncEncode, AsyncReadMinecraftExt, BorrowDecode, Decode, Encode, Identifier,
+    OwnedIdentifier, ReadMinecraftError, ReadMinecraftExt, WireSize,
+    options::{BytesOptions, Framing},
+};
+use serde::Deserialize;
+use uuid::{Builder, Uuid};
+
+use crate::{json::Json, md5::Md5, text::Text};
+
+/// The maximum size of a cookie payload.
+pub const MAX_COOKIE_SIZE: usize = 5120;
+
+/// The maximum size of an RSA-encrypted field, which is the size of a 1024-bit
+/// modulus.
+pub const MAX_ENCRYPTED_SIZE: usize = 128;
+
+/// The maximum length of an identifier that is read, such as a cookie key.
+pub const MAX_IDENTIFIER_LEN: usize = 256;
+
+/// Clientbound `0x00`: kicks the player before they have joined.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct LoginDisconnect<'a> {
+    pub reason: Json<Text<'a>>,
+}
+
+/// Clientbound `0x01`: starts encryption, which the client answers with an
+/// [`EncryptionResponse`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EncryptionRequest<'a> {
+    /// Always empty on modern servers.
+    pub server_id: &'a str,
+    /// The server's DER-encoded RSA public key.
+    pub public_key: &'a [u8],
+    /// Random bytes that the client encrypts with `public_key`, to prove it
+    /// used the right key.
+    pub verify_token: &'a [u8],
+    /// Whether the client should authenticate with the session server.
+    pub should_authenticate: bool,
+}
+
+/// Clientbound `0x02`: finishes the login, after which the client sends
+/// [`LoginAcknowledged`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoginSuccess<'a> {
+    pub uuid: Uuid,
+    pub username: &'a str,
+    pub properties: &'a [ProfileProperty<'a>],
+}
+
+/// A property of a player's profile, such as their skin in `textures`.
+///
+/// It deserializes from the JSON the session server returns.
+#[derive(AsyncEncode, Encode, WireSize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ProfileProperty<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    /// The session server's signature of `value`, if it was requested.
+    #[serde(borrow)]
+    pub signature: Option<&'a str>,
+}
+
+/// Clientbound `0x03`: enables compression for every packet after this one.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SetCompression {
+    /// The smallest packet size that is compressed, or a negative number to
+    /// disable compression.
+    #[options(.varint = true)]
+    pub threshold: i32,
+}
+
+/// Clientbound `0x04`: a custom query, answered by a [`LoginPluginResponse`]
+/// with the same `message_id`.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoginPluginRequest<'a> {
+    #[options(.varint = true)]
+    pub message_id: i32,
+    pub channel: Identifier<'a>,
+    /// Sent as the rest of the packet, without a length prefix.
+    #[options(.framing = Framing::Remaining)]
+    pub data: &'a [u8],
+}
+
+/// Clientbound `0x05`: asks for the cookie stored under `key`, which the
+/// client sends back in a [`CookieResponse`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CookieRequest<'a> {
+    pub key: Identifier<'a>,
+}
+
+/// Serverbound `0x00`: starts the login.
+#[derive(AsyncDecode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct LoginStart {
+    pub name: String<16>,
+    /// The UUID of the client's account. Offline servers ignore it and use
+    /// [`offline_uuid`] instead.
+    pub uuid: Uuid,
+}
+
+/// Returns the UUID vanilla gives a player called `name` on an offline server,
+/// which is the version 3 UUID of `OfflinePlayer:<name>`.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let mut md5 = Md5::new();
+    md5.update(b"OfflinePlayer:");
+    md5.update(name.as_bytes());
+    Builder::from_md5_bytes(md5.finalize()).into_uuid()
+}
+
+/// Serverbound `0x01`: the shared secret and verify token, each encrypted
+/// with the public key from the [`EncryptionRequest`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncryptionResponse {
+    pub shared_secret: Vec<u8, MAX_ENCRYPTED_SIZE>,
+    pub verify_token: Vec<u8, MAX_ENCRYPTED_SIZE>,
+}
+
+impl AsyncDecode for EncryptionResponse {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        Ok(Self {
+            shared_secret: reader.read_bytes().await?,
+            verify_token: reader.read_bytes().await?,
+        })
+    }
+}
+
+impl Decode for EncryptionResponse {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        Ok(Self {
+            shared_secret: reader.read_bytes()?,
+            verify_token: reader.read_bytes()?,
+        })
+    }
+}
+
+/// Serverbound `0x02`: the answer to a [`LoginPluginRequest`], holding up to
+/// `N` bytes of data.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LoginPluginResponse<const N: usize> {
+    pub message_id: i32,
+    /// [`None`] if the client didn't understand the request.
+    pub data: Option<Vec<u8, N>>,
+}
+
+impl<const N: usize> AsyncDecode for LoginPluginResponse<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let message_id = reader.read_var_i32().await?;
+        let data = match reader.read_u8().await? {
+            0 => None,
+            _ => Some(reader.read_remaining().await?),
+        };
+        Ok(Self { message_id, data })
+    }
+}
+
+impl<const N: usize> Decode for LoginPluginResponse<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        let message_id = reader.read_var_i32()?;
+        let data = match reader.read_u8()? {
+            0 => None,
+            _ => Some(reader.read_remaining()?),
+        };
+        Ok(Self { message_id, data })
+    }
+}
+
+/// Serverbound `0x02`: a [`LoginPluginResponse`] that points into the packet,
+/// so that large responses are not copied.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoginPluginResponseRef<'a> {
+    pub message_id: i32,
+    /// [`None`] if the client didn't understand the request.
+    pub data: Option<&'a [u8]>,
+}
+
+impl<'a> BorrowDecode<'a> for LoginPluginResponseRef<'a> {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        let message_id = ReadMinecraftExt::read_var_i32(reader)?;
+        let data = match ReadBytesExt::read_u8(reader)? {
+            0 => None,
+            _ => Some(<&[u8]>::decode(reader, BytesOptions { remaining: true })?),
+        };
+        Ok(Self { message_id, data })
+    }
+}
+
+/// Serverbound `0x03`: the client has received [`LoginSuccess`] and switched
+/// to the configuration state.
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoginAcknowledged;
+
+/// Serverbound `0x04`: the answer to a [`CookieRequest`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CookieResponse<const N: usize = MAX_COOKIE_SIZE> {
+    pub key: OwnedIdentifier<MAX_IDENTIFIER_LEN>,
+    /// [`None`] if the client has no cookie stored under `key`.
+    pub payload: Option<Vec<u8, N>>,
+}
+
+impl<const N: usize> AsyncDecode for CookieResponse<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    async fn decode<R: AsyncRead>(
+        reader: &mut R,
+        (): Self::Options,
+    ) -> Result<Self, Self::Error<R::Error>> {
+        let key = reader.read_identifier().await?;
+        let payload = match reader.read_u8().await? {
+            0 => None,
+            _ => Some(reader.read_bytes().await?),
+        };
+        Ok(Self { key, payload })
+    }
+}
+
+impl<const N: usize> Decode for CookieResponse<N> {
+    type Options = ();
+    type Error<E> = ReadMinecraftError<E>;
+
+    fn decode<R: Read>(reader: &mut R, (): Self::Options) -> Result<Self, Self::Error<R::Error>> {
+        let key = reader.read_identifier()?;
+        let payload = match reader.read_u8()? {
+            0 => None,
+            _ => Some(reader.read_bytes()?),
+        };
+        Ok(Self { key, payload })
+    }
+}
+
+/// Serverbound `0x04`: a [`CookieResponse`] that points into the packet, so
+/// that the payload is not copied.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CookieResponseRef<'a> {
+    pub key: Identifier<'a>,
+    /// [`None`] if the client has no cookie stored under `key`.
+    pub payload: Option<&'a [u8]>,
+}
+
+impl<'a> BorrowDecode<'a> for CookieResponseRef<'a> {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        let key = Identifier::decode(reader, ())?;
+        let payload = match ReadBytesExt::read_u8(reader)? {
+            0 => None,
+            _ => Some(<&[u8]>::decode(reader, BytesOptions::default())?),
+        };
+        Ok(Self { key, payload })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::encode;
+
+    #[test]
+    fn offline_uuid_matches_vanilla() {
+        assert_eq!(
+            offline_uuid("Notch"),
+            Uuid::from_u128(0xb50ad385_829d_3141_a216_7e7d7539ba7f)
+        );
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+    }
+
+    #[test]
+    fn encode_encryption_request() {
+        let request = EncryptionRequest {
+            server_id: "",
+            public_key: &[0x30, 0x81],
+            verify_token: &[1, 2, 3, 4],
+            should_authenticate: true,
+        };
+        assert_eq!(
+            encode(&request),
+            [0x00, 0x02, 0x30, 0x81, 0x04, 1, 2, 3, 4, 0x01]
+        );
+    }
+
+    #[test]
+    fn encode_login_success() {
+        let properties = [
+            ProfileProperty {
+                name: "textures",
+                value: "abc",
+                signature: Some("sig"),
+            },
+            ProfileProperty {
+                name: "a",
+                value: "b",
+                signature: None,
+            },
+        ];
+        let success = LoginSuccess {
+            uuid: Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5),
+            username: "Notch",
+            properties: &properties,
+        };
+        let mut expected = Vec::<u8, 256>::new();
+        expected
+            .extend_from_slice(&0x069a79f4_44e9_4726_a5be_fca90e38aaf5_u128.to_be_bytes())
+            .unwrap();
+        expected.extend_from_slice(b"\x05Notch\x02").unwrap();
+        expected
+            .extend_from_slice(b"\x08textures\x03abc\x01\x03sig")
+            .unwrap();
+        expected.extend_from_slice(b"\x01a\x01b\x00").unwrap();
+        assert_eq!(encode(&success), &expected[..]);
+    }
+
+    #[test]
+    fn encode_login_plugin_request() {
+        let request = LoginPluginRequest {
+            message_id: 129,
+            channel: Identifier::new("test", "query").unwrap(),
+            data: &[0xaa, 0xbb],
+        };
+        assert_eq!(encode(&request), *b"\x81\x01\x0atest:query\xaa\xbb");
+    }
+
+    #[test]
+    fn decode_login_plugin_response_ref() {
+        let mut reader = &[0x81, 0x01, 0x01, 0xaa, 0xbb][..];
+        let response = LoginPluginResponseRef::decode(&mut reader, ()).unwrap();
+        assert_eq!(response.message_id, 129);
+        assert_eq!(response.data, Some(&[0xaa, 0xbb][..]));
+        assert!(reader.is_empty());
+
+        let mut reader = &[0x05, 0x00][..];
+        let response = LoginPluginResponseRef::decode(&mut reader, ()).unwrap();
+        assert_eq!(response.message_id, 5);
+        assert_eq!(response.data, None);
+    }
+
+    #[test]
+    fn decode_cookie_response_ref() {
+        let mut reader = &b"\x0atest:token\x01\x03abc"[..];
+        let response = CookieResponseRef::decode(&mut reader, ()).unwrap();
+        assert_eq!(response.key.namespace(), "test");
+        assert_eq!(response.key.path(), "token");
+        assert_eq!(response.payload, Some(&b"abc"[..]));
+        assert!(reader.is_empty());
+
+        let mut reader = &b"\x0atest:token\x00"[..];
+        let response = CookieResponseRef::decode(&mut reader, ()).unwrap();
+        assert_eq!(response.payload, None);
+    }
+
+    #[test]
+    fn decode_cookie_response_ref_rejects_truncated_payload() {
+        let mut reader = &b"\x0atest:token\x01\x03ab"[..];
+        let result = CookieResponseRef::decode(&mut reader, ());
+        assert_eq!(result, Err(ReadMinecraftError::UnexpectedEof));
+    }
+}
@@ -0,0 +1,3 @@
+//! The ID of every packet, generated from the vanilla `packets.json` report.
+
+include!(concat!(env!("OUT_DIR"), "/r770/packet_ids.rs"));
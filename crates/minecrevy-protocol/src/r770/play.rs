@@ -0,0 +1,836 @@
+//! The play state, which the client enters once it has sent
+//! [`AcknowledgeFinishConfiguration`](super::configuration::AcknowledgeFinishConfiguration).
+//!
+//! To spawn the player the server sends [`Login`], [`GameEvent`] with
+//! [`GameEventKind::StartWaitingForLevelChunks`], the chunks around the player
+//! with [`SetCenterChunk`] and [`ChunkDataAndUpdateLight`], and finally a
+//! [`SynchronizePlayerPosition`]. The client confirms the position with
+//! [`ConfirmTeleportation`], and sends [`PlayerLoaded`] once it has left the
+//! loading screen.
+//!
+//! Keep alives use the same [`ClientboundKeepAlive`] and
+//! [`ServerboundKeepAlive`] as configuration, under clientbound `0x26` and
+//! serverbound `0x1B`.
+
+use core::convert::Infallible;
+
+use embedded_byteorder::{
+    AsyncWrite, AsyncWriteBytesExt, BigEndian, ReadBytesExt, Write, WriteBytesExt,
+};
+use minecrevy_encdec::{
+    AsyncDecode, AsyncEncode, AsyncWriteMinecraftExt, BitSet, BorrowDecode, Decode, Encode,
+    FixedBitSet, Identifier, Position, ReadMinecraftError, ReadMinecraftExt, WireSize,
+    WriteArrayError, WriteMinecraftExt,
+    nbt::Tag,
+    options::{ArrayOptions, Framing},
+    var_i32_size,
+};
+
+use super::blocks::Block;
+pub use super::configuration::{ClientboundKeepAlive, ServerboundKeepAlive};
+
+/// The size of a block or sky light array, which holds a nibble for each
+/// block of a chunk section.
+pub const LIGHT_ARRAY_SIZE: usize = 2048;
+
+/// Clientbound `0x2B`: joins the player to the world, in the dimension the
+/// rest of the packets refer to.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Login<'a> {
+    /// The player's entity ID.
+    pub entity_id: i32,
+    pub is_hardcore: bool,
+    /// The names of every dimension on the server, such as
+    /// `minecraft:overworld`.
+    pub dimension_names: &'a [Identifier<'a>],
+    /// Unused by the client.
+    #[options(.varint = true)]
+    pub max_players: i32,
+    /// The render distance, in chunks.
+    #[options(.varint = true)]
+    pub view_distance: i32,
+    /// The distance, in chunks, in which entities are ticked.
+    #[options(.varint = true)]
+    pub simulation_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub do_limited_crafting: bool,
+    /// The ID of the dimension's type in `minecraft:dimension_type`.
+    #[options(.varint = true)]
+    pub dimension_type: i32,
+    /// The name of the dimension the player spawns in.
+    pub dimension_name: Identifier<'a>,
+    /// The first 8 bytes of the SHA-256 of the world seed, used for biome
+    /// noise.
+    pub hashed_seed: i64,
+    pub game_mode: GameMode,
+    /// Shown by the F3+F4 game mode switcher.
+    pub previous_game_mode: PreviousGameMode,
+    /// Whether the dimension is a debug world.
+    pub is_debug: bool,
+    /// Whether the dimension is a superflat world, which changes the horizon.
+    pub is_flat: bool,
+    /// Where the player last died, used by recovery compasses.
+    pub death_location: Option<DeathLocation<'a>>,
+    /// The number of ticks until the player can use a portal again.
+    #[options(.varint = true)]
+    pub portal_cooldown: i32,
+    #[options(.varint = true)]
+    pub sea_level: i32,
+    /// Whether the client must sign its chat messages.
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(AsyncEncode, Encode, WireSize, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+#[tag(u8)]
+pub enum GameMode {
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
+
+/// The game mode a player was in before their current one, if any.
+///
+/// Unlike an `Option<GameMode>`, which is prefixed with a boolean, it is a
+/// single byte that is `-1` if there is no previous game mode.
+#[derive(defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PreviousGameMode(pub Option<GameMode>);
+
+impl PreviousGameMode {
+    fn to_i8(self) -> i8 {
+        self.0.map_or(-1, |mode| mode as i8)
+    }
+}
+
+impl AsyncEncode for PreviousGameMode {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_i8(self.to_i8()).await
+    }
+}
+
+impl Encode for PreviousGameMode {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        writer.write_i8(self.to_i8())
+    }
+}
+
+impl WireSize for PreviousGameMode {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        1
+    }
+}
+
+/// The dimension and block a player died in.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DeathLocation<'a> {
+    pub dimension: Identifier<'a>,
+    pub position: Position,
+}
+
+/// Clientbound `0x22`: a change to the game state, such as the weather.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct GameEvent {
+    pub event: GameEventKind,
+    /// The meaning depends on the event, and is usually `0.0`.
+    pub value: f32,
+}
+
+#[derive(AsyncEncode, Encode, WireSize, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+#[tag(u8)]
+pub enum GameEventKind {
+    NoRespawnBlockAvailable = 0,
+    BeginRaining = 1,
+    EndRaining = 2,
+    /// The value is the new [`GameMode`].
+    ChangeGameMode = 3,
+    /// The value is `1.0` to show the credits, or `0.0` to respawn right away.
+    WinGame = 4,
+    DemoEvent = 5,
+    ArrowHitPlayer = 6,
+    RainLevelChange = 7,
+    ThunderLevelChange = 8,
+    PufferfishSting = 9,
+    ElderGuardianAppearance = 10,
+    /// The value is `1.0` to respawn right away, or `0.0` to show the
+    /// respawn screen.
+    EnableRespawnScreen = 11,
+    LimitedCrafting = 12,
+    /// Makes the client wait on the loading screen until the chunk it is in
+    /// has arrived.
+    StartWaitingForLevelChunks = 13,
+}
+
+/// Clientbound `0x27`: a chunk column with its light.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct ChunkDataAndUpdateLight<'a> {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub heightmaps: &'a [Heightmap<'a>],
+    pub sections: ChunkSections<'a>,
+    pub block_entities: &'a [BlockEntity<'a>],
+    pub light: LightData<'a>,
+}
+
+/// The height of the highest block of a kind in each column of a chunk.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Heightmap<'a> {
+    pub kind: HeightmapKind,
+    /// The heights, packed into longs with just enough bits to hold the
+    /// dimension's height plus one.
+    pub data: &'a [i64],
+}
+
+/// The heightmaps the client uses, sent with their IDs.
+#[derive(AsyncEncode, Encode, WireSize, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeightmapKind {
+    /// The highest non-air block.
+    WorldSurface = 1,
+    /// The highest block that blocks motion or holds a fluid.
+    MotionBlocking = 4,
+    /// Like [`HeightmapKind::MotionBlocking`], but ignoring leaves.
+    MotionBlockingNoLeaves = 5,
+}
+
+/// The sections of a chunk column, from the bottom of the dimension to its
+/// top.
+///
+/// They are prefixed with their size in bytes as a VarInt, rather than with
+/// their number, which the client knows from the dimension's height.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkSections<'a>(pub &'a [ChunkSection<'a>]);
+
+impl ChunkSections<'_> {
+    const OPTIONS: ArrayOptions<()> = ArrayOptions {
+        framing: Framing::Fixed,
+        element: (),
+    };
+
+    /// The size of the sections, which they are prefixed with.
+    fn size(&self) -> Option<i32> {
+        i32::try_from(self.0.wire_size(Self::OPTIONS)).ok()
+    }
+}
+
+impl<'a> AsyncEncode for ChunkSections<'a> {
+    type Options = ();
+    type Error<E> = <[ChunkSection<'a>] as AsyncEncode>::Error<E>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let size = self.size().ok_or(WriteArrayError::LengthExceeded)?;
+        writer
+            .write_var_i32(size)
+            .await
+            .map_err(WriteArrayError::Length)?;
+        AsyncEncode::encode(self.0, writer, Self::OPTIONS).await
+    }
+}
+
+impl<'a> Encode for ChunkSections<'a> {
+    type Options = ();
+    type Error<E> = <[ChunkSection<'a>] as Encode>::Error<E>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let size = self.size().ok_or(WriteArrayError::LengthExceeded)?;
+        writer
+            .write_var_i32(size)
+            .map_err(WriteArrayError::Length)?;
+        Encode::encode(self.0, writer, Self::OPTIONS)
+    }
+}
+
+impl WireSize for ChunkSections<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        let size = self.0.wire_size(Self::OPTIONS);
+        var_i32_size(i32::try_from(size).unwrap()) + size
+    }
+}
+
+/// A 16×16×16 section of a chunk.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChunkSection<'a> {
+    /// The number of blocks that are not air, which the client uses to skip
+    /// empty sections.
+    pub block_count: i16,
+    /// The block state of each of the 4096 blocks.
+    pub block_states: PalettedContainer<'a>,
+    /// The biome of each of the 64 4×4×4 cells.
+    pub biomes: PalettedContainer<'a>,
+}
+
+impl ChunkSection<'_> {
+    /// A section of air in the given biome.
+    pub const fn empty(biome: i32) -> Self {
+        Self {
+            block_count: 0,
+            block_states: PalettedContainer::Single(Block::Air.default_state_id()),
+            biomes: PalettedContainer::Single(biome),
+        }
+    }
+}
+
+/// The block states or biomes of a section, which are IDs in
+/// `minecraft:block` states or `minecraft:worldgen/biome`.
+///
+/// The data is packed into longs with `bits` bits per entry, without entries
+/// spanning two longs. Its length follows from `bits`, so it is sent without
+/// a length prefix.
+///
+/// The container starts with `bits`, which is `0` for [`Self::Single`], so
+/// unlike other enums it has no tag of its own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PalettedContainer<'a> {
+    /// Every entry is the same ID.
+    Single(i32),
+    /// The entries are indices into `palette`.
+    Indirect {
+        bits: u8,
+        palette: &'a [i32],
+        data: &'a [i64],
+    },
+    /// The entries are the IDs themselves.
+    Direct { bits: u8, data: &'a [i64] },
+}
+
+impl AsyncEncode for PalettedContainer<'_> {
+    type Options = ();
+    type Error<E> = E;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        match *self {
+            Self::Single(id) => {
+                writer.write_u8(0).await?;
+                writer.write_var_i32(id).await
+            }
+            Self::Indirect {
+                bits,
+                palette,
+                data,
+            } => {
+                writer.write_u8(bits).await?;
+                writer.write_var_i32(palette_len(palette)).await?;
+                for &id in palette {
+                    writer.write_var_i32(id).await?;
+                }
+                for &long in data {
+                    writer.write_i64::<BigEndian>(long).await?;
+                }
+                Ok(())
+            }
+            Self::Direct { bits, data } => {
+                writer.write_u8(bits).await?;
+                for &long in data {
+                    writer.write_i64::<BigEndian>(long).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Encode for PalettedContainer<'_> {
+    type Options = ();
+    type Error<E> = E;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        match *self {
+            Self::Single(id) => {
+                writer.write_u8(0)?;
+                writer.write_var_i32(id)
+            }
+            Self::Indirect {
+                bits,
+                palette,
+                data,
+            } => {
+                writer.write_u8(bits)?;
+                writer.write_var_i32(palette_len(palette))?;
+                for &id in palette {
+                    writer.write_var_i32(id)?;
+                }
+                for &long in data {
+                    writer.write_i64::<BigEndian>(long)?;
+                }
+                Ok(())
+            }
+            Self::Direct { bits, data } => {
+                writer.write_u8(bits)?;
+                for &long in data {
+                    writer.write_i64::<BigEndian>(long)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl WireSize for PalettedContainer<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        match *self {
+            Self::Single(id) => 1 + var_i32_size(id),
+            Self::Indirect { palette, data, .. } => {
+                1 + var_i32_size(palette_len(palette))
+                    + palette.iter().map(|&id| var_i32_size(id)).sum::<usize>()
+                    + 8 * data.len()
+            }
+            Self::Direct { data, .. } => 1 + 8 * data.len(),
+        }
+    }
+}
+
+/// The length of a palette, which is at most 256 entries.
+fn palette_len(palette: &[i32]) -> i32 {
+    i32::try_from(palette.len()).unwrap()
+}
+
+/// A block entity in a [`ChunkDataAndUpdateLight`], such as a chest or a
+/// sign.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct BlockEntity<'a> {
+    /// The X and Z of the block within the chunk, packed as `x << 4 | z`.
+    pub packed_xz: u8,
+    pub y: i16,
+    /// The ID of the block entity's type in `minecraft:block_entity_type`.
+    #[options(.varint = true)]
+    pub kind: i32,
+    /// The block entity's data, without its position and ID.
+    pub data: Tag<'a>,
+}
+
+/// The sections of a chunk column that a [`LightData`] mask holds a bit for,
+/// enough for the tallest dimension vanilla allows.
+pub type LightMask = BitSet<4>;
+
+/// The light of a chunk column, which has a section more than the dimension
+/// at either end.
+///
+/// Bit `n` of a mask stands for section `n`, counting from the one below the
+/// dimension.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LightData<'a> {
+    /// The sections whose sky light is in `sky_light`.
+    pub sky_light_mask: LightMask,
+    /// The sections whose block light is in `block_light`.
+    pub block_light_mask: LightMask,
+    /// The sections whose sky light is all zero.
+    pub empty_sky_light_mask: LightMask,
+    /// The sections whose block light is all zero.
+    pub empty_block_light_mask: LightMask,
+    /// One array for each bit set in `sky_light_mask`, in order.
+    #[options(.element.framing = Framing::VarInt)]
+    pub sky_light: &'a [[u8; LIGHT_ARRAY_SIZE]],
+    /// One array for each bit set in `block_light_mask`, in order.
+    #[options(.element.framing = Framing::VarInt)]
+    pub block_light: &'a [[u8; LIGHT_ARRAY_SIZE]],
+}
+
+impl LightData<'_> {
+    /// No light at all, which the client treats as dark.
+    pub const EMPTY: Self = Self {
+        sky_light_mask: LightMask::new(),
+        block_light_mask: LightMask::new(),
+        empty_sky_light_mask: LightMask::new(),
+        empty_block_light_mask: LightMask::new(),
+        sky_light: &[],
+        block_light: &[],
+    };
+}
+
+/// Clientbound `0x41`: teleports the player, which it confirms with
+/// [`ConfirmTeleportation`].
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct SynchronizePlayerPosition {
+    /// Echoed back in [`ConfirmTeleportation`].
+    #[options(.varint = true)]
+    pub teleport_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub velocity_z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Which of the fields are relative to the player's current ones.
+    pub flags: TeleportFlags,
+}
+
+/// The fields of a [`SynchronizePlayerPosition`] that are added to the
+/// player's current ones rather than replacing them.
+#[derive(AsyncEncode, Encode, WireSize, defmt::Format, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TeleportFlags(pub i32);
+
+impl TeleportFlags {
+    /// Every field is absolute.
+    pub const ABSOLUTE: Self = Self(0);
+    pub const X: Self = Self(0x01);
+    pub const Y: Self = Self(0x02);
+    pub const Z: Self = Self(0x04);
+    pub const YAW: Self = Self(0x08);
+    pub const PITCH: Self = Self(0x10);
+    pub const VELOCITY_X: Self = Self(0x20);
+    pub const VELOCITY_Y: Self = Self(0x40);
+    pub const VELOCITY_Z: Self = Self(0x80);
+    /// Rotates the velocity by the change in rotation.
+    pub const ROTATE_VELOCITY: Self = Self(0x100);
+
+    /// The flags that are set in either `self` or `other`.
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// Clientbound `0x57`: the chunk the player is in, around which the client
+/// keeps chunks loaded.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SetCenterChunk {
+    #[options(.varint = true)]
+    pub chunk_x: i32,
+    #[options(.varint = true)]
+    pub chunk_z: i32,
+}
+
+/// Clientbound `0x5A`: where compasses point, and where the client spawns
+/// while it waits for the world to load.
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Debug)]
+pub struct SetDefaultSpawnPosition {
+    pub location: Position,
+    /// The yaw the player faces after respawning.
+    pub angle: f32,
+}
+
+/// Serverbound `0x00`: confirms a [`SynchronizePlayerPosition`]. Until it is
+/// sent, the client's movement packets are ignored.
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConfirmTeleportation {
+    #[options(.varint = true)]
+    pub teleport_id: i32,
+}
+
+/// The size of a chat message signature.
+pub const SIGNATURE_SIZE: usize = 256;
+
+/// Serverbound `0x07`: a chat message the player sent, which points into the
+/// packet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChatMessage<'a> {
+    /// At most 256 characters.
+    pub message: &'a str,
+    /// When the message was sent, in milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub salt: i64,
+    /// The signature of the message, if the player has a chat session.
+    pub signature: Option<&'a [u8; SIGNATURE_SIZE]>,
+    /// The number of messages the client has seen since it last said so.
+    pub message_count: i32,
+    /// Which of the last 20 messages the client has seen.
+    pub acknowledged: FixedBitSet<3>,
+    /// A checksum of the acknowledged messages' signatures.
+    pub checksum: u8,
+}
+
+impl<'a> BorrowDecode<'a> for ChatMessage<'a> {
+    type Options = ();
+    type Error = ReadMinecraftError<Infallible>;
+
+    fn decode(reader: &mut &'a [u8], (): Self::Options) -> Result<Self, Self::Error> {
+        Ok(Self {
+            message: <&str>::decode(reader, ())?,
+            timestamp: reader.read_i64::<BigEndian>()?,
+            salt: reader.read_i64::<BigEndian>()?,
+            signature: match reader.read_u8()? {
+                0 => None,
+                _ => Some(<&[u8; SIGNATURE_SIZE]>::decode(reader, ())?),
+            },
+            message_count: reader.read_var_i32()?,
+            acknowledged: Decode::decode(reader, ())?,
+            checksum: reader.read_u8()?,
+        })
+    }
+}
+
+/// Serverbound `0x2B`: the client has closed the loading screen, after which
+/// the player can be hurt.
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerLoaded;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::test_util::encode;
+
+    /// A chat message up to its signature.
+    const CHAT_MESSAGE_HEAD: &[u8] = &[
+        0x02, b'h', b'i', // message
+        0x00, 0x00, 0x01, 0x99, 0xef, 0x6a, 0x40, 0x00, // timestamp
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, // salt
+    ];
+    /// A chat message after its signature.
+    const CHAT_MESSAGE_TAIL: &[u8] = &[
+        0x03, // message count
+        0x05, 0x00, 0x08, // acknowledged
+        0x7f, // checksum
+    ];
+
+    #[test]
+    fn decode_chat_message() {
+        let mut packet = heapless::Vec::<u8, 512>::new();
+        packet.extend_from_slice(CHAT_MESSAGE_HEAD).unwrap();
+        packet.push(0x01).unwrap();
+        packet.extend_from_slice(&[0x42; SIGNATURE_SIZE]).unwrap();
+        packet.extend_from_slice(CHAT_MESSAGE_TAIL).unwrap();
+
+        let mut reader = &packet[..];
+        let message = ChatMessage::decode(&mut reader, ()).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(message.message, "hi");
+        assert_eq!(message.timestamp, 0x0199_ef6a_4000);
+        assert_eq!(message.salt, -2);
+        assert_eq!(message.signature, Some(&[0x42; SIGNATURE_SIZE]));
+        assert_eq!(message.message_count, 3);
+        assert_eq!(
+            message
+                .acknowledged
+                .ones()
+                .collect::<heapless::Vec<_, 20>>(),
+            [0, 2, 19]
+        );
+        assert_eq!(message.checksum, 0x7f);
+    }
+
+    #[test]
+    fn decode_unsigned_chat_message() {
+        let mut packet = heapless::Vec::<u8, 64>::new();
+        packet.extend_from_slice(CHAT_MESSAGE_HEAD).unwrap();
+        packet.push(0x00).unwrap();
+        packet.extend_from_slice(CHAT_MESSAGE_TAIL).unwrap();
+
+        let message = ChatMessage::decode(&mut &packet[..], ()).unwrap();
+        assert_eq!(message.message, "hi");
+        assert_eq!(message.signature, None);
+        assert_eq!(message.message_count, 3);
+    }
+
+    #[test]
+    fn decode_chat_message_rejects_truncated_signature() {
+        let mut packet = heapless::Vec::<u8, 64>::new();
+        packet.extend_from_slice(CHAT_MESSAGE_HEAD).unwrap();
+        packet.extend_from_slice(&[0x01, 0x42, 0x42]).unwrap();
+
+        let result = ChatMessage::decode(&mut &packet[..], ());
+        assert_eq!(result, Err(ReadMinecraftError::UnexpectedEof));
+    }
+
+    const DIMENSION: Identifier<'static> = Identifier::minecraft("a");
+
+    fn login(
+        previous_game_mode: PreviousGameMode,
+        death_location: Option<DeathLocation<'static>>,
+    ) -> Login<'static> {
+        Login {
+            entity_id: 7,
+            is_hardcore: true,
+            dimension_names: &[DIMENSION],
+            max_players: 20,
+            view_distance: 10,
+            simulation_distance: 300,
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            do_limited_crafting: false,
+            dimension_type: 1,
+            dimension_name: DIMENSION,
+            hashed_seed: -2,
+            game_mode: GameMode::Creative,
+            previous_game_mode,
+            is_debug: false,
+            is_flat: true,
+            death_location,
+            portal_cooldown: 0,
+            sea_level: 63,
+            enforces_secure_chat: false,
+        }
+    }
+
+    /// The fields of [`login`] up to the game mode.
+    const LOGIN_HEAD: &[u8] = &[
+        0, 0, 0, 7, // entity ID
+        1, // hardcore
+        1, 11, b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', b':', b'a', // dimensions
+        20, 10, 0xAC, 0x02, // max players, view and simulation distance
+        0, 1, 0, // debug info, respawn screen, limited crafting
+        1, // dimension type
+        11, b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', b':', b'a', // dimension
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, // hashed seed
+        1,    // game mode
+    ];
+
+    #[test]
+    fn encode_login_without_previous_game_mode() {
+        let death_location = DeathLocation {
+            dimension: DIMENSION,
+            position: Position::new(1, 2, 3),
+        };
+        let expected = [
+            LOGIN_HEAD,
+            &[
+                0xFF, // no previous game mode
+                0, 1, // debug, flat
+                1, 11, b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', b':', b'a', //
+                0, 0, 0, 0x40, 0, 0, 0x30, 0x02, // death location
+                0, 63, 0, // portal cooldown, sea level, secure chat
+            ],
+        ]
+        .concat();
+        let packet = login(PreviousGameMode(None), Some(death_location));
+        assert_eq!(encode(&packet), expected);
+    }
+
+    #[test]
+    fn encode_login_with_previous_game_mode() {
+        let expected = [
+            LOGIN_HEAD,
+            &[
+                3, // previous game mode
+                0, 1, // debug, flat
+                0, // no death location
+                0, 63, 0, // portal cooldown, sea level, secure chat
+            ],
+        ]
+        .concat();
+        let packet = login(PreviousGameMode(Some(GameMode::Spectator)), None);
+        assert_eq!(encode(&packet), expected);
+    }
+
+    #[test]
+    fn encode_empty_chunk() {
+        let sections = [ChunkSection::empty(5); 2];
+        let chunk = ChunkDataAndUpdateLight {
+            chunk_x: 1,
+            chunk_z: -1,
+            heightmaps: &[],
+            sections: ChunkSections(&sections),
+            block_entities: &[],
+            light: LightData::EMPTY,
+        };
+        let expected: &[u8] = &[
+            0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, // chunk X and Z
+            0,    // heightmaps
+            12,   // size of the sections
+            0, 0, 0, 0, 0, 5, // block count, air, biome
+            0, 0, 0, 0, 0, 5, //
+            0, // block entities
+            0, 0, 0, 0, // light masks
+            0, 0, // sky and block light
+        ];
+        assert_eq!(encode(&chunk), expected);
+    }
+
+    #[test]
+    fn encode_chunk_without_data_array_lengths() {
+        let block_states = [0x1111_1111_1111_1111; 256];
+        let sections = [ChunkSection {
+            block_count: 4096,
+            block_states: PalettedContainer::Indirect {
+                bits: 4,
+                palette: &[0, 300],
+                data: &block_states,
+            },
+            biomes: PalettedContainer::Direct {
+                bits: 7,
+                data: &[-1; 7],
+            },
+        }];
+        let light = [[0xAB; LIGHT_ARRAY_SIZE]];
+        let chunk = ChunkDataAndUpdateLight {
+            chunk_x: 0,
+            chunk_z: 0,
+            heightmaps: &[Heightmap {
+                kind: HeightmapKind::MotionBlocking,
+                data: &[9],
+            }],
+            sections: ChunkSections(&sections),
+            block_entities: &[BlockEntity {
+                packed_xz: 0x1F,
+                y: -64,
+                kind: 7,
+                data: Tag::Compound(&[]),
+            }],
+            light: LightData {
+                sky_light_mask: LightMask::from_iter([1]),
+                sky_light: &light,
+                ..LightData::EMPTY
+            },
+        };
+        let bytes = encode(&chunk);
+
+        let (head, rest) = bytes.split_at(19);
+        assert_eq!(
+            head,
+            [
+                0, 0, 0, 0, 0, 0, 0, 0, // chunk X and Z
+                1, 4, 1, 0, 0, 0, 0, 0, 0, 0, 9, // heightmap
+            ]
+        );
+
+        // The longs of either container follow straight after its palette.
+        let section_size = 2 + (1 + 1 + 1 + 2 + 256 * 8) + (1 + 7 * 8);
+        assert_eq!(section_size, 0x840);
+        assert_eq!(rest[..2], [0xC0, 0x10]);
+        let (section, rest) = rest[2..].split_at(section_size);
+        assert_eq!(section[..7], [0x10, 0x00, 4, 2, 0, 0xAC, 0x02]);
+        assert!(section[7..7 + 256 * 8].iter().all(|&byte| byte == 0x11));
+        assert_eq!(section[7 + 256 * 8], 7);
+        assert!(section[7 + 256 * 8 + 1..].iter().all(|&byte| byte == 0xFF));
+
+        let (block_entities, light) = rest.split_at(7);
+        assert_eq!(block_entities, [1, 0x1F, 0xFF, 0xC0, 7, 10, 0]);
+        // The sky light mask, the other masks, then the one sky light array.
+        assert_eq!(light[..9], [1, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(light[9..15], [0, 0, 0, 1, 0x80, 0x10]);
+        let (sky_light, block_light) = light[15..].split_at(LIGHT_ARRAY_SIZE);
+        assert!(sky_light.iter().all(|&byte| byte == 0xAB));
+        assert_eq!(block_light, [0]);
+    }
+}
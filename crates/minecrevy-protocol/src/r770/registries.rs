@@ -0,0 +1,471 @@
+//! Vanilla registries built into the server, sent as [`RegistryData`] during
+//! configuration.
+//!
+//! Only the registries and entries a client needs to join are included, with
+//! the values of vanilla 1.21.5. Everything is a `static` NBT tree, so it is
+//! encoded straight from flash.
+//!
+//! All of the entries come from [`CORE_PACK`]. A client that has it, which
+//! every vanilla client does, loads them from its own copy of the pack, so
+//! only their IDs need to be sent.
+//!
+//! The registry IDs of items and entity types are generated from the vanilla
+//! `registries.json` report.
+
+use minecrevy_encdec::{
+    Identifier,
+    nbt::{List, Tag},
+};
+
+use super::configuration::{KnownPack, RegistryData, RegistryEntry};
+
+/// The vanilla data pack that every 1.21.5 client has built in.
+pub const CORE_PACK: KnownPack<'static> = KnownPack {
+    namespace: "minecraft",
+    id: "core",
+    version: "1.21.5",
+};
+
+/// A registry whose entries are built into the server.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BuiltinRegistry {
+    pub id: Identifier<'static>,
+    /// The entries with their data.
+    pub entries: &'static [RegistryEntry<'static>],
+    /// The same entries without their data, for clients that have
+    /// [`CORE_PACK`].
+    pub ids: &'static [RegistryEntry<'static>],
+}
+
+impl BuiltinRegistry {
+    /// The packet that sends this registry, leaving the entries' data out if
+    /// the client has [`CORE_PACK`].
+    pub const fn registry_data(&self, has_core_pack: bool) -> RegistryData<'static> {
+        RegistryData {
+            registry: self.id,
+            entries: if has_core_pack {
+                self.ids
+            } else {
+                self.entries
+            },
+        }
+    }
+}
+
+/// Builds a [`BuiltinRegistry`] from its entries and their data.
+macro_rules! registry {
+    ($id:literal { $($entry:literal => $data:expr),* $(,)? }) => {
+        BuiltinRegistry {
+            id: identifier($id),
+            entries: &[$(RegistryEntry { id: identifier($entry), data: Some($data) }),*],
+            ids: &[$(RegistryEntry { id: identifier($entry), data: None }),*],
+        }
+    };
+}
+
+/// Parses an identifier in a constant, so that a typo fails the build.
+const fn identifier(identifier: &'static str) -> Identifier<'static> {
+    match Identifier::parse(identifier) {
+        Ok(identifier) => identifier,
+        Err(_) => panic!("invalid identifier"),
+    }
+}
+
+/// The registries the client requires, in the order they are sent.
+pub static REGISTRIES: &[BuiltinRegistry] = &[
+    DIMENSION_TYPES,
+    BIOMES,
+    DAMAGE_TYPES,
+    CHAT_TYPES,
+    PAINTING_VARIANTS,
+    WOLF_VARIANTS,
+    WOLF_SOUND_VARIANTS,
+    CAT_VARIANTS,
+    CHICKEN_VARIANTS,
+    COW_VARIANTS,
+    FROG_VARIANTS,
+    PIG_VARIANTS,
+];
+
+const TRUE: Tag<'static> = Tag::Byte(1);
+const FALSE: Tag<'static> = Tag::Byte(0);
+
+pub const DIMENSION_TYPES: BuiltinRegistry = registry!("minecraft:dimension_type" {
+    "minecraft:overworld" => Tag::Compound(&[
+        ("has_skylight", TRUE),
+        ("has_ceiling", FALSE),
+        ("ultrawarm", FALSE),
+        ("natural", TRUE),
+        ("coordinate_scale", Tag::Double(1.0)),
+        ("bed_works", TRUE),
+        ("respawn_anchor_works", FALSE),
+        ("min_y", Tag::Int(-64)),
+        ("height", Tag::Int(384)),
+        ("logical_height", Tag::Int(384)),
+        ("infiniburn", Tag::String("#minecraft:infiniburn_overworld")),
+        ("effects", Tag::String("minecraft:overworld")),
+        ("ambient_light", Tag::Float(0.0)),
+        ("piglin_safe", FALSE),
+        ("has_raids", TRUE),
+        ("monster_spawn_light_level", Tag::Compound(&[
+            ("type", Tag::String("minecraft:uniform")),
+            ("min_inclusive", Tag::Int(0)),
+            ("max_inclusive", Tag::Int(7)),
+        ])),
+        ("monster_spawn_block_light_limit", Tag::Int(0)),
+    ]),
+});
+
+pub const BIOMES: BuiltinRegistry = registry!("minecraft:worldgen/biome" {
+    "minecraft:plains" => Tag::Compound(&[
+        ("has_precipitation", TRUE),
+        ("temperature", Tag::Float(0.8)),
+        ("downfall", Tag::Float(0.4)),
+        ("effects", Tag::Compound(&[
+            ("fog_color", Tag::Int(12638463)),
+            ("sky_color", Tag::Int(7907327)),
+            ("water_color", Tag::Int(4159204)),
+            ("water_fog_color", Tag::Int(329011)),
+            ("mood_sound", Tag::Compound(&[
+                ("sound", Tag::String("minecraft:ambient.cave")),
+                ("tick_delay", Tag::Int(6000)),
+                ("block_search_extent", Tag::Int(8)),
+                ("offset", Tag::Double(2.0)),
+            ])),
+        ])),
+    ]),
+});
+
+/// Builds a damage type, which is scaled with difficulty when a mob deals it
+/// unless another `scaling` is given.
+macro_rules! damage_type {
+    (
+        $message_id:literal,
+        scaling: $scaling:literal,
+        $exhaustion:literal
+        $(, $key:literal => $value:literal)*
+    ) => {
+        Tag::Compound(&[
+            ("message_id", Tag::String($message_id)),
+            ("scaling", Tag::String($scaling)),
+            ("exhaustion", Tag::Float($exhaustion)),
+            $(($key, Tag::String($value)),)*
+        ])
+    };
+    ($message_id:literal, $exhaustion:literal $(, $key:literal => $value:literal)*) => {
+        damage_type!(
+            $message_id,
+            scaling: "when_caused_by_living_non_player",
+            $exhaustion
+            $(, $key => $value)*
+        )
+    };
+}
+
+/// The client looks every one of these up when it joins.
+pub const DAMAGE_TYPES: BuiltinRegistry = registry!("minecraft:damage_type" {
+    "minecraft:arrow" => damage_type!("arrow", 0.1),
+    "minecraft:bad_respawn_point" => damage_type!(
+        "badRespawnPoint",
+        scaling: "always",
+        0.1,
+        "death_message_type" => "intentional_game_design"
+    ),
+    "minecraft:cactus" => damage_type!("cactus", 0.1),
+    "minecraft:campfire" => damage_type!("inFire", 0.1, "effects" => "burning"),
+    "minecraft:cramming" => damage_type!("cramming", 0.0),
+    "minecraft:dragon_breath" => damage_type!("dragonBreath", 0.0),
+    "minecraft:drown" => damage_type!("drown", 0.0, "effects" => "drowning"),
+    "minecraft:dry_out" => damage_type!("dryout", 0.1),
+    "minecraft:ender_pearl" => damage_type!("fall", 0.0, "death_message_type" => "fall_variants"),
+    "minecraft:explosion" => damage_type!("explosion", scaling: "always", 0.1),
+    "minecraft:fall" => damage_type!("fall", 0.0, "death_message_type" => "fall_variants"),
+    "minecraft:falling_anvil" => damage_type!("anvil", 0.1),
+    "minecraft:falling_block" => damage_type!("fallingBlock", 0.1),
+    "minecraft:falling_stalactite" => damage_type!("fallingStalactite", 0.1),
+    "minecraft:fireball" => damage_type!("fireball", 0.1, "effects" => "burning"),
+    "minecraft:fireworks" => damage_type!("fireworks", 0.1),
+    "minecraft:fly_into_wall" => damage_type!("flyIntoWall", 0.0),
+    "minecraft:freeze" => damage_type!("freeze", 0.0, "effects" => "freezing"),
+    "minecraft:generic" => damage_type!("generic", 0.0),
+    "minecraft:generic_kill" => damage_type!("genericKill", 0.0),
+    "minecraft:hot_floor" => damage_type!("hotFloor", 0.1, "effects" => "burning"),
+    "minecraft:in_fire" => damage_type!("inFire", 0.1, "effects" => "burning"),
+    "minecraft:in_wall" => damage_type!("inWall", 0.0),
+    "minecraft:indirect_magic" => damage_type!("indirectMagic", 0.0),
+    "minecraft:lava" => damage_type!("lava", 0.1, "effects" => "burning"),
+    "minecraft:lightning_bolt" => damage_type!("lightningBolt", 0.1),
+    "minecraft:mace_smash" => damage_type!("mace_smash", 0.1),
+    "minecraft:magic" => damage_type!("magic", 0.0),
+    "minecraft:mob_attack" => damage_type!("mob", 0.1),
+    "minecraft:mob_attack_no_aggro" => damage_type!("mob", 0.1),
+    "minecraft:mob_projectile" => damage_type!("mob", 0.1),
+    "minecraft:on_fire" => damage_type!("onFire", 0.0, "effects" => "burning"),
+    "minecraft:out_of_world" => damage_type!("outOfWorld", 0.0),
+    "minecraft:outside_border" => damage_type!("outsideBorder", 0.0),
+    "minecraft:player_attack" => damage_type!("player", 0.1),
+    "minecraft:player_explosion" => damage_type!("explosion.player", scaling: "always", 0.1),
+    "minecraft:sonic_boom" => damage_type!("sonic_boom", scaling: "always", 0.0),
+    "minecraft:spit" => damage_type!("mob", 0.1),
+    "minecraft:stalagmite" => damage_type!("stalagmite", 0.0),
+    "minecraft:starve" => damage_type!("starve", 0.0),
+    "minecraft:sting" => damage_type!("sting", 0.1),
+    "minecraft:sweet_berry_bush" => damage_type!("sweetBerryBush", 0.1, "effects" => "poking"),
+    "minecraft:thorns" => damage_type!("thorns", 0.1, "effects" => "thorns"),
+    "minecraft:thrown" => damage_type!("thrown", 0.1),
+    "minecraft:trident" => damage_type!("trident", 0.1),
+    "minecraft:unattributed_fireball" => damage_type!("onFire", 0.1, "effects" => "burning"),
+    "minecraft:wind_charge" => damage_type!("mob", 0.1),
+    "minecraft:wither" => damage_type!("wither", 0.0),
+    "minecraft:wither_skull" => damage_type!("witherSkull", 0.1),
+});
+
+pub const CHAT_TYPES: BuiltinRegistry = registry!("minecraft:chat_type" {
+    "minecraft:chat" => Tag::Compound(&[
+        ("chat", Tag::Compound(&[
+            ("translation_key", Tag::String("chat.type.text")),
+            ("parameters", Tag::List(List::String(&["sender", "content"]))),
+        ])),
+        ("narration", Tag::Compound(&[
+            ("translation_key", Tag::String("chat.type.text.narrate")),
+            ("parameters", Tag::List(List::String(&["sender", "content"]))),
+        ])),
+    ]),
+});
+
+pub const PAINTING_VARIANTS: BuiltinRegistry = registry!("minecraft:painting_variant" {
+    "minecraft:kebab" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:kebab")),
+        ("width", Tag::Int(1)),
+        ("height", Tag::Int(1)),
+    ]),
+});
+
+pub const WOLF_VARIANTS: BuiltinRegistry = registry!("minecraft:wolf_variant" {
+    "minecraft:pale" => Tag::Compound(&[
+        ("assets", Tag::Compound(&[
+            ("wild", Tag::String("minecraft:entity/wolf/wolf")),
+            ("tame", Tag::String("minecraft:entity/wolf/wolf_tame")),
+            ("angry", Tag::String("minecraft:entity/wolf/wolf_angry")),
+        ])),
+    ]),
+});
+
+pub const WOLF_SOUND_VARIANTS: BuiltinRegistry = registry!("minecraft:wolf_sound_variant" {
+    "minecraft:classic" => Tag::Compound(&[
+        ("ambient_sound", Tag::String("minecraft:entity.wolf.ambient")),
+        ("death_sound", Tag::String("minecraft:entity.wolf.death")),
+        ("growl_sound", Tag::String("minecraft:entity.wolf.growl")),
+        ("hurt_sound", Tag::String("minecraft:entity.wolf.hurt")),
+        ("pant_sound", Tag::String("minecraft:entity.wolf.pant")),
+        ("whine_sound", Tag::String("minecraft:entity.wolf.whine")),
+    ]),
+});
+
+pub const CAT_VARIANTS: BuiltinRegistry = registry!("minecraft:cat_variant" {
+    "minecraft:tabby" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:entity/cat/tabby")),
+    ]),
+});
+
+pub const CHICKEN_VARIANTS: BuiltinRegistry = registry!("minecraft:chicken_variant" {
+    "minecraft:temperate" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:entity/chicken/temperate_chicken")),
+    ]),
+});
+
+pub const COW_VARIANTS: BuiltinRegistry = registry!("minecraft:cow_variant" {
+    "minecraft:temperate" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:entity/cow/temperate_cow")),
+    ]),
+});
+
+pub const FROG_VARIANTS: BuiltinRegistry = registry!("minecraft:frog_variant" {
+    "minecraft:temperate" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:entity/frog/temperate_frog")),
+    ]),
+});
+
+pub const PIG_VARIANTS: BuiltinRegistry = registry!("minecraft:pig_variant" {
+    "minecraft:temperate" => Tag::Compound(&[
+        ("asset_id", Tag::String("minecraft:entity/pig/temperate_pig")),
+    ]),
+});
+
+include!(concat!(env!("OUT_DIR"), "/r770/registries.rs"));
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use minecrevy_encdec::WireSize;
+
+    use super::*;
+    use crate::test_util::encode;
+
+    /// Concatenates the pieces of an expected encoding.
+    fn bytes(pieces: &[&[u8]]) -> Vec<u8> {
+        pieces.concat()
+    }
+
+    #[test]
+    fn encode_dimension_types() {
+        let expected = bytes(&[
+            b"\x18minecraft:dimension_type\x01\x13minecraft:overworld\x01",
+            b"\x0a",
+            b"\x01\x00\x0chas_skylight\x01",
+            b"\x01\x00\x0bhas_ceiling\x00",
+            b"\x01\x00\x09ultrawarm\x00",
+            b"\x01\x00\x07natural\x01",
+            b"\x06\x00\x10coordinate_scale\x3f\xf0\x00\x00\x00\x00\x00\x00",
+            b"\x01\x00\x09bed_works\x01",
+            b"\x01\x00\x14respawn_anchor_works\x00",
+            b"\x03\x00\x05min_y\xff\xff\xff\xc0",
+            b"\x03\x00\x06height\x00\x00\x01\x80",
+            b"\x03\x00\x0elogical_height\x00\x00\x01\x80",
+            b"\x08\x00\x0ainfiniburn\x00\x1f#minecraft:infiniburn_overworld",
+            b"\x08\x00\x07effects\x00\x13minecraft:overworld",
+            b"\x05\x00\x0dambient_light\x00\x00\x00\x00",
+            b"\x01\x00\x0bpiglin_safe\x00",
+            b"\x01\x00\x09has_raids\x01",
+            b"\x0a\x00\x19monster_spawn_light_level",
+            b"\x08\x00\x04type\x00\x11minecraft:uniform",
+            b"\x03\x00\x0dmin_inclusive\x00\x00\x00\x00",
+            b"\x03\x00\x0dmax_inclusive\x00\x00\x00\x07",
+            b"\x00",
+            b"\x03\x00\x1fmonster_spawn_block_light_limit\x00\x00\x00\x00",
+            b"\x00",
+        ]);
+        assert_eq!(encode(&DIMENSION_TYPES.registry_data(false)), expected);
+    }
+
+    #[test]
+    fn encode_biomes() {
+        let expected = bytes(&[
+            b"\x18minecraft:worldgen/biome\x01\x10minecraft:plains\x01",
+            b"\x0a",
+            b"\x01\x00\x11has_precipitation\x01",
+            b"\x05\x00\x0btemperature\x3f\x4c\xcc\xcd",
+            b"\x05\x00\x08downfall\x3e\xcc\xcc\xcd",
+            b"\x0a\x00\x07effects",
+            b"\x03\x00\x09fog_color\x00\xc0\xd8\xff",
+            b"\x03\x00\x09sky_color\x00\x78\xa7\xff",
+            b"\x03\x00\x0bwater_color\x00\x3f\x76\xe4",
+            b"\x03\x00\x0fwater_fog_color\x00\x05\x05\x33",
+            b"\x0a\x00\x0amood_sound",
+            b"\x08\x00\x05sound\x00\x16minecraft:ambient.cave",
+            b"\x03\x00\x0atick_delay\x00\x00\x17\x70",
+            b"\x03\x00\x13block_search_extent\x00\x00\x00\x08",
+            b"\x06\x00\x06offset\x40\x00\x00\x00\x00\x00\x00\x00",
+            b"\x00",
+            b"\x00",
+            b"\x00",
+        ]);
+        assert_eq!(encode(&BIOMES.registry_data(false)), expected);
+    }
+
+    #[test]
+    fn encode_chat_types() {
+        let parameters = b"\x09\x00\x0aparameters\x08\x00\x00\x00\x02\x00\x06sender\x00\x07content";
+        let expected = bytes(&[
+            b"\x13minecraft:chat_type\x01\x0eminecraft:chat\x01",
+            b"\x0a",
+            b"\x0a\x00\x04chat",
+            b"\x08\x00\x0ftranslation_key\x00\x0echat.type.text",
+            parameters,
+            b"\x00",
+            b"\x0a\x00\x09narration",
+            b"\x08\x00\x0ftranslation_key\x00\x16chat.type.text.narrate",
+            parameters,
+            b"\x00",
+            b"\x00",
+        ]);
+        assert_eq!(encode(&CHAT_TYPES.registry_data(false)), expected);
+    }
+
+    #[test]
+    fn encode_variants() {
+        let expected = bytes(&[
+            b"\x15minecraft:cat_variant\x01\x0fminecraft:tabby\x01",
+            b"\x0a",
+            b"\x08\x00\x08asset_id\x00\x1aminecraft:entity/cat/tabby",
+            b"\x00",
+        ]);
+        assert_eq!(encode(&CAT_VARIANTS.registry_data(false)), expected);
+
+        let expected = bytes(&[
+            b"\x1aminecraft:painting_variant\x01\x0fminecraft:kebab\x01",
+            b"\x0a",
+            b"\x08\x00\x08asset_id\x00\x0fminecraft:kebab",
+            b"\x03\x00\x05width\x00\x00\x00\x01",
+            b"\x03\x00\x06height\x00\x00\x00\x01",
+            b"\x00",
+        ]);
+        assert_eq!(encode(&PAINTING_VARIANTS.registry_data(false)), expected);
+    }
+
+    #[test]
+    fn encode_damage_types() {
+        let data = encode(&DAMAGE_TYPES.registry_data(false));
+        assert!(data.starts_with(b"\x15minecraft:damage_type\x31\x0fminecraft:arrow\x01"));
+
+        let entry = DAMAGE_TYPES
+            .entries
+            .iter()
+            .find(|entry| entry.id == Identifier::minecraft("bad_respawn_point"))
+            .unwrap();
+        let expected = bytes(&[
+            b"\x1bminecraft:bad_respawn_point\x01",
+            b"\x0a",
+            b"\x08\x00\x0amessage_id\x00\x0fbadRespawnPoint",
+            b"\x08\x00\x07scaling\x00\x06always",
+            b"\x05\x00\x0aexhaustion\x3d\xcc\xcc\xcd",
+            b"\x08\x00\x12death_message_type\x00\x17intentional_game_design",
+            b"\x00",
+        ]);
+        assert_eq!(encode(entry), expected);
+
+        // Mobs' damage scales with difficulty unless it says otherwise.
+        let entry = &DAMAGE_TYPES.entries[0];
+        let expected = bytes(&[
+            b"\x0fminecraft:arrow\x01",
+            b"\x0a",
+            b"\x08\x00\x0amessage_id\x00\x05arrow",
+            b"\x08\x00\x07scaling\x00\x20when_caused_by_living_non_player",
+            b"\x05\x00\x0aexhaustion\x3d\xcc\xcc\xcd",
+            b"\x00",
+        ]);
+        assert_eq!(encode(entry), expected);
+    }
+
+    #[test]
+    fn registry_data_with_core_pack_sends_only_ids() {
+        assert_eq!(
+            encode(&DIMENSION_TYPES.registry_data(true)),
+            b"\x18minecraft:dimension_type\x01\x13minecraft:overworld\x00"
+        );
+
+        for registry in REGISTRIES {
+            let ids = registry.registry_data(true);
+            assert_eq!(ids.registry, registry.id);
+            assert_eq!(ids.entries.len(), registry.entries.len());
+            for (id, entry) in ids.entries.iter().zip(registry.entries) {
+                assert_eq!(id.id, entry.id);
+                assert_eq!(id.data, None);
+                assert!(entry.data.is_some());
+            }
+
+            // The entries are the same apart from their data.
+            let full = encode(&registry.registry_data(false));
+            let ids = encode(&ids);
+            let data_size: usize = registry
+                .entries
+                .iter()
+                .map(|entry| entry.data.unwrap().wire_size(()))
+                .sum();
+            assert_eq!(ids.len() + data_size, full.len());
+        }
+    }
+}
@@ -1,108 +1,112 @@
-use core::convert::Infallible;
+use core::fmt;
 
-use embedded_byteorder::{
-    AsyncRead, AsyncReadBytesExt, AsyncWrite, AsyncWriteBytesExt, BigEndian, ReadExactError,
+use embedded_byteorder::{AsyncWrite, ErrorType, Write};
+use minecrevy_encdec::{
+    AsyncDecode, AsyncEncode, AsyncWriteMinecraftExt, Decode, Encode, WindowedError, WireSize,
+    write_windowed,
 };
-use minecrevy_encdec::{AsyncDecode, AsyncEncode, AsyncWriteMinecraftExt, WireSize, var_i32_size};
 use serde::{Serialize, ser::SerializeMap};
+use uuid::Uuid;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+use crate::{
+    json::{self, Json, JsonError},
+    text::Text,
+};
+
+#[derive(AsyncDecode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct StatusRequest;
 
-impl AsyncDecode for StatusRequest {
-    type Options = ();
-    type Error<E> = Infallible;
+#[derive(AsyncEncode, AsyncDecode, Encode, Decode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StatusPing(pub i64);
 
-    async fn decode<R: AsyncRead>(
-        _reader: &mut R,
-        (): Self::Options,
-    ) -> Result<Self, Self::Error<R::Error>> {
-        Ok(Self)
-    }
-}
+#[derive(AsyncEncode, Encode, WireSize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StatusResponseSimple<'a>(pub &'a str);
 
-#[derive(WireSize, Clone, Copy, PartialEq, Eq, Debug)]
-pub struct StatusPing(pub i64);
+/// The server list entry, sent as a JSON string.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse<'a> {
+    pub version: Version,
+    pub players: StatusResponsePlayers<'a>,
+    pub description: Text<'a>,
+    pub enforces_secure_chat: bool,
+    /// Tells clients with the No Chat Reports mod that chat is unsigned.
+    pub prevents_chat_reports: bool,
+    /// Serialized last, so that it can be streamed after everything else.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<Favicon<'a>>,
+}
 
-impl AsyncEncode for StatusPing {
+impl AsyncEncode for StatusResponse<'_> {
     type Options = ();
-    type Error<E> = E;
+    type Error<E> = JsonError<E>;
 
     async fn encode<W: AsyncWrite>(
         &self,
         writer: &mut W,
         (): Self::Options,
     ) -> Result<(), Self::Error<W::Error>> {
-        writer.write_i64::<BigEndian>(self.0).await?;
-        Ok(())
-    }
-}
+        let Some(favicon) = self.favicon else {
+            return AsyncEncode::encode(&Json(self), writer, ()).await;
+        };
 
-impl AsyncDecode for StatusPing {
-    type Options = ();
-    type Error<E> = ReadExactError<E>;
+        // The favicon takes several KB, which `write_windowed` would encode
+        // again for every window. So only the rest of the JSON is windowed,
+        // up to its closing brace, and the favicon is written straight after.
+        let rest = StatusResponse {
+            favicon: None,
+            ..self.clone()
+        };
+        let rest_len = json::size(&rest) - 1;
+        let len = i32::try_from(json::size(self)).map_err(|_| JsonError::LengthExceeded)?;
+        writer.write_var_i32(len).await?;
+        write_windowed(writer, |window| {
+            json::to_writer(
+                &mut Truncated {
+                    writer: window,
+                    remaining: rest_len,
+                },
+                &rest,
+            )
+        })
+        .await
+        .map_err(|e| match e {
+            WindowedError::Write(e) => JsonError::Other(e),
+            // A full window is handled by `write_windowed` and never surfaces here.
+            WindowedError::Encode(e) => e.map_other(|_| unreachable!()),
+        })?;
 
-    async fn decode<R: AsyncRead>(
-        reader: &mut R,
-        (): Self::Options,
-    ) -> Result<Self, Self::Error<R::Error>> {
-        Ok(Self(reader.read_i64::<BigEndian>().await?))
+        writer.write_all(b",\"favicon\":\"").await?;
+        writer.write_all(Favicon::PREFIX.as_bytes()).await?;
+        for (chunk, len) in favicon.base64_chunks() {
+            writer.write_all(&chunk[..len]).await?;
+        }
+        writer.write_all(b"\"}").await?;
+        Ok(())
     }
 }
 
-pub struct StatusResponseSimple<'a>(pub &'a str);
-
-impl AsyncEncode for StatusResponseSimple<'_> {
+impl Encode for StatusResponse<'_> {
     type Options = ();
-    type Error<E> = E;
+    type Error<E> = JsonError<E>;
 
-    async fn encode<W: AsyncWrite>(
+    fn encode<W: Write>(
         &self,
         writer: &mut W,
         (): Self::Options,
     ) -> Result<(), Self::Error<W::Error>> {
-        let len = i32::try_from(self.0.len()).unwrap();
-        writer.write_var_i32(len).await?;
-        writer.write_all(self.0.as_bytes()).await?;
-        Ok(())
+        Encode::encode(&Json(self), writer, ())
     }
 }
 
-impl WireSize for StatusResponseSimple<'_> {
+impl WireSize for StatusResponse<'_> {
     type Options = ();
 
     fn wire_size(&self, (): Self::Options) -> usize {
-        let mut size = 0;
-        size += var_i32_size(self.0.len() as i32);
-        size += self.0.len();
-        size
+        Json(self).wire_size(())
     }
 }
 
-#[derive(Serialize, Clone, PartialEq, Debug)]
-pub struct StatusResponse<'a> {
-    pub version: Version,
-    pub players: StatusResponsePlayers,
-    pub description: &'a str,
-    pub enforces_secure_chat: bool,
-}
-
-// TODO: write a serde_json_core::to_writer instead
-// impl AsyncEncode for StatusResponse<'_> {
-//     type Options<'a> = &'a mut [u8];
-//     type Error<E> = E;
-
-//     async fn encode<W: AsyncWrite>(
-//         &self,
-//         writer: &mut W,
-//         buf: Self::Options<'_>,
-//     ) -> Result<(), Self::Error<W::Error>> {
-//         let written = serde_json_core::to_slice(self, buf).unwrap();
-//         writer.write_all(&buf[..written]).await?;
-//         Ok(())
-//     }
-// }
-
 #[derive(Clone, PartialEq, Debug)]
 pub enum Version {
     V1_21_5,
@@ -135,7 +139,160 @@ impl Serialize for Version {
 }
 
 #[derive(Serialize, Clone, PartialEq, Debug)]
-pub struct StatusResponsePlayers {
+pub struct StatusResponsePlayers<'a> {
     pub max: i32,
     pub online: i32,
+    /// The players listed when hovering over the player count.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub sample: &'a [PlayerSample<'a>],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerSample<'a> {
+    pub name: &'a str,
+    pub id: Uuid,
+}
+
+impl Serialize for PlayerSample<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        struct Id(Uuid);
+
+        impl Serialize for Id {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_str(&self.0.hyphenated())
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("name", self.name)?;
+        map.serialize_entry("id", &Id(self.id))?;
+        map.end()
+    }
+}
+
+/// The server icon: a 64x64 PNG image, e.g. from `include_bytes!`.
+///
+/// It is sent as a `data:image/png;base64,` URL, which is encoded on the fly
+/// instead of being buffered.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Favicon<'a>(pub &'a [u8]);
+
+impl Favicon<'_> {
+    const PREFIX: &'static str = "data:image/png;base64,";
+
+    /// The image in base64, as chunks of up to 64 characters and their
+    /// lengths.
+    fn base64_chunks(&self) -> impl Iterator<Item = ([u8; 64], usize)> {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        self.0.chunks(48).map(|chunk| {
+            let mut buf = [0; 64];
+            let mut len = 0;
+            for group in chunk.chunks(3) {
+                let bits = group.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                    bits | (u32::from(byte) << (16 - 8 * i))
+                });
+                for i in 0..4 {
+                    buf[len + i] = match i <= group.len() {
+                        true => ALPHABET[((bits >> (18 - 6 * i)) & 0x3F) as usize],
+                        false => b'=',
+                    };
+                }
+                len += 4;
+            }
+            (buf, len)
+        })
+    }
+}
+
+impl fmt::Display for Favicon<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::PREFIX)?;
+        for (chunk, len) in self.base64_chunks() {
+            // Only ASCII was written.
+            f.write_str(core::str::from_utf8(&chunk[..len]).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Favicon<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// A blocking [`Write`] that drops everything past its first `remaining`
+/// bytes.
+struct Truncated<'w, W: ?Sized> {
+    writer: &'w mut W,
+    remaining: usize,
+}
+
+impl<W: Write + ?Sized> ErrorType for Truncated<'_, W> {
+    type Error = W::Error;
+}
+
+impl<W: Write + ?Sized> Write for Truncated<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.remaining);
+        self.writer.write_all(&buf[..n])?;
+        self.remaining -= n;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::encode;
+
+    fn response(favicon: Option<Favicon<'_>>) -> StatusResponse<'_> {
+        StatusResponse {
+            version: Version::V1_21_5,
+            players: StatusResponsePlayers {
+                max: 20,
+                online: 0,
+                sample: &[],
+            },
+            description: Text::literal("A Minecraft Server"),
+            enforces_secure_chat: false,
+            prevents_chat_reports: true,
+            favicon,
+        }
+    }
+
+    #[test]
+    fn encodes_without_favicon() {
+        let buf = encode(&response(None));
+        assert_eq!(
+            &buf[2..],
+            br#"{"version":{"name":"1.21.5","protocol":770},"players":{"max":20,"online":0},"description":{"text":"A Minecraft Server"},"enforcesSecureChat":false,"preventsChatReports":true}"#
+        );
+    }
+
+    #[test]
+    fn streams_favicon_last() {
+        let mut image = [0; 200];
+        for (i, byte) in image.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let buf = encode(&response(Some(Favicon(&image))));
+        assert!(buf[2..].starts_with(b"{\"version\""));
+        assert!(buf.ends_with(b"\"preventsChatReports\":true,\"favicon\":\"data:image/png;base64,AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4OTo7PD0+P0BBQkNERUZHSElKS0xNTk9QUVJTVFVWV1hZWltcXV5fYGFiY2RlZmdoaWprbG1ub3BxcnN0dXZ3eHl6e3x9fn+AgYKDhIWGh4iJiouMjY6PkJGSk5SVlpeYmZqbnJ2en6ChoqOkpaanqKmqq6ytrq+wsbKztLW2t7i5uru8vb6/wMHCw8TFxsc=\"}"));
+    }
 }
@@ -0,0 +1,50 @@
+//! Helpers shared by the tests.
+
+extern crate std;
+
+use core::{
+    fmt::Debug,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+use std::{vec, vec::Vec};
+
+use embedded_byteorder::SliceWriteError;
+use minecrevy_encdec::{AsyncEncode, Encode, WireSize};
+
+/// Runs a future that never waits on anything outside of memory.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Encodes `value` with both the blocking and the async encoder, checking
+/// that they write the same bytes and as many as its wire size says.
+pub fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: Encode<Options = ()> + AsyncEncode<Options = ()> + WireSize<Options = ()> + ?Sized,
+    <T as Encode>::Error<SliceWriteError>: Debug,
+    <T as AsyncEncode>::Error<SliceWriteError>: Debug,
+{
+    const MAX_LEN: usize = 1 << 16;
+
+    let mut blocking = vec![0; MAX_LEN];
+    let mut writer = &mut blocking[..];
+    Encode::encode(value, &mut writer, ()).unwrap();
+    let len = MAX_LEN - writer.len();
+    blocking.truncate(len);
+
+    let mut buf = vec![0; MAX_LEN];
+    let mut writer = &mut buf[..];
+    block_on(AsyncEncode::encode(value, &mut writer, ())).unwrap();
+    assert_eq!(MAX_LEN - writer.len(), len);
+    assert_eq!(buf[..len], blocking);
+
+    assert_eq!(value.wire_size(()), len);
+    blocking
+}
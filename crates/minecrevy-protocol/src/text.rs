@@ -0,0 +1,938 @@
+//! Text components, used for chat messages, disconnect reasons and the server
+//! list MOTD.
+//!
+//! A [`Text`] serializes to JSON through [`Serialize`] for the status response,
+//! and encodes to network NBT through [`AsyncEncode`] and [`Encode`] for
+//! packets in the configuration and play states. Both use the 1.21.5 format.
+
+use core::fmt;
+
+use embedded_byteorder::{AsyncWrite, Write};
+use minecrevy_encdec::{
+    AsyncEncode, Encode, SizeCounter, WindowedError, WireSize,
+    nbt::{CompoundWriter, NbtWriteError, Tag, TagType},
+    write_windowed,
+};
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use uuid::Uuid;
+
+/// A text component: some content, its style, and children that inherit the
+/// style.
+///
+/// Everything is borrowed and the builder methods are `const`, so components
+/// can be built in `const`s, e.g.
+/// `Text::literal("Hello, ").color(Color::Gold).extra(&[Text::literal("world!")])`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Text<'a> {
+    pub content: Content<'a>,
+    pub style: Style<'a>,
+    pub extra: &'a [Text<'a>],
+}
+
+impl<'a> Text<'a> {
+    pub const fn new(content: Content<'a>) -> Self {
+        Self {
+            content,
+            style: Style::EMPTY,
+            extra: &[],
+        }
+    }
+
+    /// Plain text.
+    pub const fn literal(text: &'a str) -> Self {
+        Self::new(Content::Literal(text))
+    }
+
+    /// Text looked up in the client's language file, with `with` substituted
+    /// for the `%s` placeholders.
+    pub const fn translatable(key: &'a str, with: &'a [Text<'a>]) -> Self {
+        Self::new(Content::Translatable {
+            key,
+            fallback: None,
+            with,
+        })
+    }
+
+    /// The name of the key bound to a control, e.g. `key.jump`.
+    pub const fn keybind(key: &'a str) -> Self {
+        Self::new(Content::Keybind(key))
+    }
+
+    /// The score of an entity in a scoreboard objective.
+    pub const fn score(name: &'a str, objective: &'a str) -> Self {
+        Self::new(Content::Score { name, objective })
+    }
+
+    pub const fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    /// Sets the shadow color as ARGB.
+    pub const fn shadow_color(mut self, argb: u32) -> Self {
+        self.style.shadow_color = Some(argb);
+        self
+    }
+
+    pub const fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub const fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub const fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub const fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub const fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub const fn font(mut self, font: &'a str) -> Self {
+        self.style.font = Some(font);
+        self
+    }
+
+    /// Sets the text inserted into the chat box when the text is shift-clicked.
+    pub const fn insertion(mut self, insertion: &'a str) -> Self {
+        self.style.insertion = Some(insertion);
+        self
+    }
+
+    pub const fn click_event(mut self, event: ClickEvent<'a>) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub const fn hover_event(mut self, event: HoverEvent<'a>) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    pub const fn extra(mut self, extra: &'a [Text<'a>]) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Writes the entries of this component into an NBT compound.
+    pub fn write_nbt<W: Write + ?Sized>(
+        &self,
+        nbt: &mut CompoundWriter<'_, W>,
+    ) -> Result<(), NbtWriteError<W::Error>> {
+        match self.content {
+            Content::Literal(text) => nbt.string("text", text)?,
+            Content::Translatable {
+                key,
+                fallback,
+                with,
+            } => {
+                nbt.string("translate", key)?;
+                if let Some(fallback) = fallback {
+                    nbt.string("fallback", fallback)?;
+                }
+                write_nbt_list(nbt, "with", with)?;
+            }
+            Content::Keybind(key) => nbt.string("keybind", key)?,
+            Content::Score { name, objective } => {
+                let mut score = nbt.compound("score")?;
+                score.string("name", name)?;
+                score.string("objective", objective)?;
+                score.end()?;
+            }
+        }
+        self.style.write_nbt(nbt)?;
+        write_nbt_list(nbt, "extra", self.extra)
+    }
+}
+
+fn write_nbt_list<W: Write + ?Sized>(
+    nbt: &mut CompoundWriter<'_, W>,
+    name: &str,
+    texts: &[Text<'_>],
+) -> Result<(), NbtWriteError<W::Error>> {
+    if texts.is_empty() {
+        return Ok(());
+    }
+    let mut list = nbt.list(name, TagType::Compound, texts.len())?;
+    for text in texts {
+        let mut compound = list.compound()?;
+        text.write_nbt(&mut compound)?;
+        compound.end()?;
+    }
+    list.end()
+}
+
+/// What a [`Text`] displays, before its children.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Content<'a> {
+    Literal(&'a str),
+    Translatable {
+        key: &'a str,
+        /// Shown instead of `key` if the client has no translation for it.
+        fallback: Option<&'a str>,
+        with: &'a [Text<'a>],
+    },
+    Keybind(&'a str),
+    Score {
+        /// A player name, an entity UUID or `*` for the viewing player.
+        name: &'a str,
+        objective: &'a str,
+    },
+}
+
+/// The formatting of a [`Text`]. Fields left as [`None`] are inherited from
+/// the parent component.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct Style<'a> {
+    pub color: Option<Color>,
+    /// The color of the text shadow as ARGB.
+    pub shadow_color: Option<u32>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub font: Option<&'a str>,
+    pub insertion: Option<&'a str>,
+    pub click_event: Option<ClickEvent<'a>>,
+    pub hover_event: Option<HoverEvent<'a>>,
+}
+
+impl Style<'_> {
+    /// A style that inherits everything from its parent.
+    pub const EMPTY: Self = Self {
+        color: None,
+        shadow_color: None,
+        bold: None,
+        italic: None,
+        underlined: None,
+        strikethrough: None,
+        obfuscated: None,
+        font: None,
+        insertion: None,
+        click_event: None,
+        hover_event: None,
+    };
+
+    fn flags(&self) -> [(&'static str, Option<bool>); 5] {
+        [
+            ("bold", self.bold),
+            ("italic", self.italic),
+            ("underlined", self.underlined),
+            ("strikethrough", self.strikethrough),
+            ("obfuscated", self.obfuscated),
+        ]
+    }
+
+    fn serialize_entries<M: SerializeMap>(&self, map: &mut M) -> Result<(), M::Error> {
+        if let Some(color) = self.color {
+            map.serialize_entry("color", &color)?;
+        }
+        if let Some(argb) = self.shadow_color {
+            map.serialize_entry("shadow_color", &(argb as i32))?;
+        }
+        for (name, flag) in self.flags() {
+            if let Some(flag) = flag {
+                map.serialize_entry(name, &flag)?;
+            }
+        }
+        if let Some(font) = self.font {
+            map.serialize_entry("font", font)?;
+        }
+        if let Some(insertion) = self.insertion {
+            map.serialize_entry("insertion", insertion)?;
+        }
+        if let Some(event) = &self.click_event {
+            map.serialize_entry("click_event", event)?;
+        }
+        if let Some(event) = &self.hover_event {
+            map.serialize_entry("hover_event", event)?;
+        }
+        Ok(())
+    }
+
+    fn write_nbt<W: Write + ?Sized>(
+        &self,
+        nbt: &mut CompoundWriter<'_, W>,
+    ) -> Result<(), NbtWriteError<W::Error>> {
+        if let Some(color) = self.color {
+            nbt.string("color", color.encode(&mut [0; 7]))?;
+        }
+        if let Some(argb) = self.shadow_color {
+            nbt.int("shadow_color", argb as i32)?;
+        }
+        for (name, flag) in self.flags() {
+            if let Some(flag) = flag {
+                nbt.bool(name, flag)?;
+            }
+        }
+        if let Some(font) = self.font {
+            nbt.string("font", font)?;
+        }
+        if let Some(insertion) = self.insertion {
+            nbt.string("insertion", insertion)?;
+        }
+        if let Some(event) = &self.click_event {
+            let mut compound = nbt.compound("click_event")?;
+            event.write_nbt(&mut compound)?;
+            compound.end()?;
+        }
+        if let Some(event) = &self.hover_event {
+            let mut compound = nbt.compound("hover_event")?;
+            event.write_nbt(&mut compound)?;
+            compound.end()?;
+        }
+        Ok(())
+    }
+}
+
+/// A text color: one of the sixteen named chat colors or any RGB color.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    /// A color given as `0xRRGGBB`.
+    Rgb(u32),
+}
+
+impl Color {
+    /// The name of a named color, or [`None`] for [`Color::Rgb`].
+    pub const fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Black => "black",
+            Self::DarkBlue => "dark_blue",
+            Self::DarkGreen => "dark_green",
+            Self::DarkAqua => "dark_aqua",
+            Self::DarkRed => "dark_red",
+            Self::DarkPurple => "dark_purple",
+            Self::Gold => "gold",
+            Self::Gray => "gray",
+            Self::DarkGray => "dark_gray",
+            Self::Blue => "blue",
+            Self::Green => "green",
+            Self::Aqua => "aqua",
+            Self::Red => "red",
+            Self::LightPurple => "light_purple",
+            Self::Yellow => "yellow",
+            Self::White => "white",
+            Self::Rgb(_) => return None,
+        })
+    }
+
+    /// Returns the name of the color, or formats it as `#rrggbb` into `buf`.
+    fn encode<'b>(&self, buf: &'b mut [u8; 7]) -> &'b str {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+
+        let Self::Rgb(rgb) = *self else {
+            return self.name().unwrap_or_default();
+        };
+        buf[0] = b'#';
+        for (i, digit) in buf[1..].iter_mut().enumerate() {
+            *digit = HEX[(rgb >> (20 - 4 * i) & 0xF) as usize];
+        }
+        // Only ASCII was written.
+        core::str::from_utf8(buf).unwrap()
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.encode(&mut [0; 7]))
+    }
+}
+
+/// What happens when a [`Text`] is clicked.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClickEvent<'a> {
+    OpenUrl(&'a str),
+    RunCommand(&'a str),
+    SuggestCommand(&'a str),
+    /// Turns to a page of the book the text is in.
+    ChangePage(i32),
+    CopyToClipboard(&'a str),
+}
+
+impl ClickEvent<'_> {
+    fn action(&self) -> &'static str {
+        match self {
+            Self::OpenUrl(_) => "open_url",
+            Self::RunCommand(_) => "run_command",
+            Self::SuggestCommand(_) => "suggest_command",
+            Self::ChangePage(_) => "change_page",
+            Self::CopyToClipboard(_) => "copy_to_clipboard",
+        }
+    }
+
+    fn write_nbt<W: Write + ?Sized>(
+        &self,
+        nbt: &mut CompoundWriter<'_, W>,
+    ) -> Result<(), NbtWriteError<W::Error>> {
+        nbt.string("action", self.action())?;
+        match *self {
+            Self::OpenUrl(url) => nbt.string("url", url),
+            Self::RunCommand(command) | Self::SuggestCommand(command) => {
+                nbt.string("command", command)
+            }
+            Self::ChangePage(page) => nbt.int("page", page),
+            Self::CopyToClipboard(value) => nbt.string("value", value),
+        }
+    }
+}
+
+impl Serialize for ClickEvent<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("action", self.action())?;
+        match self {
+            Self::OpenUrl(url) => map.serialize_entry("url", url)?,
+            Self::RunCommand(command) | Self::SuggestCommand(command) => {
+                map.serialize_entry("command", command)?
+            }
+            Self::ChangePage(page) => map.serialize_entry("page", page)?,
+            Self::CopyToClipboard(value) => map.serialize_entry("value", value)?,
+        }
+        map.end()
+    }
+}
+
+/// What is shown when hovering over a [`Text`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HoverEvent<'a> {
+    ShowText(&'a Text<'a>),
+    ShowItem {
+        /// The item's identifier, e.g. `minecraft:diamond`.
+        id: &'a str,
+        count: i32,
+    },
+    ShowEntity {
+        /// The entity type's identifier, e.g. `minecraft:pig`.
+        id: &'a str,
+        uuid: Uuid,
+        name: Option<&'a Text<'a>>,
+    },
+}
+
+impl HoverEvent<'_> {
+    fn action(&self) -> &'static str {
+        match self {
+            Self::ShowText(_) => "show_text",
+            Self::ShowItem { .. } => "show_item",
+            Self::ShowEntity { .. } => "show_entity",
+        }
+    }
+
+    fn write_nbt<W: Write + ?Sized>(
+        &self,
+        nbt: &mut CompoundWriter<'_, W>,
+    ) -> Result<(), NbtWriteError<W::Error>> {
+        nbt.string("action", self.action())?;
+        match *self {
+            Self::ShowText(text) => {
+                let mut value = nbt.compound("value")?;
+                text.write_nbt(&mut value)?;
+                value.end()
+            }
+            Self::ShowItem { id, count } => {
+                nbt.string("id", id)?;
+                nbt.int("count", count)
+            }
+            Self::ShowEntity { id, uuid, name } => {
+                nbt.string("id", id)?;
+                nbt.tag("uuid", &Tag::IntArray(&uuid_ints(uuid)))?;
+                if let Some(name) = name {
+                    let mut compound = nbt.compound("name")?;
+                    name.write_nbt(&mut compound)?;
+                    compound.end()?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Serialize for HoverEvent<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("action", self.action())?;
+        match self {
+            Self::ShowText(text) => map.serialize_entry("value", text)?,
+            Self::ShowItem { id, count } => {
+                map.serialize_entry("id", id)?;
+                map.serialize_entry("count", count)?;
+            }
+            Self::ShowEntity { id, uuid, name } => {
+                map.serialize_entry("id", id)?;
+                map.serialize_entry("uuid", &uuid_ints(*uuid))?;
+                if let Some(name) = name {
+                    map.serialize_entry("name", name)?;
+                }
+            }
+        }
+        map.end()
+    }
+}
+
+/// Splits a UUID into four ints, most significant first, the way vanilla
+/// stores UUIDs in NBT and JSON.
+fn uuid_ints(uuid: Uuid) -> [i32; 4] {
+    let bits = uuid.as_u128();
+    [
+        (bits >> 96) as i32,
+        (bits >> 64) as i32,
+        (bits >> 32) as i32,
+        bits as i32,
+    ]
+}
+
+/// Writes the text without any styling, for places that only show plain
+/// strings such as the legacy server list.
+///
+/// Translations and keybinds are resolved by the client, so they are written
+/// as their fallback or key instead, and scores are left out.
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.content {
+            Content::Literal(text) => f.write_str(text)?,
+            Content::Translatable { key, fallback, .. } => f.write_str(fallback.unwrap_or(key))?,
+            Content::Keybind(key) => f.write_str(key)?,
+            Content::Score { .. } => {}
+        }
+        self.extra
+            .iter()
+            .try_for_each(|child| fmt::Display::fmt(child, f))
+    }
+}
+
+impl Serialize for Text<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Score<'a> {
+            name: &'a str,
+            objective: &'a str,
+        }
+
+        impl Serialize for Score<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("name", self.name)?;
+                map.serialize_entry("objective", self.objective)?;
+                map.end()
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        match self.content {
+            Content::Literal(text) => map.serialize_entry("text", text)?,
+            Content::Translatable {
+                key,
+                fallback,
+                with,
+            } => {
+                map.serialize_entry("translate", key)?;
+                if let Some(fallback) = fallback {
+                    map.serialize_entry("fallback", fallback)?;
+                }
+                if !with.is_empty() {
+                    map.serialize_entry("with", with)?;
+                }
+            }
+            Content::Keybind(key) => map.serialize_entry("keybind", key)?,
+            Content::Score { name, objective } => {
+                map.serialize_entry("score", &Score { name, objective })?
+            }
+        }
+        self.style.serialize_entries(&mut map)?;
+        if !self.extra.is_empty() {
+            map.serialize_entry("extra", self.extra)?;
+        }
+        map.end()
+    }
+}
+
+impl Encode for Text<'_> {
+    type Options = ();
+    type Error<E> = NbtWriteError<E>;
+
+    fn encode<W: Write>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        let mut nbt = CompoundWriter::root(writer)?;
+        self.write_nbt(&mut nbt)?;
+        nbt.end()
+    }
+}
+
+impl AsyncEncode for Text<'_> {
+    type Options = ();
+    type Error<E> = NbtWriteError<E>;
+
+    async fn encode<W: AsyncWrite>(
+        &self,
+        writer: &mut W,
+        (): Self::Options,
+    ) -> Result<(), Self::Error<W::Error>> {
+        write_windowed(writer, |window| Encode::encode(self, window, ()))
+            .await
+            .map_err(|e| match e {
+                WindowedError::Write(e) => NbtWriteError::Other(e),
+                // A full window is handled by `write_windowed` and never surfaces here.
+                WindowedError::Encode(e) => e.map_other(|_| unreachable!()),
+            })
+    }
+}
+
+impl WireSize for Text<'_> {
+    type Options = ();
+
+    fn wire_size(&self, (): Self::Options) -> usize {
+        let mut counter = SizeCounter::default();
+        // Components that fail to encode are never sent, so their size doesn't matter.
+        let _ = Encode::encode(self, &mut counter, ());
+        counter.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{format, string::String, vec};
+
+    use minecrevy_encdec::nbt::{Compound, List};
+
+    use super::*;
+    use crate::{json, test_util::encode};
+
+    /// Serializes `value` as JSON, checking that its size is right.
+    fn to_json<T: Serialize>(value: &T) -> String {
+        const MAX_LEN: usize = 1024;
+
+        let mut buf = vec![0; MAX_LEN];
+        let mut writer = &mut buf[..];
+        json::to_writer(&mut writer, value).unwrap();
+        let len = MAX_LEN - writer.len();
+        assert_eq!(json::size(value), len);
+
+        buf.truncate(len);
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Checks that `text` encodes to the same NBT as `tag`.
+    fn assert_nbt(text: Text<'_>, tag: Compound<'_>) {
+        assert_eq!(encode(&text), encode(&Tag::Compound(tag)));
+    }
+
+    /// A UUID whose ints are `[1, 2, 3, -1]`.
+    const UUID: Uuid = Uuid::from_u128(0x00000001_00000002_00000003_ffffffff);
+
+    const TRANSLATABLE: Text = Text::new(Content::Translatable {
+        key: "chat.type.text",
+        fallback: Some("<%s> %s"),
+        with: &[Text::literal("Steve"), Text::literal("hi")],
+    });
+
+    #[test]
+    fn literal_json() {
+        assert_eq!(to_json(&Text::literal("hi")), r#"{"text":"hi"}"#);
+    }
+
+    #[test]
+    fn literal_nbt() {
+        assert_eq!(
+            encode(&Text::literal("hi")),
+            [
+                10, // nameless root compound
+                8, 0, 4, b't', b'e', b'x', b't', 0, 2, b'h', b'i', // "text": "hi"
+                0,
+            ]
+        );
+    }
+
+    #[test]
+    fn translatable() {
+        assert_eq!(
+            to_json(&TRANSLATABLE),
+            r#"{"translate":"chat.type.text","fallback":"<%s> %s","with":[{"text":"Steve"},{"text":"hi"}]}"#
+        );
+        assert_nbt(
+            TRANSLATABLE,
+            &[
+                ("translate", Tag::String("chat.type.text")),
+                ("fallback", Tag::String("<%s> %s")),
+                (
+                    "with",
+                    Tag::List(List::Compound(&[
+                        &[("text", Tag::String("Steve"))],
+                        &[("text", Tag::String("hi"))],
+                    ])),
+                ),
+            ],
+        );
+
+        // Without arguments or a fallback, both are left out.
+        let bare = Text::translatable("menu.quit", &[]);
+        assert_eq!(to_json(&bare), r#"{"translate":"menu.quit"}"#);
+        assert_nbt(bare, &[("translate", Tag::String("menu.quit"))]);
+    }
+
+    #[test]
+    fn keybind_and_score() {
+        let keybind = Text::keybind("key.jump");
+        assert_eq!(to_json(&keybind), r#"{"keybind":"key.jump"}"#);
+        assert_nbt(keybind, &[("keybind", Tag::String("key.jump"))]);
+
+        let score = Text::score("*", "kills");
+        assert_eq!(
+            to_json(&score),
+            r#"{"score":{"name":"*","objective":"kills"}}"#
+        );
+        assert_nbt(
+            score,
+            &[(
+                "score",
+                Tag::Compound(&[
+                    ("name", Tag::String("*")),
+                    ("objective", Tag::String("kills")),
+                ]),
+            )],
+        );
+    }
+
+    #[test]
+    fn colors_and_style() {
+        let rgb = Text::literal("a")
+            .color(Color::Rgb(0x12ab0f))
+            .shadow_color(0xff00_0000)
+            .bold(true)
+            .italic(false)
+            .font("minecraft:uniform")
+            .insertion("b");
+        assert_eq!(
+            to_json(&rgb),
+            r##"{"text":"a","color":"#12ab0f","shadow_color":-16777216,"bold":true,"italic":false,"font":"minecraft:uniform","insertion":"b"}"##
+        );
+        assert_nbt(
+            rgb,
+            &[
+                ("text", Tag::String("a")),
+                ("color", Tag::String("#12ab0f")),
+                ("shadow_color", Tag::Int(-16777216)),
+                ("bold", Tag::Byte(1)),
+                ("italic", Tag::Byte(0)),
+                ("font", Tag::String("minecraft:uniform")),
+                ("insertion", Tag::String("b")),
+            ],
+        );
+
+        let named = Text::literal("a").color(Color::DarkPurple);
+        assert_eq!(to_json(&named), r#"{"text":"a","color":"dark_purple"}"#);
+        assert_nbt(
+            named,
+            &[
+                ("text", Tag::String("a")),
+                ("color", Tag::String("dark_purple")),
+            ],
+        );
+        // Leading zeros are kept.
+        assert_eq!(to_json(&Color::Rgb(0x0000ff)), r##""#0000ff""##);
+    }
+
+    #[test]
+    fn click_events() {
+        let cases: [(ClickEvent, &str, Compound); 5] = [
+            (
+                ClickEvent::OpenUrl("https://a.b"),
+                r#"{"action":"open_url","url":"https://a.b"}"#,
+                &[
+                    ("action", Tag::String("open_url")),
+                    ("url", Tag::String("https://a.b")),
+                ],
+            ),
+            (
+                ClickEvent::RunCommand("/help"),
+                r#"{"action":"run_command","command":"/help"}"#,
+                &[
+                    ("action", Tag::String("run_command")),
+                    ("command", Tag::String("/help")),
+                ],
+            ),
+            (
+                ClickEvent::SuggestCommand("/msg "),
+                r#"{"action":"suggest_command","command":"/msg "}"#,
+                &[
+                    ("action", Tag::String("suggest_command")),
+                    ("command", Tag::String("/msg ")),
+                ],
+            ),
+            (
+                ClickEvent::ChangePage(3),
+                r#"{"action":"change_page","page":3}"#,
+                &[
+                    ("action", Tag::String("change_page")),
+                    ("page", Tag::Int(3)),
+                ],
+            ),
+            (
+                ClickEvent::CopyToClipboard("x"),
+                r#"{"action":"copy_to_clipboard","value":"x"}"#,
+                &[
+                    ("action", Tag::String("copy_to_clipboard")),
+                    ("value", Tag::String("x")),
+                ],
+            ),
+        ];
+        for (event, json, nbt) in cases {
+            assert_eq!(to_json(&event), json);
+            let text = Text::literal("").click_event(event);
+            assert_eq!(
+                to_json(&text),
+                [r#"{"text":"","click_event":"#, json, "}"].concat()
+            );
+            assert_nbt(
+                text,
+                &[
+                    ("text", Tag::String("")),
+                    ("click_event", Tag::Compound(nbt)),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn hover_events() {
+        let name = Text::literal("Pig");
+        let cases: [(HoverEvent, &str, Compound); 4] = [
+            (
+                HoverEvent::ShowText(&name),
+                r#"{"action":"show_text","value":{"text":"Pig"}}"#,
+                &[
+                    ("action", Tag::String("show_text")),
+                    ("value", Tag::Compound(&[("text", Tag::String("Pig"))])),
+                ],
+            ),
+            (
+                HoverEvent::ShowItem {
+                    id: "minecraft:diamond",
+                    count: 2,
+                },
+                r#"{"action":"show_item","id":"minecraft:diamond","count":2}"#,
+                &[
+                    ("action", Tag::String("show_item")),
+                    ("id", Tag::String("minecraft:diamond")),
+                    ("count", Tag::Int(2)),
+                ],
+            ),
+            (
+                HoverEvent::ShowEntity {
+                    id: "minecraft:pig",
+                    uuid: UUID,
+                    name: Some(&name),
+                },
+                r#"{"action":"show_entity","id":"minecraft:pig","uuid":[1,2,3,-1],"name":{"text":"Pig"}}"#,
+                &[
+                    ("action", Tag::String("show_entity")),
+                    ("id", Tag::String("minecraft:pig")),
+                    ("uuid", Tag::IntArray(&[1, 2, 3, -1])),
+                    ("name", Tag::Compound(&[("text", Tag::String("Pig"))])),
+                ],
+            ),
+            (
+                HoverEvent::ShowEntity {
+                    id: "minecraft:pig",
+                    uuid: UUID,
+                    name: None,
+                },
+                r#"{"action":"show_entity","id":"minecraft:pig","uuid":[1,2,3,-1]}"#,
+                &[
+                    ("action", Tag::String("show_entity")),
+                    ("id", Tag::String("minecraft:pig")),
+                    ("uuid", Tag::IntArray(&[1, 2, 3, -1])),
+                ],
+            ),
+        ];
+        for (event, json, nbt) in cases {
+            assert_eq!(to_json(&event), json);
+            let text = Text::literal("").hover_event(event);
+            assert_eq!(
+                to_json(&text),
+                [r#"{"text":"","hover_event":"#, json, "}"].concat()
+            );
+            assert_nbt(
+                text,
+                &[
+                    ("text", Tag::String("")),
+                    ("hover_event", Tag::Compound(nbt)),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn extra() {
+        const TEXT: Text = Text::literal("a").extra(&[
+            Text::literal("b").bold(true),
+            Text::keybind("key.jump").extra(&[Text::literal("c")]),
+        ]);
+        let text = TEXT;
+        assert_eq!(
+            to_json(&text),
+            r#"{"text":"a","extra":[{"text":"b","bold":true},{"keybind":"key.jump","extra":[{"text":"c"}]}]}"#
+        );
+        assert_nbt(
+            text,
+            &[
+                ("text", Tag::String("a")),
+                (
+                    "extra",
+                    Tag::List(List::Compound(&[
+                        &[("text", Tag::String("b")), ("bold", Tag::Byte(1))],
+                        &[
+                            ("keybind", Tag::String("key.jump")),
+                            (
+                                "extra",
+                                Tag::List(List::Compound(&[&[("text", Tag::String("c"))]])),
+                            ),
+                        ],
+                    ])),
+                ),
+            ],
+        );
+        assert_eq!(format!("{text}"), "abkey.jumpc");
+    }
+
+    #[test]
+    fn wire_size_spans_many_windows() {
+        // Longer than a window, so the async encoder takes several passes,
+        // and `encode` checks them against the blocking one and the size.
+        let long = ["x"; 100].concat();
+        let extra = [Text::literal(&long).color(Color::Rgb(0xabcdef)); 4];
+        let bytes = encode(&TRANSLATABLE.extra(&extra));
+        assert!(bytes.len() > 4 * 100);
+    }
+}
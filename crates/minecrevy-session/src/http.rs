@@ -0,0 +1,302 @@
+//! A minimal HTTP/1.1 client, just enough to query a session server.
+//!
+//! It sends one `GET` request per connection and reads the whole response
+//! into a caller-supplied buffer, understanding `Content-Length` and chunked
+//! bodies.
+
+use core::str;
+
+use defmt::Format;
+use embedded_byteorder::{AsyncRead, AsyncWrite};
+use thiserror::Error;
+
+/// A response whose body borrows from the buffer it was read into.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Response<'b> {
+    pub status: u16,
+    pub body: &'b [u8],
+}
+
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum HttpError<E> {
+    #[error("response too large")]
+    ResponseTooLarge,
+    #[error("malformed response")]
+    Malformed,
+    #[error("unexpected EOF")]
+    UnexpectedEof,
+    #[error("other error: {0}")]
+    Other(#[from] E),
+}
+
+/// Sends a `GET` request for `target` to `host` and reads the response into
+/// `buf`.
+///
+/// The request asks the server to close the connection once it has
+/// responded, so `connection` can't be used for another request.
+pub async fn get<'b, C: AsyncRead + AsyncWrite>(
+    connection: &mut C,
+    host: &str,
+    target: &str,
+    buf: &'b mut [u8],
+) -> Result<Response<'b>, HttpError<C::Error>> {
+    for part in [
+        "GET ",
+        target,
+        " HTTP/1.1\r\nHost: ",
+        host,
+        "\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+    ] {
+        connection.write_all(part.as_bytes()).await?;
+    }
+    connection.flush().await?;
+
+    let mut len = 0;
+    let head_len = loop {
+        if let Some(end) = find(&buf[..len], b"\r\n\r\n") {
+            break end + 4;
+        }
+        match read_more(connection, buf, len).await? {
+            0 => return Err(HttpError::UnexpectedEof),
+            n => len += n,
+        }
+    };
+    let head = parse_head(&buf[..head_len]).ok_or(HttpError::Malformed)?;
+
+    // These never have a body, whatever their headers say.
+    if head.status == 204 || head.status == 304 || head.status / 100 == 1 {
+        return Ok(Response {
+            status: head.status,
+            body: &[],
+        });
+    }
+
+    let body = match head.content_length {
+        Some(content_length) if !head.chunked => {
+            let end = head_len.saturating_add(content_length);
+            if end > buf.len() {
+                return Err(HttpError::ResponseTooLarge);
+            }
+            while len < end {
+                match read_more(connection, buf, len).await? {
+                    0 => return Err(HttpError::UnexpectedEof),
+                    n => len += n,
+                }
+            }
+            &buf[head_len..end]
+        }
+        _ => {
+            // Otherwise the body ends when the server closes the connection.
+            while let n @ 1.. = read_more(connection, buf, len).await? {
+                len += n;
+            }
+            let body = &mut buf[head_len..len];
+            if head.chunked {
+                let body_len = dechunk(body).ok_or(HttpError::Malformed)?;
+                &body[..body_len]
+            } else {
+                body
+            }
+        }
+    };
+
+    Ok(Response {
+        status: head.status,
+        body,
+    })
+}
+
+/// The parts of the status line and headers that we need.
+struct Head {
+    status: u16,
+    content_length: Option<usize>,
+    chunked: bool,
+}
+
+fn parse_head(head: &[u8]) -> Option<Head> {
+    let head = str::from_utf8(head).ok()?;
+    let mut lines = head.split("\r\n");
+
+    // HTTP/1.1 200 OK
+    let mut status_line = lines.next()?.splitn(3, ' ');
+    if !status_line.next()?.starts_with("HTTP/1.") {
+        return None;
+    }
+    let status = status_line.next()?.parse().ok()?;
+
+    let mut content_length = None;
+    let mut chunked = false;
+    for line in lines.filter(|line| !line.is_empty()) {
+        let (name, value) = line.split_once(':')?;
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(value.parse().ok()?);
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        }
+    }
+
+    Some(Head {
+        status,
+        content_length,
+        chunked,
+    })
+}
+
+/// Reads more of the response into `buf` after the first `len` bytes,
+/// returning how many bytes were read, or 0 at the end of the stream.
+async fn read_more<C: AsyncRead>(
+    connection: &mut C,
+    buf: &mut [u8],
+    len: usize,
+) -> Result<usize, HttpError<C::Error>> {
+    let free = &mut buf[len..];
+    if free.is_empty() {
+        return Err(HttpError::ResponseTooLarge);
+    }
+    Ok(connection.read(free).await?)
+}
+
+/// Decodes a chunked body in place, returning its decoded length.
+fn dechunk(body: &mut [u8]) -> Option<usize> {
+    let mut read = 0;
+    let mut written = 0;
+    loop {
+        // <size in hex>[;extensions]\r\n<data>\r\n
+        let line_end = read + find(body.get(read..)?, b"\r\n")?;
+        let size = str::from_utf8(&body[read..line_end]).ok()?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        read = line_end + 2;
+        if size == 0 {
+            // Any trailers that follow are of no interest.
+            return Some(written);
+        }
+
+        let data_end = read.checked_add(size).filter(|&end| end <= body.len())?;
+        body.copy_within(read..data_end, written);
+        written += size;
+        read = data_end + 2;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConnection, block_on};
+
+    fn get<'b>(
+        response: &[u8],
+        buf: &'b mut [u8],
+    ) -> Result<Response<'b>, HttpError<core::convert::Infallible>> {
+        let mut connection = MockConnection::new(response);
+        block_on(super::get(&mut connection, "example.com", "/path", buf))
+    }
+
+    #[test]
+    fn sends_request() {
+        let mut connection = MockConnection::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+        let mut buf = [0; 64];
+        block_on(super::get(
+            &mut connection,
+            "example.com",
+            "/path?q=1",
+            &mut buf,
+        ))
+        .unwrap();
+        assert_eq!(
+            &connection.request[..],
+            b"GET /path?q=1 HTTP/1.1\r\nHost: example.com\r\n\
+              Accept: application/json\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn reads_content_length_body() {
+        let mut buf = [0; 128];
+        let response = get(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n{\"ok\": true}\nignored",
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{\"ok\": true}\n");
+    }
+
+    #[test]
+    fn reads_chunked_body() {
+        let mut buf = [0; 128];
+        let response = get(
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+              5\r\n{\"ok\"\r\n8;ext=1\r\n: true}\n\r\n0\r\nTrailer: x\r\n\r\n",
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{\"ok\": true}\n");
+    }
+
+    #[test]
+    fn reads_body_until_close() {
+        let mut buf = [0; 128];
+        let response = get(b"HTTP/1.0 200 OK\r\n\r\nbody", &mut buf).unwrap();
+        assert_eq!(response.body, b"body");
+    }
+
+    #[test]
+    fn ignores_body_of_no_content() {
+        let mut buf = [0; 128];
+        let response = get(
+            b"HTTP/1.1 204 No Content\r\nContent-Length: 4\r\n\r\nbody",
+            &mut buf,
+        )
+        .unwrap();
+        assert_eq!(response.status, 204);
+        assert_eq!(response.body, b"");
+    }
+
+    #[test]
+    fn rejects_truncated_response() {
+        let mut buf = [0; 128];
+        let result = get(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort",
+            &mut buf,
+        );
+        assert_eq!(result, Err(HttpError::UnexpectedEof));
+
+        let result = get(b"HTTP/1.1 200 OK\r\nContent-", &mut buf);
+        assert_eq!(result, Err(HttpError::UnexpectedEof));
+    }
+
+    #[test]
+    fn rejects_oversized_response() {
+        let mut buf = [0; 64];
+        let result = get(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n{}",
+            &mut buf,
+        );
+        assert_eq!(result, Err(HttpError::ResponseTooLarge));
+
+        let result = get(&[b'x'; 100], &mut buf);
+        assert_eq!(result, Err(HttpError::ResponseTooLarge));
+    }
+
+    #[test]
+    fn rejects_malformed_response() {
+        let mut buf = [0; 128];
+        let result = get(b"SSH-2.0-OpenSSH\r\n\r\n", &mut buf);
+        assert_eq!(result, Err(HttpError::Malformed));
+
+        let result = get(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n",
+            &mut buf,
+        );
+        assert_eq!(result, Err(HttpError::Malformed));
+    }
+}
@@ -0,0 +1,220 @@
+//! Authentication of online-mode players against a session server, the
+//! service that checks a player really owns the account they log in with.
+
+#![no_std]
+
+pub mod http;
+#[cfg(test)]
+mod mock;
+mod transport;
+
+use core::fmt::{self, Write as _};
+
+use defmt::Format;
+use heapless::{String, Vec};
+use minecrevy_crypto::ServerHash;
+use minecrevy_protocol::r770::ProfileProperty;
+use serde::Deserialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+use self::http::HttpError;
+pub use self::transport::*;
+
+/// The maximum number of properties in a [`Profile`]. Mojang only sends
+/// `textures`.
+pub const MAX_PROFILE_PROPERTIES: usize = 4;
+
+/// The longest request target we send: the path, a 16-character name whose
+/// characters are all percent-encoded 4-byte sequences, and a [`ServerHash`].
+const MAX_TARGET_LEN: usize = 320;
+
+/// Where to authenticate players.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SessionServer<'a> {
+    /// A domain name or an IP address.
+    pub host: &'a str,
+    pub port: u16,
+}
+
+impl SessionServer<'static> {
+    /// Mojang's session server. It only accepts HTTPS, so it needs a
+    /// [`Transport`] that speaks TLS.
+    pub const MOJANG: Self = Self {
+        host: "sessionserver.mojang.com",
+        port: 443,
+    };
+}
+
+impl SessionServer<'_> {
+    /// Asks whether the player called `username` has told the session server
+    /// that they are joining the server identified by `server_hash`, which
+    /// proves they own the account.
+    ///
+    /// The response is read into `buf`, which the returned profile borrows
+    /// from. A profile with a skin takes about 2 KB, headers included.
+    pub async fn has_joined<'b, T: Transport>(
+        &self,
+        transport: &mut T,
+        username: &str,
+        server_hash: &ServerHash,
+        buf: &'b mut [u8],
+    ) -> Result<Profile<'b>, HasJoinedError<T::ConnectError, T::Error>> {
+        let mut target = String::<MAX_TARGET_LEN>::new();
+        write!(
+            target,
+            "/session/minecraft/hasJoined?username={}&serverId={}",
+            PercentEncoded(username),
+            server_hash,
+        )
+        .map_err(|_| HasJoinedError::UsernameTooLong)?;
+
+        let mut connection = transport
+            .connect(self.host, self.port)
+            .await
+            .map_err(HasJoinedError::Connect)?;
+        let response = http::get(&mut connection, self.host, &target, buf)
+            .await
+            .map_err(HasJoinedError::Http)?;
+
+        match response.status {
+            200 => serde_json_core::from_slice(response.body)
+                .map(|(profile, _)| profile)
+                .map_err(|_| HasJoinedError::InvalidProfile),
+            204 => Err(HasJoinedError::NotJoined),
+            status => Err(HasJoinedError::UnexpectedStatus(status)),
+        }
+    }
+}
+
+/// Error type for [`SessionServer::has_joined`].
+///
+/// `CE` stands for "connect error" and `E` is the connection's I/O error.
+#[derive(Format, Error, Clone, PartialEq, Eq, Debug)]
+pub enum HasJoinedError<CE, E> {
+    #[error("username too long")]
+    UsernameTooLong,
+    #[error("failed to connect to the session server: {0}")]
+    Connect(CE),
+    #[error("failed to query the session server: {0}")]
+    Http(HttpError<E>),
+    #[error("player has not joined")]
+    NotJoined,
+    #[error("unexpected status code {0}")]
+    UnexpectedStatus(u16),
+    #[error("invalid profile")]
+    InvalidProfile,
+}
+
+/// An authenticated player's profile.
+///
+/// Strings are borrowed as they appear in the JSON, so escape sequences are
+/// left in. The session server doesn't escape anything it sends.
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Profile<'a> {
+    pub id: Uuid,
+    pub name: &'a str,
+    #[serde(borrow, default)]
+    pub properties: Vec<ProfileProperty<'a>, MAX_PROFILE_PROPERTIES>,
+}
+
+/// Percent-encodes every byte that isn't unreserved in a URI.
+struct PercentEncoded<'a>(&'a str);
+
+impl fmt::Display for PercentEncoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                f.write_char(char::from(byte))?;
+            } else {
+                write!(f, "%{byte:02X}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::*;
+    use crate::mock::{MockTransport, block_on};
+
+    const SERVER: SessionServer<'static> = SessionServer {
+        host: "localhost",
+        port: 8080,
+    };
+
+    fn has_joined<'b>(
+        response: &[u8],
+        buf: &'b mut [u8],
+    ) -> Result<Profile<'b>, HasJoinedError<Infallible, Infallible>> {
+        let mut transport = MockTransport { response };
+        let server_hash = ServerHash::new("Notch", &[], &[]);
+        block_on(SERVER.has_joined(&mut transport, "Notch", &server_hash, buf))
+    }
+
+    /// The base64 `textures` of a profile with a skin, as Mojang sends it.
+    const TEXTURES: &str = "ewogICJ0aW1lc3RhbXAiOiAxNzYwNjU5MjAwMDAwLAogICJwcm9maWxlSWQiOiAiMDY5YTc5ZjQ0NGU5NDcyNmE1YmVmY2E5MGUzOGFhZjUiLAogICJwcm9maWxlTmFtZSI6ICJOb3RjaCIsCiAgInRleHR1cmVzIjogewogICAgIlNLSU4iOiB7CiAgICAgICJ1cmwiOiAiaHR0cDovL3RleHR1cmVzLm1pbmVjcmFmdC5uZXQvdGV4dHVyZS8yOTIwMDlhNDkyNWI1OGYwMmM3N2RhZGMzZWNlZjA3ZWE0Yzc0NzJmNjRlMGZkYzMyY2U1NTIyNDg5MzYyNjgwIgogICAgfQogIH0KfQ==";
+
+    /// A `hasJoined` response, formatted the way Mojang's session server
+    /// formats it.
+    const PROFILE_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\n\
+        Content-Type: application/json; charset=utf-8\r\n\
+        Content-Length: 557\r\n\
+        \r\n\
+        {\n  \"id\" : \"069a79f444e94726a5befca90e38aaf5\",\n  \"name\" : \"Notch\",\n  \
+        \"properties\" : [ {\n    \"name\" : \"textures\",\n    \"value\" : \"ewogICJ0aW1lc3RhbXAiOiAxNzYwNjU5MjAwMDAwLAogICJwcm9maWxlSWQiOiAiMDY5YTc5ZjQ0NGU5NDcyNmE1YmVmY2E5MGUzOGFhZjUiLAogICJwcm9maWxlTmFtZSI6ICJOb3RjaCIsCiAgInRleHR1cmVzIjogewogICAgIlNLSU4iOiB7CiAgICAgICJ1cmwiOiAiaHR0cDovL3RleHR1cmVzLm1pbmVjcmFmdC5uZXQvdGV4dHVyZS8yOTIwMDlhNDkyNWI1OGYwMmM3N2RhZGMzZWNlZjA3ZWE0Yzc0NzJmNjRlMGZkYzMyY2U1NTIyNDg5MzYyNjgwIgogICAgfQogIH0KfQ==\",\n    \
+        \"signature\" : \"c2lnbmF0dXJl\"\n  } ],\n  \"profileActions\" : [ ]\n}";
+
+    #[test]
+    fn parses_profile() {
+        let mut buf = [0; 1024];
+        let profile = has_joined(PROFILE_RESPONSE, &mut buf).unwrap();
+        assert_eq!(
+            profile.id,
+            Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5)
+        );
+        assert_eq!(profile.name, "Notch");
+        assert_eq!(
+            &profile.properties[..],
+            &[ProfileProperty {
+                name: "textures",
+                value: TEXTURES,
+                signature: Some("c2lnbmF0dXJl"),
+            }]
+        );
+    }
+
+    #[test]
+    fn not_joined() {
+        let mut buf = [0; 1024];
+        let result = has_joined(b"HTTP/1.1 204 No Content\r\n\r\n", &mut buf);
+        assert_eq!(result, Err(HasJoinedError::NotJoined));
+    }
+
+    #[test]
+    fn unexpected_status() {
+        let mut buf = [0; 1024];
+        let result = has_joined(
+            b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n",
+            &mut buf,
+        );
+        assert_eq!(result, Err(HasJoinedError::UnexpectedStatus(403)));
+    }
+
+    #[test]
+    fn invalid_profile() {
+        let mut buf = [0; 1024];
+        let result = has_joined(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]", &mut buf);
+        assert_eq!(result, Err(HasJoinedError::InvalidProfile));
+    }
+
+    #[test]
+    fn percent_encodes_username() {
+        let mut target = String::<64>::new();
+        write!(target, "{}", PercentEncoded("a b&c~ä")).unwrap();
+        assert_eq!(target, "a%20b%26c~%C3%A4");
+    }
+}
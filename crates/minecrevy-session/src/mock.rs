@@ -0,0 +1,84 @@
+//! An in-memory stand-in for a session server, for tests.
+
+use core::{
+    convert::Infallible,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use embedded_byteorder::{AsyncRead, AsyncWrite, ErrorType};
+use heapless::Vec;
+
+use crate::Transport;
+
+/// A connection that answers with a canned `response`, handing it out at
+/// most `chunk` bytes per read to exercise partial reads.
+pub struct MockConnection<'a> {
+    pub response: &'a [u8],
+    pub chunk: usize,
+    pub request: Vec<u8, 512>,
+}
+
+impl<'a> MockConnection<'a> {
+    pub fn new(response: &'a [u8]) -> Self {
+        Self {
+            response,
+            chunk: 7,
+            request: Vec::new(),
+        }
+    }
+}
+
+impl ErrorType for MockConnection<'_> {
+    type Error = Infallible;
+}
+
+impl AsyncRead for MockConnection<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        let n = buf.len().min(self.chunk).min(self.response.len());
+        let (read, rest) = self.response.split_at(n);
+        buf[..n].copy_from_slice(read);
+        self.response = rest;
+        Ok(n)
+    }
+}
+
+impl AsyncWrite for MockConnection<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.request.extend_from_slice(buf).unwrap();
+        Ok(buf.len())
+    }
+}
+
+/// A transport whose every connection answers with the same `response`.
+pub struct MockTransport<'a> {
+    pub response: &'a [u8],
+}
+
+impl Transport for MockTransport<'_> {
+    type ConnectError = Infallible;
+    type Error = Infallible;
+    type Connection<'t>
+        = MockConnection<'t>
+    where
+        Self: 't;
+
+    async fn connect(
+        &mut self,
+        _host: &str,
+        _port: u16,
+    ) -> Result<Self::Connection<'_>, Self::ConnectError> {
+        Ok(MockConnection::new(self.response))
+    }
+}
+
+/// Runs a future that never waits on anything outside of memory.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
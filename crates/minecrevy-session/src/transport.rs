@@ -0,0 +1,25 @@
+#![expect(async_fn_in_trait)]
+
+use embedded_byteorder::{AsyncRead, AsyncWrite};
+
+/// A way to open connections to a session server.
+///
+/// Implement this for whatever network stack the server runs on. Wrapping
+/// the connection in TLS is up to the implementation too, since the HTTP
+/// client itself only speaks plain text.
+pub trait Transport {
+    /// The error returned when a connection can't be opened.
+    type ConnectError;
+    /// The error returned when reading from or writing to a connection fails.
+    type Error;
+    type Connection<'t>: AsyncRead<Error = Self::Error> + AsyncWrite<Error = Self::Error>
+    where
+        Self: 't;
+
+    /// Connects to `host`, which is a domain name or an IP address, on `port`.
+    async fn connect(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<Self::Connection<'_>, Self::ConnectError>;
+}